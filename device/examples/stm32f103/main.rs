@@ -181,8 +181,8 @@ const APP: () = {
                 // trigger timer so that it can set itself up
                 rtfm::pend(Interrupt::TIM2);
             }
-            LoRaWanResponse::Error => {
-                // write!(ctx.resources.debug_uart, "LoRaWanResponse::Error!!\r\n").unwrap();
+            LoRaWanResponse::Error(_err) => {
+                // write!(ctx.resources.debug_uart, "LoRaWanResponse::Error {:?}\r\n", _err).unwrap();
             }
         }
     }
@@ -197,7 +197,9 @@ const APP: () = {
         let data: [u8; 5] = [0xDE, 0xAD, 0xBE, 0xEF, *ctx.resources.count];
         *ctx.resources.count += 1;
 
-        lorawan.send(sx12xx, &data, 1, false);
+        if let Err(_err) = lorawan.send(sx12xx, &data, 1, false) {
+            // write!(debug, "Send failed {:?}\r\n", _err).unwrap();
+        }
     }
 
     // #[task(binds = USART2, priority=1, resources = [uart_rx], spawn = [send_ping])]
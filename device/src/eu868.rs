@@ -17,6 +17,9 @@ const ADR_ACK_LIMIT: usize = 64;
 const ADR_ACK_DELAY: usize = 32;
 const ACK_TIMEOUT: usize = 2; // random delay between 1 and 3 seconds
 
+// uplinks are always sent with SF10/125kHz
+const MAX_PAYLOAD_LENGTH_DR2: usize = 51;
+
 pub struct Configuration {
     subband: Option<u8>,
     last_join: u8,
@@ -33,23 +36,29 @@ impl Configuration {
         self.subband = Some(subband);
     }
 
-    pub fn get_join_frequency(&mut self, random: u8) -> u32 {
-        let subband = if let Some(subband) = &self.subband {
-            subband - 1
+    fn get_channel(&self, random: u8) -> Option<u8> {
+        let subband = if let Some(subband) = self.subband {
+            subband.checked_sub(1)?
         } else {
-            (random >> 3) & 0b111
+            ((random >> 3) & 0b111) % UPLINK_CHANNEL_MAP.len() as u8
         };
-        self.last_join = subband;
-        UPLINK_CHANNEL_MAP[subband as usize]
+        if (subband as usize) < UPLINK_CHANNEL_MAP.len() {
+            Some(subband)
+        } else {
+            None
+        }
     }
 
-    pub fn get_data_frequency(&mut self, random: u8) -> u32 {
-        let subband = if let Some(subband) = &self.subband {
-            subband - 1
-        } else {
-            (random >> 3) & 0b111
-        };
-        UPLINK_CHANNEL_MAP[subband as usize]
+    pub fn get_join_frequency(&mut self, random: u8) -> Option<u32> {
+        let subband = self.get_channel(random)?;
+        // join accepts are only expected on the downlink channels
+        self.last_join = subband % DOWNLINK_CHANNEL_MAP.len() as u8;
+        Some(UPLINK_CHANNEL_MAP[subband as usize])
+    }
+
+    pub fn get_data_frequency(&mut self, random: u8) -> Option<u32> {
+        let subband = self.get_channel(random)?;
+        Some(UPLINK_CHANNEL_MAP[subband as usize])
     }
 
     pub fn get_join_accept_frequency1(&mut self) -> u32 {
@@ -63,4 +72,8 @@ impl Configuration {
     pub fn get_join_accept_delay2(&mut self) -> usize {
         JOIN_ACCEPT_DELAY2
     }
+
+    pub fn get_max_payload_length(&self) -> usize {
+        MAX_PAYLOAD_LENGTH_DR2
+    }
 }
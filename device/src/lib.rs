@@ -27,13 +27,26 @@ use us915::Configuration as RegionalConfiguration;
 type DevNonce = lorawan_encoding::parser::DevNonce<[u8; 2]>;
 type Confirmed = bool;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Event {
     StartJoin, // user issued command to start a join process
     TxComplete,
     RxComplete(radio::RxQuality),
     TimerFired,
     SendData(Confirmed),
+    TxTimeout,
+    RxError,
+}
+
+/// The states of the device state machine, used to report where an error happened.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DeviceState {
+    NotJoined,
+    JoinSent,
+    WaitingJoinDelay1,
+    WaitingJoinAccept1,
+    JoinedIdle,
+    JoinedSending,
 }
 
 type JoinAttempts = usize;
@@ -75,7 +88,30 @@ struct Session {
 #[derive(Debug)]
 pub enum Response {
     TimerRequest(usize),
-    Error,
+    Error(Error),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Error {
+    /// The event can not be handled in the current state. The state is left unchanged, except
+    /// while waiting for the join accept, where the join is aborted.
+    UnexpectedEvent(DeviceState, Event),
+    /// The radio did not complete the transmission.
+    RadioTxTimeout,
+    /// The radio failed to receive a packet in the receive window.
+    RadioRxError,
+    /// The payload does not fit in a frame at the current data rate.
+    PayloadTooLong,
+    /// Data can only be sent once the device has joined.
+    NotJoined,
+    /// The MIC of the received join accept did not match.
+    JoinAcceptMicMismatch,
+    /// The received join accept could not be decrypted.
+    InvalidJoinAccept,
+    /// The region configuration does not provide a channel to transmit on.
+    NoFreeChannel,
+    /// The frame could not be built.
+    Encoding(lorawan_encoding::error::Error),
 }
 
 impl<R: Radio, E> Device<R, E> {
@@ -108,24 +144,37 @@ impl<R: Radio, E> Device<R, E> {
         data: &[u8],
         fport: u8,
         confirmed: bool,
-    ) {
+    ) -> Result<(), Error> {
         if let Data::Session(session) = &mut self.sm_data {
+            if data.len() > self.region.get_max_payload_length() {
+                return Err(Error::PayloadTooLong);
+            }
             let mut phy = DataPayloadCreator::new();
             phy.set_confirmed(confirmed)
                 .set_f_port(fport)
                 .set_dev_addr(session.devaddr)
                 .set_fcnt(session.fcnt);
 
-            match phy.build(&data, &[], &session.newskey, &session.appskey) {
-                Ok(packet) => {
-                    session.fcnt += 1;
-                    let buffer = radio.get_mut_buffer();
-                    buffer.extend(packet);
-
-                    (self.sm_handler)(self, radio, Event::SendData(confirmed));
-                }
-                Err(_output) => {}
+            let packet = phy
+                .build(&data, &[], &session.newskey, &session.appskey)
+                .map_err(Error::Encoding)?;
+            let buffer = radio.get_mut_buffer();
+            buffer.clear();
+            buffer.extend(packet);
+
+            if let Some(Response::Error(err)) =
+                (self.sm_handler)(self, radio, Event::SendData(confirmed))
+            {
+                // the frame was not sent, it must not be sent along with the next one
+                radio.get_mut_buffer().clear();
+                return Err(err);
+            }
+            if let Data::Session(session) = &mut self.sm_data {
+                session.fcnt += 1;
             }
+            Ok(())
+        } else {
+            Err(Error::NotJoined)
         }
     }
 
@@ -152,24 +201,28 @@ impl<R: Radio, E> Device<R, E> {
         devnonce_ret
     }
 
-    fn send_join_request(&mut self, radio: &mut dyn Radio<Event = E>) -> DevNonce {
+    fn send_join_request(&mut self, radio: &mut dyn Radio<Event = E>) -> Result<DevNonce, Error> {
+        let mut random = (self.get_random)();
+        // use lowest 16 bits for devnonce
+        let devnonce = random as u16;
+        // we'll use the rest for frequency and subband selection
+        random >>= 16;
+        let frequency = self
+            .region
+            .get_join_frequency(random as u8)
+            .ok_or(Error::NoFreeChannel)?;
+
         radio.configure_tx(
             14,
             Bandwidth::_125KHZ,
             SpreadingFactor::_10,
             CodingRate::_4_5,
         );
-
-        let mut random = (self.get_random)();
-        // use lowest 16 bits for devnonce
-        let devnonce = random as u16;
-        // we'll use the rest for frequency and subband selection
-        random >>= 16;
-        radio.set_frequency(self.region.get_join_frequency(random as u8));
+        radio.set_frequency(frequency);
         // prepares the buffer
         let devnonce = self.create_join_request(radio.get_mut_buffer(), devnonce);
         radio.send_buffer();
-        devnonce
+        Ok(devnonce)
     }
 
     fn set_join_accept_rx(&mut self, radio: &mut dyn Radio<Event = E>) {
@@ -190,8 +243,8 @@ impl<R: Radio, E> Device<R, E> {
             radio::State::Busy => None,
             radio::State::TxDone => self.handle_event(radio, Event::TxComplete),
             radio::State::RxDone(quality) => self.handle_event(radio, Event::RxComplete(quality)),
-            radio::State::TxError => None,
-            radio::State::RxError => None,
+            radio::State::TxError => self.handle_event(radio, Event::TxTimeout),
+            radio::State::RxError => self.handle_event(radio, Event::RxError),
         }
     }
 
@@ -204,24 +257,38 @@ impl<R: Radio, E> Device<R, E> {
     }
 
     // BELOW HERE ARE PRIVATE STATE MACHINE HANDLERS
-    fn error(&mut self, _radio: &mut dyn Radio<Event = E>, _event: Event) -> Option<Response> {
-        // can do a richer implementation later
-        Some(Response::Error)
+    fn error(&mut self, state: DeviceState, event: Event) -> Option<Response> {
+        Some(Response::Error(Error::UnexpectedEvent(state, event)))
+    }
+
+    // aborts the ongoing operation, puts the radio in standby and goes back to the idle state
+    // matching the session data
+    fn recover(&mut self, radio: &mut dyn Radio<Event = E>, error: Error) -> Option<Response> {
+        radio.set_standby();
+        self.sm_handler = match self.sm_data {
+            Data::NoSession(_, _) => Device::not_joined,
+            Data::Session(_) => Device::joined_idle,
+        };
+        Some(Response::Error(error))
     }
 
     fn not_joined(&mut self, radio: &mut dyn Radio<Event = E>, event: Event) -> Option<Response> {
         match event {
             Event::StartJoin => {
                 if let Data::NoSession(attempts, _) = self.sm_data {
-                    self.sm_handler = Device::join_sent;
-                    let devnonce = self.send_join_request(radio);
-                    self.sm_data = Data::NoSession(attempts + 1, devnonce);
-                    None
+                    match self.send_join_request(radio) {
+                        Ok(devnonce) => {
+                            self.sm_handler = Device::join_sent;
+                            self.sm_data = Data::NoSession(attempts + 1, devnonce);
+                            None
+                        }
+                        Err(err) => Some(Response::Error(err)),
+                    }
                 } else {
-                    self.error(radio, event)
+                    self.error(DeviceState::NotJoined, event)
                 }
             }
-            _ => self.error(radio, event),
+            _ => self.error(DeviceState::NotJoined, event),
         }
     }
 
@@ -234,7 +301,8 @@ impl<R: Radio, E> Device<R, E> {
                     self.region.get_join_accept_delay1() * 1000 - 150,
                 ))
             }
-            _ => self.error(radio, event),
+            Event::TxTimeout => self.recover(radio, Error::RadioTxTimeout),
+            _ => self.error(DeviceState::JoinSent, event),
         }
     }
 
@@ -249,7 +317,10 @@ impl<R: Radio, E> Device<R, E> {
                 self.set_join_accept_rx(radio);
                 None
             }
-            _ => self.error(radio, event),
+            _ => self.recover(
+                radio,
+                Error::UnexpectedEvent(DeviceState::WaitingJoinDelay1, event),
+            ),
         }
     }
 
@@ -261,42 +332,46 @@ impl<R: Radio, E> Device<R, E> {
         match event {
             Event::RxComplete(_quality) => {
                 if let Data::NoSession(_, devnonce) = self.sm_data {
-                    let packet = lorawan_parse(radio.get_received_packet()).unwrap();
-
-                    if let PhyPayload::JoinAccept(join_accept) = packet {
-                        if let JoinAcceptPayload::Encrypted(encrypted) = join_accept {
-                            let decrypt = encrypted.decrypt(&self.credentials.appkey);
-                            if decrypt.validate_mic(&self.credentials.appkey) {
-                                let session = Session {
-                                    newskey: decrypt
-                                        .derive_newskey(&devnonce, &self.credentials.appkey),
-                                    appskey: decrypt
-                                        .derive_appskey(&devnonce, &self.credentials.appkey),
-                                    devaddr: DevAddr::new([
-                                        decrypt.dev_addr().as_ref()[0],
-                                        decrypt.dev_addr().as_ref()[1],
-                                        decrypt.dev_addr().as_ref()[2],
-                                        decrypt.dev_addr().as_ref()[3],
-                                    ])
-                                    .unwrap(),
-                                    fcnt: 0,
-                                };
-                                self.sm_handler = Device::joined_idle;
-                                self.sm_data = Data::Session(session);
-                            } else {
-                            }
-                        } else {
-                            panic!("Cannot possibly be decrypted already")
+                    let packet = lorawan_parse(radio.get_received_packet());
+
+                    let encrypted = match packet {
+                        Ok(PhyPayload::JoinAccept(JoinAcceptPayload::Encrypted(encrypted))) => {
+                            encrypted
                         }
-                    } else {
-                        // just some other packet, ignore
+                        // the receive window is over, without a join accept
+                        _ => return self.recover(radio, Error::InvalidJoinAccept),
+                    };
+                    let decrypt = encrypted.decrypt(&self.credentials.appkey);
+                    if !decrypt.validate_mic(&self.credentials.appkey) {
+                        return self.recover(radio, Error::JoinAcceptMicMismatch);
                     }
+                    let session = Session {
+                        newskey: decrypt.derive_newskey(&devnonce, &self.credentials.appkey),
+                        appskey: decrypt.derive_appskey(&devnonce, &self.credentials.appkey),
+                        devaddr: DevAddr::new([
+                            decrypt.dev_addr().as_ref()[0],
+                            decrypt.dev_addr().as_ref()[1],
+                            decrypt.dev_addr().as_ref()[2],
+                            decrypt.dev_addr().as_ref()[3],
+                        ])
+                        .unwrap(),
+                        fcnt: 0,
+                    };
+                    self.sm_handler = Device::joined_idle;
+                    self.sm_data = Data::Session(session);
                     None
                 } else {
-                    self.error(radio, event)
+                    self.recover(
+                        radio,
+                        Error::UnexpectedEvent(DeviceState::WaitingJoinAccept1, event),
+                    )
                 }
             }
-            _ => self.error(radio, event),
+            Event::RxError => self.recover(radio, Error::RadioRxError),
+            _ => self.recover(
+                radio,
+                Error::UnexpectedEvent(DeviceState::WaitingJoinAccept1, event),
+            ),
         }
     }
 
@@ -311,16 +386,20 @@ impl<R: Radio, E> Device<R, E> {
                         CodingRate::_4_5,
                     );
                     let random = (self.get_random)();
-                    radio.set_frequency(self.region.get_data_frequency(random as u8));
-                    radio.send_buffer();
-                    self.sm_handler = Device::joined_sending;
+                    if let Some(frequency) = self.region.get_data_frequency(random as u8) {
+                        radio.set_frequency(frequency);
+                        radio.send_buffer();
+                        self.sm_handler = Device::joined_sending;
 
-                    None
+                        None
+                    } else {
+                        Some(Response::Error(Error::NoFreeChannel))
+                    }
                 }
-                _ => self.error(radio, event),
+                _ => self.error(DeviceState::JoinedIdle, event),
             }
         } else {
-            self.error(radio, event)
+            self.recover(radio, Error::NotJoined)
         }
     }
 
@@ -334,7 +413,8 @@ impl<R: Radio, E> Device<R, E> {
                 self.sm_handler = Device::joined_idle;
                 None
             }
-            _ => self.error(radio, event),
+            Event::TxTimeout => self.recover(radio, Error::RadioTxTimeout),
+            _ => self.error(DeviceState::JoinedSending, event),
         }
     }
 }
@@ -23,12 +23,18 @@ pub enum CodingRate {
     _4_8,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct RxQuality {
     rssi: i16,
     snr: i8,
 }
 
+impl RxQuality {
+    pub fn new(rssi: i16, snr: i8) -> RxQuality {
+        RxQuality { rssi, snr }
+    }
+}
+
 pub enum State {
     Busy,
     TxDone,
@@ -58,6 +64,7 @@ pub trait Radio {
         coderate: CodingRate,
     );
     fn set_rx(&mut self);
+    fn set_standby(&mut self);
     fn handle_event(&mut self, event: Self::Event) -> State;
 }
 
@@ -113,6 +120,10 @@ impl Radio for sx12xx::Sx12xx {
         self.set_rx();
     }
 
+    fn set_standby(&mut self) {
+        self.set_standby();
+    }
+
     fn handle_event(&mut self, event: Self::Event) -> State {
         self.handle_event(event).into()
     }
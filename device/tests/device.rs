@@ -0,0 +1,276 @@
+use heapless::consts::*;
+use heapless::Vec;
+use lorawan_device::radio::{Bandwidth, CodingRate, RxQuality, SpreadingFactor, State};
+use lorawan_device::{Device, DeviceState, Error, Event, Radio, Response};
+use lorawan_encoding::creator::DataPayloadCreator;
+use lorawan_encoding::keys::AES128;
+
+#[derive(Default)]
+struct MockRadio {
+    buffer: Vec<u8, U256>,
+    received: Vec<u8, U256>,
+    calls: std::vec::Vec<&'static str>,
+}
+
+impl Radio for MockRadio {
+    type Event = State;
+
+    fn send(&mut self, _buffer: &[u8]) {
+        self.calls.push("send");
+    }
+
+    fn send_buffer(&mut self) {
+        self.calls.push("send_buffer");
+    }
+
+    fn set_frequency(&mut self, _frequency_mhz: u32) {}
+
+    fn get_mut_buffer(&mut self) -> &mut Vec<u8, U256> {
+        &mut self.buffer
+    }
+
+    fn get_received_packet(&mut self) -> &mut Vec<u8, U256> {
+        &mut self.received
+    }
+
+    fn configure_tx(
+        &mut self,
+        _power: i8,
+        _bandwidth: Bandwidth,
+        _datarate: SpreadingFactor,
+        _coderate: CodingRate,
+    ) {
+    }
+
+    fn configure_rx(
+        &mut self,
+        _bandwidth: Bandwidth,
+        _spreading_factor: SpreadingFactor,
+        _coderate: CodingRate,
+    ) {
+    }
+
+    fn set_rx(&mut self) {
+        self.calls.push("set_rx");
+    }
+
+    fn set_standby(&mut self) {
+        self.calls.push("set_standby");
+    }
+
+    fn handle_event(&mut self, event: Self::Event) -> State {
+        event
+    }
+}
+
+impl MockRadio {
+    fn receive(&mut self, packet: &[u8]) {
+        self.received = Vec::new();
+        self.received.extend_from_slice(packet).unwrap();
+    }
+
+    fn last_call(&self) -> Option<&'static str> {
+        self.calls.last().copied()
+    }
+}
+
+fn app_key() -> [u8; 16] {
+    [
+        0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee,
+        0xff,
+    ]
+}
+
+fn join_accept() -> [u8; 17] {
+    [
+        0x20, 0x49, 0x3e, 0xeb, 0x51, 0xfb, 0xa2, 0x11, 0x6f, 0x81, 0x0e, 0xdb, 0x37, 0x42, 0x97,
+        0x51, 0x42,
+    ]
+}
+
+fn random() -> u32 {
+    0
+}
+
+fn new_device() -> Device<MockRadio, State> {
+    Device::new([1; 8], [2; 8], app_key(), random)
+}
+
+fn rx_complete() -> Event {
+    Event::RxComplete(RxQuality::new(-80, 5))
+}
+
+// drives the device up to the point where it listens for the join accept
+fn open_join_accept_window(device: &mut Device<MockRadio, State>, radio: &mut MockRadio) {
+    assert!(device.handle_event(radio, Event::StartJoin).is_none());
+    match device.handle_event(radio, Event::TxComplete) {
+        Some(Response::TimerRequest(_)) => (),
+        other => panic!("unexpected response: {:?}", other),
+    }
+    assert!(device.handle_event(radio, Event::TimerFired).is_none());
+    assert_eq!(radio.last_call(), Some("set_rx"));
+}
+
+fn join(device: &mut Device<MockRadio, State>, radio: &mut MockRadio) {
+    open_join_accept_window(device, radio);
+    radio.receive(&join_accept());
+    assert!(device.handle_event(radio, rx_complete()).is_none());
+}
+
+fn assert_recovered(response: Option<Response>, radio: &MockRadio, expected: Error) {
+    match response {
+        Some(Response::Error(err)) => assert_eq!(err, expected),
+        other => panic!("unexpected response: {:?}", other),
+    }
+    assert_eq!(radio.last_call(), Some("set_standby"));
+}
+
+#[test]
+fn test_unexpected_event_keeps_state() {
+    let mut radio = MockRadio::default();
+    let mut device = new_device();
+
+    match device.handle_event(&mut radio, Event::TimerFired) {
+        Some(Response::Error(err)) => assert_eq!(
+            err,
+            Error::UnexpectedEvent(DeviceState::NotJoined, Event::TimerFired)
+        ),
+        other => panic!("unexpected response: {:?}", other),
+    }
+    assert!(radio.calls.is_empty());
+    assert!(device.handle_event(&mut radio, Event::StartJoin).is_none());
+}
+
+#[test]
+fn test_join_tx_timeout_recovers() {
+    let mut radio = MockRadio::default();
+    let mut device = new_device();
+    assert!(device.handle_event(&mut radio, Event::StartJoin).is_none());
+
+    let response = device.handle_radio_event(&mut radio, State::TxError);
+    assert_recovered(response, &radio, Error::RadioTxTimeout);
+
+    assert!(device.handle_event(&mut radio, Event::StartJoin).is_none());
+}
+
+#[test]
+fn test_join_accept_rx_error_recovers() {
+    let mut radio = MockRadio::default();
+    let mut device = new_device();
+    open_join_accept_window(&mut device, &mut radio);
+
+    let response = device.handle_radio_event(&mut radio, State::RxError);
+    assert_recovered(response, &radio, Error::RadioRxError);
+
+    assert!(device.handle_event(&mut radio, Event::StartJoin).is_none());
+}
+
+#[test]
+fn test_join_accept_mic_mismatch_recovers() {
+    let mut radio = MockRadio::default();
+    let mut device = new_device();
+    open_join_accept_window(&mut device, &mut radio);
+
+    let mut packet = join_accept();
+    packet[16] ^= 0xff;
+    radio.receive(&packet);
+    let response = device.handle_event(&mut radio, rx_complete());
+    assert_recovered(response, &radio, Error::JoinAcceptMicMismatch);
+
+    assert!(device.handle_event(&mut radio, Event::StartJoin).is_none());
+}
+
+#[test]
+fn test_data_frame_instead_of_join_accept_recovers() {
+    let mut radio = MockRadio::default();
+    let mut device = new_device();
+    open_join_accept_window(&mut device, &mut radio);
+
+    let mut phy = DataPayloadCreator::new();
+    phy.set_dev_addr(&[1, 2, 3, 4]).set_f_port(1).set_fcnt(1);
+    let packet = phy
+        .build(&[1, 2, 3], &[], &AES128([1; 16]), &AES128([2; 16]))
+        .unwrap()
+        .to_vec();
+    radio.receive(&packet);
+    let response = device.handle_event(&mut radio, rx_complete());
+    assert_recovered(response, &radio, Error::InvalidJoinAccept);
+
+    assert!(device.handle_event(&mut radio, Event::StartJoin).is_none());
+}
+
+#[test]
+fn test_unexpected_event_while_joining_recovers() {
+    let mut radio = MockRadio::default();
+    let mut device = new_device();
+    assert!(device.handle_event(&mut radio, Event::StartJoin).is_none());
+    assert!(device.handle_event(&mut radio, Event::TxComplete).is_some());
+
+    let response = device.handle_event(&mut radio, Event::TxComplete);
+    assert_recovered(
+        response,
+        &radio,
+        Error::UnexpectedEvent(DeviceState::WaitingJoinDelay1, Event::TxComplete),
+    );
+    open_join_accept_window(&mut device, &mut radio);
+
+    let response = device.handle_event(&mut radio, Event::TimerFired);
+    assert_recovered(
+        response,
+        &radio,
+        Error::UnexpectedEvent(DeviceState::WaitingJoinAccept1, Event::TimerFired),
+    );
+    assert!(device.handle_event(&mut radio, Event::StartJoin).is_none());
+}
+
+#[test]
+fn test_send_before_join() {
+    let mut radio = MockRadio::default();
+    let mut device = new_device();
+
+    assert_eq!(
+        device.send(&mut radio, &[1, 2, 3], 1, false),
+        Err(Error::NotJoined)
+    );
+    assert!(radio.calls.is_empty());
+}
+
+#[test]
+fn test_data_tx_timeout_recovers() {
+    let mut radio = MockRadio::default();
+    let mut device = new_device();
+    join(&mut device, &mut radio);
+
+    assert!(device.send(&mut radio, &[1, 2, 3], 1, false).is_ok());
+    assert_eq!(radio.last_call(), Some("send_buffer"));
+
+    let response = device.handle_radio_event(&mut radio, State::TxError);
+    assert_recovered(response, &radio, Error::RadioTxTimeout);
+
+    assert!(device.send(&mut radio, &[1, 2, 3], 1, false).is_ok());
+    assert_eq!(radio.last_call(), Some("send_buffer"));
+}
+
+#[test]
+fn test_failed_send_leaves_no_frame_in_buffer() {
+    let mut radio = MockRadio::default();
+    let mut device = new_device();
+    join(&mut device, &mut radio);
+
+    assert!(device.send(&mut radio, &[1, 2, 3], 1, false).is_ok());
+    // MHDR, FHDR, FPort, FRMPayload and MIC
+    assert_eq!(radio.buffer.len(), 16);
+
+    assert_eq!(
+        device.send(&mut radio, &[1, 2, 3], 1, false),
+        Err(Error::UnexpectedEvent(
+            DeviceState::JoinedSending,
+            Event::SendData(false)
+        ))
+    );
+    assert!(radio.buffer.is_empty());
+
+    assert!(device.handle_event(&mut radio, Event::TxComplete).is_none());
+    assert!(device.send(&mut radio, &[1, 2, 3], 1, false).is_ok());
+    assert_eq!(radio.buffer.len(), 16);
+}
@@ -0,0 +1,409 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Provides the network side of adaptive data rate (ADR), computing the data rate, TX power and
+//! NbTrans of a device from its recent uplinks and producing the LinkADRReq commands applying
+//! them.
+//!
+//! The link margin of the best recent uplink, reduced by an installation margin, is split in
+//! steps of 3 dB. Positive steps first increase the data rate up to the maximum of the region and
+//! then lower the TX power, while negative steps raise the TX power back. NbTrans follows the
+//! packet loss seen in the gaps between the frame counters.
+//!
+//! # Examples
+//!
+//! ```
+//! use lorawan_encoding::adr::{AdrEngine, LinkSettings, Region, UplinkHistory, UplinkMetrics};
+//!
+//! let engine = AdrEngine::new(Region::EU868);
+//! let mut history = UplinkHistory::new();
+//! for fcnt in 0..20 {
+//!     history.push(UplinkMetrics { fcnt, snr: 5.0, rssi: -90 });
+//! }
+//! let current = LinkSettings { data_rate: 0, tx_power: 0, nb_trans: 1 };
+//! let settings = engine.optimal_settings(&history, &current).unwrap();
+//! assert_eq!(settings, LinkSettings { data_rate: 5, tx_power: 0, nb_trans: 1 });
+//!
+//! let cmds = engine.link_adr_reqs(&settings).unwrap();
+//! assert_eq!(cmds.len(), 1);
+//! assert_eq!(cmds[0].build(), [0x03, 0x50, 0x07, 0x00, 0x01]);
+//! ```
+
+use heapless::consts::{U20, U5};
+use heapless::Vec;
+
+use super::error::Error;
+use super::maccommandcreator::LinkADRReqCreator;
+
+/// The number of uplinks kept in an UplinkHistory.
+pub const HISTORY_LEN: usize = 20;
+
+/// The number of dB of link margin needed for a step of data rate or TX power.
+const STEP_DB: f32 = 3.0;
+
+/// The sensitivity in dBm of the demodulator for SF7 to SF12 with a bandwidth of 125 kHz.
+const SENSITIVITY: [f32; 6] = [-123.0, -126.0, -129.0, -132.0, -134.5, -137.0];
+
+/// The NbTrans to use for the current NbTrans (rows) and packet loss below 5%, 10%, 30% and
+/// above (columns).
+const NB_TRANS: [[u8; 4]; 3] = [[1, 1, 2, 3], [1, 2, 3, 3], [2, 3, 3, 3]];
+
+/// Gives the SNR in dB needed to demodulate a frame with the given spreading factor, -7.5 dB for
+/// SF7 down to -20 dB for SF12.
+pub fn required_snr(spreading_factor: u8) -> f32 {
+    10.0 - 2.5 * f32::from(spreading_factor)
+}
+
+/// Gives the RSSI in dBm needed to demodulate a frame with the given spreading factor and a
+/// bandwidth of 125 kHz.
+pub fn sensitivity(spreading_factor: u8) -> f32 {
+    SENSITIVITY[usize::from(spreading_factor.clamp(7, 12) - 7)]
+}
+
+/// Region represents the regional parameters needed by ADR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    /// EU 863-870 MHz with up to 16 channels.
+    EU868,
+    /// US 902-928 MHz with 64 channels of 125 kHz and 8 channels of 500 kHz.
+    US915,
+}
+
+impl Region {
+    /// Gives the spreading factor of a data rate that ADR can assign, i.e. one with a bandwidth
+    /// of 125 kHz.
+    pub fn spreading_factor(self, data_rate: u8) -> Option<u8> {
+        if data_rate > self.max_data_rate() {
+            return None;
+        }
+        match self {
+            Region::EU868 => Some(12 - data_rate),
+            Region::US915 => Some(10 - data_rate),
+        }
+    }
+
+    /// Gives the highest data rate that ADR can assign.
+    pub fn max_data_rate(self) -> u8 {
+        match self {
+            Region::EU868 => 5,
+            Region::US915 => 3,
+        }
+    }
+
+    /// Gives the TX power index of the lowest TX power, 0 being the highest one.
+    pub fn max_tx_power(self) -> u8 {
+        match self {
+            Region::EU868 => 7,
+            Region::US915 => 10,
+        }
+    }
+
+    /// Gives the number of channels of the region.
+    pub fn channels(self) -> usize {
+        match self {
+            Region::EU868 => 16,
+            Region::US915 => 72,
+        }
+    }
+}
+
+/// UplinkMetrics represents the reception of an uplink by the gateway with the best SNR.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UplinkMetrics {
+    /// The full frame counter of the uplink.
+    pub fcnt: u32,
+    /// The SNR in dB.
+    pub snr: f32,
+    /// The RSSI in dBm.
+    pub rssi: i16,
+}
+
+/// UplinkHistory keeps the metrics of the last HISTORY_LEN uplinks of a device.
+#[derive(Debug, Clone)]
+pub struct UplinkHistory {
+    uplinks: Vec<UplinkMetrics, U20>,
+}
+
+impl Default for UplinkHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UplinkHistory {
+    /// Creates an empty history.
+    pub fn new() -> Self {
+        UplinkHistory {
+            uplinks: Vec::new(),
+        }
+    }
+
+    /// Records an uplink, dropping the oldest one when the history is full.
+    ///
+    /// Receptions of the last uplink by other gateways only improve its metrics. A frame
+    /// counter lower than the last one means that the device started a new session, so the
+    /// history is cleared first.
+    pub fn push(&mut self, metrics: UplinkMetrics) {
+        if let Some(last) = self.uplinks.last_mut() {
+            if last.fcnt == metrics.fcnt {
+                last.snr = last.snr.max(metrics.snr);
+                last.rssi = last.rssi.max(metrics.rssi);
+                return;
+            }
+            if last.fcnt > metrics.fcnt {
+                self.uplinks = Vec::new();
+            }
+        }
+        if self.uplinks.len() == HISTORY_LEN {
+            self.uplinks.rotate_left(1);
+            self.uplinks.pop();
+        }
+        // there is room after dropping the oldest uplink
+        let _ = self.uplinks.push(metrics);
+    }
+
+    /// Forgets all uplinks, e.g. after the settings of the device changed.
+    pub fn clear(&mut self) {
+        self.uplinks = Vec::new();
+    }
+
+    /// Gives the recorded uplinks, the oldest first.
+    pub fn uplinks(&self) -> &[UplinkMetrics] {
+        &self.uplinks
+    }
+
+    /// Gives the number of recorded uplinks.
+    pub fn len(&self) -> usize {
+        self.uplinks.len()
+    }
+
+    /// Whether no uplinks are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.uplinks.is_empty()
+    }
+
+    /// Gives the best SNR of the recorded uplinks.
+    pub fn max_snr(&self) -> Option<f32> {
+        self.uplinks.iter().map(|u| u.snr).fold(None, |acc, snr| {
+            Some(acc.map_or(snr, |max: f32| max.max(snr)))
+        })
+    }
+
+    /// Gives the best RSSI of the recorded uplinks.
+    pub fn max_rssi(&self) -> Option<i16> {
+        self.uplinks.iter().map(|u| u.rssi).max()
+    }
+
+    /// Gives the percentage of uplinks missing between the first and the last recorded ones.
+    pub fn packet_loss(&self) -> f32 {
+        let (first, last) = match (self.uplinks.first(), self.uplinks.last()) {
+            (Some(first), Some(last)) => (first.fcnt, last.fcnt),
+            _ => return 0.0,
+        };
+        let expected = (last - first) as f32 + 1.0;
+        (expected - self.uplinks.len() as f32) * 100.0 / expected
+    }
+}
+
+/// LinkSettings represents the link parameters of a device that are controlled by ADR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkSettings {
+    /// The data rate index.
+    pub data_rate: u8,
+    /// The TX power index, 0 being the highest TX power.
+    pub tx_power: u8,
+    /// The number of transmissions of each uplink, between 1 and 15.
+    pub nb_trans: u8,
+}
+
+/// AdrEngine computes the link settings of the devices of a region.
+#[derive(Debug, Clone)]
+pub struct AdrEngine {
+    region: Region,
+    installation_margin: f32,
+    min_history: usize,
+    channels: [u8; 9],
+}
+
+impl AdrEngine {
+    /// Creates an engine with an installation margin of 10 dB that needs a full history.
+    ///
+    /// The channels 0 to 2 are enabled for EU868 and all channels are enabled for US915.
+    pub fn new(region: Region) -> Self {
+        let channels = match region {
+            Region::EU868 => [0x07, 0, 0, 0, 0, 0, 0, 0, 0],
+            Region::US915 => [0xff; 9],
+        };
+        AdrEngine {
+            region,
+            installation_margin: 10.0,
+            min_history: HISTORY_LEN,
+            channels,
+        }
+    }
+
+    /// Gives the region of the engine.
+    pub fn region(&self) -> Region {
+        self.region
+    }
+
+    /// Sets the number of dB of link margin that is kept to absorb fading.
+    pub fn set_installation_margin(&mut self, margin: f32) -> &mut Self {
+        self.installation_margin = margin;
+        self
+    }
+
+    /// Sets the number of uplinks needed before the settings of a device are changed.
+    ///
+    /// # Argument
+    ///
+    /// * min_history - the number of uplinks, between 1 and HISTORY_LEN.
+    pub fn set_min_history(&mut self, min_history: usize) -> Result<&mut Self, Error> {
+        if min_history == 0 || min_history > HISTORY_LEN {
+            return Err(Error::ValueOutOfRange);
+        }
+        self.min_history = min_history;
+        Ok(self)
+    }
+
+    /// Sets the channels that the device should use, bit `i % 8` of byte `i / 8` enabling
+    /// channel `i`.
+    ///
+    /// Only the first 2 bytes are used for EU868, while for US915 the channels 64 to 71 are the
+    /// 500 kHz ones.
+    pub fn set_channels(&mut self, channels: [u8; 9]) -> &mut Self {
+        self.channels = channels;
+        self
+    }
+
+    /// Enables only the 8 channels of 125 kHz and the channel of 500 kHz of a US915 sub-band.
+    ///
+    /// # Argument
+    ///
+    /// * sub_band - the sub-band, between 1 and 8.
+    pub fn set_sub_band(&mut self, sub_band: u8) -> Result<&mut Self, Error> {
+        if self.region != Region::US915 || sub_band == 0 || sub_band > 8 {
+            return Err(Error::ValueOutOfRange);
+        }
+        let mut channels = [0; 9];
+        channels[usize::from(sub_band - 1)] = 0xff;
+        channels[8] = 1 << (sub_band - 1);
+        self.channels = channels;
+        Ok(self)
+    }
+
+    /// Computes the link settings of a device from its recent uplinks.
+    ///
+    /// The current settings are kept until the history has enough uplinks. The link margin is the
+    /// smaller of the SNR margin and the RSSI margin of the best uplinks for the current data rate.
+    ///
+    /// # Argument
+    ///
+    /// * history - the uplinks received since the device uses the current settings.
+    /// * current - the settings used by the device.
+    pub fn optimal_settings(
+        &self,
+        history: &UplinkHistory,
+        current: &LinkSettings,
+    ) -> Result<LinkSettings, Error> {
+        let spreading_factor = self.validate(current)?;
+        let (snr, rssi) = match (history.max_snr(), history.max_rssi()) {
+            (Some(snr), Some(rssi)) if history.len() >= self.min_history => (snr, rssi),
+            _ => return Ok(*current),
+        };
+
+        let snr_margin = snr - required_snr(spreading_factor);
+        let rssi_margin = f32::from(rssi) - sensitivity(spreading_factor);
+        let margin = snr_margin.min(rssi_margin) - self.installation_margin;
+        let mut steps = (margin / STEP_DB) as i32;
+
+        let mut settings = *current;
+        while steps > 0 && settings.data_rate < self.region.max_data_rate() {
+            settings.data_rate += 1;
+            steps -= 1;
+        }
+        while steps > 0 && settings.tx_power < self.region.max_tx_power() {
+            settings.tx_power += 1;
+            steps -= 1;
+        }
+        while steps < 0 && settings.tx_power > 0 {
+            settings.tx_power -= 1;
+            steps += 1;
+        }
+
+        let loss = history.packet_loss();
+        let column = if loss < 5.0 {
+            0
+        } else if loss < 10.0 {
+            1
+        } else if loss < 30.0 {
+            2
+        } else {
+            3
+        };
+        settings.nb_trans = NB_TRANS[usize::from(current.nb_trans.min(3) - 1)][column];
+        Ok(settings)
+    }
+
+    /// Creates the block of LinkADRReq commands applying the settings and the channels of the
+    /// engine.
+    ///
+    /// EU868 needs a single command. For US915 all channels of 125 kHz are disabled first and the
+    /// enabled ones are turned back on with one command per block of 16 channels, unless all of
+    /// them are enabled.
+    pub fn link_adr_reqs(
+        &self,
+        settings: &LinkSettings,
+    ) -> Result<Vec<LinkADRReqCreator, U5>, Error> {
+        self.validate(settings)?;
+        let mut masks: Vec<(u8, [u8; 2]), U5> = Vec::new();
+        // at most 5 commands are needed so pushing can not fail
+        match self.region {
+            Region::EU868 => {
+                let _ = masks.push((0, [self.channels[0], self.channels[1]]));
+            }
+            Region::US915 => {
+                let mask_500 = [self.channels[8], 0];
+                if self.channels[..8].iter().all(|b| *b == 0xff) {
+                    let _ = masks.push((6, mask_500));
+                } else {
+                    let _ = masks.push((7, mask_500));
+                    for (i, block) in self.channels[..8].chunks(2).enumerate() {
+                        if block != [0, 0] {
+                            let _ = masks.push((i as u8, [block[0], block[1]]));
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut res = Vec::new();
+        for &(ch_mask_cntl, mask) in masks.iter() {
+            let mut creator = LinkADRReqCreator::new();
+            creator
+                .set_data_rate(settings.data_rate)?
+                .set_tx_power(settings.tx_power)?
+                .set_channel_mask(mask)
+                .set_redundancy((ch_mask_cntl << 4) | settings.nb_trans);
+            let _ = res.push(creator);
+        }
+        Ok(res)
+    }
+
+    fn validate(&self, settings: &LinkSettings) -> Result<u8, Error> {
+        if settings.tx_power > self.region.max_tx_power()
+            || settings.nb_trans == 0
+            || settings.nb_trans > 15
+        {
+            return Err(Error::ValueOutOfRange);
+        }
+        self.region
+            .spreading_factor(settings.data_rate)
+            .ok_or(Error::ValueOutOfRange)
+    }
+}
@@ -10,6 +10,7 @@
 //!
 //! See [JoinAcceptCreator.new](struct.JoinAcceptCreator.html#method.new) for an example.
 
+use super::error::Error;
 use super::keys;
 use super::keys::CryptoFactory;
 use super::maccommandcreator;
@@ -25,13 +26,10 @@ use super::default_crypto::DefaultFactory;
 #[cfg(feature = "with-downlink")]
 use super::keys::Decrypter;
 
-#[cfg(any(feature = "with-downlink", feature = "default-crypto"))]
-use generic_array::GenericArray;
-
-#[cfg(feature = "default-crypto")]
 use generic_array::typenum::U256;
+use generic_array::GenericArray;
 
-const PIGGYBACK_MAC_COMMANDS_MAX_LEN: usize = 15;
+pub(crate) const PIGGYBACK_MAC_COMMANDS_MAX_LEN: usize = 15;
 
 /// JoinAcceptCreator serves for creating binary representation of Physical
 /// Payload of JoinAccept.
@@ -49,10 +47,10 @@ impl<D: AsMut<[u8]>, F: CryptoFactory + Default> JoinAcceptCreator<D, F> {
     /// Creates a well initialized JoinAcceptCreator with specific data and crypto functions.
     ///
     /// TODO: Add more detials & and example
-    pub fn with_options<'a>(mut data: D, factory: F) -> Result<Self, &'a str> {
+    pub fn with_options(mut data: D, factory: F) -> Result<Self, Error> {
         let d = data.as_mut();
         if d.len() < 33 {
-            return Err("data slice is too short");
+            return Err(Error::BufferTooShort);
         }
         d[0] = 0x20;
         Ok(Self {
@@ -143,10 +141,10 @@ impl<D: AsMut<[u8]>, F: CryptoFactory + Default> JoinAcceptCreator<D, F> {
     pub fn set_c_f_list<'a, C: AsRef<[Frequency<'a>]>>(
         &mut self,
         list: C,
-    ) -> Result<&mut Self, &str> {
+    ) -> Result<&mut Self, Error> {
         let ch_list = list.as_ref();
         if ch_list.len() > 5 {
-            return Err("too many frequences");
+            return Err(Error::TooManyFrequencies);
         }
         let d = self.data.as_mut();
         ch_list.iter().enumerate().for_each(|(i, fr)| {
@@ -155,6 +153,7 @@ impl<D: AsMut<[u8]>, F: CryptoFactory + Default> JoinAcceptCreator<D, F> {
             d[14 + i * 3] = ((v >> 8) & 0xff) as u8;
             d[15 + i * 3] = ((v >> 16) & 0xff) as u8;
         });
+        self.with_c_f_list = true;
 
         Ok(self)
     }
@@ -165,20 +164,111 @@ impl<D: AsMut<[u8]>, F: CryptoFactory + Default> JoinAcceptCreator<D, F> {
     /// # Argument
     ///
     /// * key - the key to be used for encryption and setting the MIC.
-    pub fn build(&mut self, key: &keys::AES128) -> Result<&[u8], &str> {
+    pub fn build(&mut self, key: &keys::AES128) -> Result<&[u8], Error> {
+        let len = self.payload_len();
         if !self.encrypted {
+            set_mic(&mut self.data.as_mut()[..len], key, &self.factory);
             self.encrypt_payload(key);
         }
-        Ok(&self.data.as_mut()[..])
+        Ok(&self.data.as_mut()[..len])
     }
 
-    fn encrypt_payload(&mut self, key: &keys::AES128) {
-        let d = if self.with_c_f_list {
-            self.data.as_mut()
+    /// Provides the binary representation of the encrypted LoRaWAN 1.1 join accept
+    /// physical payload with the MIC set.
+    ///
+    /// The LoRaWAN 1.1 MIC is only used by devices when the OptNeg bit of the DLSettings is set.
+    ///
+    /// # Argument
+    ///
+    /// * key - the key to be used for encryption, NwkKey when answering a JoinRequest and JSEncKey
+    ///   when answering a RejoinRequest.
+    /// * js_int_key - the key to be used for setting the MIC.
+    /// * join_req_type - the type of the request that the JoinAccept answers.
+    /// * join_eui - the JoinEUI of the device.
+    /// * dev_nonce - the DevNonce of the JoinRequest or the RJcount of the RejoinRequest.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut phy = lorawan_encoding::creator::JoinAcceptCreator::new();
+    /// let nwk_key = lorawan_encoding::keys::AES128([1; 16]);
+    /// let js_int_key = lorawan_encoding::keys::AES128([2; 16]);
+    /// phy.set_app_nonce(&[1; 3])
+    ///     .set_net_id(&[1; 3])
+    ///     .set_dev_addr(&[1; 4])
+    ///     .set_dl_settings(0x80)
+    ///     .set_rx_delay(1);
+    /// let payload = phy
+    ///     .build_1_1(
+    ///         &nwk_key,
+    ///         &js_int_key,
+    ///         lorawan_encoding::parser::JoinReqType::JoinRequest,
+    ///         &lorawan_encoding::parser::EUI64::from(&[1; 8]),
+    ///         &lorawan_encoding::parser::DevNonce::from(&[1; 2]),
+    ///     )
+    ///     .unwrap();
+    /// ```
+    pub fn build_1_1<H: AsRef<[u8]>, I: AsRef<[u8]>>(
+        &mut self,
+        key: &keys::AES128,
+        js_int_key: &keys::AES128,
+        join_req_type: parser::JoinReqType,
+        join_eui: &parser::EUI64<H>,
+        dev_nonce: &parser::DevNonce<I>,
+    ) -> Result<&[u8], Error> {
+        let len = self.payload_len();
+        if !self.encrypted {
+            let d = self.data.as_mut();
+            let mic = securityhelpers::calculate_join_accept_mic_1_1(
+                &d[..len - 4],
+                self.factory.new_mac(js_int_key),
+                join_req_type.into(),
+                join_eui.as_ref(),
+                dev_nonce.as_ref(),
+            );
+            d[len - 4..len].copy_from_slice(&mic.0[..]);
+            self.encrypt_payload(key);
+        }
+        Ok(&self.data.as_mut()[..len])
+    }
+
+    /// Sets the CFList of the JoinAccept to the provided 16 bytes, whatever its CFListType.
+    #[cfg(feature = "serde")]
+    pub(crate) fn set_raw_c_f_list(&mut self, c_f_list: &[u8]) -> Result<&mut Self, Error> {
+        if c_f_list.len() != 16 {
+            return Err(Error::InvalidLength);
+        }
+        self.data.as_mut()[13..29].copy_from_slice(c_f_list);
+        self.with_c_f_list = true;
+
+        Ok(self)
+    }
+
+    /// Provides the binary representation of the decrypted join accept physical payload with the
+    /// provided MIC.
+    ///
+    /// It serves for rebuilding join accepts that are kept decrypted, e.g. when deserializing
+    /// them, so no keys are needed.
+    #[cfg(feature = "serde")]
+    pub(crate) fn build_with_mic(&mut self, mic: &keys::MIC) -> &[u8] {
+        let len = self.payload_len();
+        let d = self.data.as_mut();
+        d[len - 4..len].copy_from_slice(&mic.0[..]);
+
+        &d[..len]
+    }
+
+    fn payload_len(&self) -> usize {
+        if self.with_c_f_list {
+            33
         } else {
-            &mut self.data.as_mut()[..17]
-        };
-        set_mic(d, key, &self.factory);
+            17
+        }
+    }
+
+    fn encrypt_payload(&mut self, key: &keys::AES128) {
+        let len = self.payload_len();
+        let d = &mut self.data.as_mut()[..len];
         let aes_enc = self.factory.new_dec(key);
         for i in 0..(d.len() >> 4) {
             let start = (i << 4) + 1;
@@ -189,7 +279,7 @@ impl<D: AsMut<[u8]>, F: CryptoFactory + Default> JoinAcceptCreator<D, F> {
     }
 }
 
-#[cfg(feature = "default-crypto,with-downlink")]
+#[cfg(all(feature = "default-crypto", feature = "with-downlink"))]
 impl JoinAcceptCreator<[u8; 33], DefaultFactory> {
     /// Creates a well initialized JoinAcceptCreator.
     ///
@@ -205,9 +295,9 @@ impl JoinAcceptCreator<[u8; 33], DefaultFactory> {
     /// phy.set_dl_settings(2);
     /// phy.set_rx_delay(1);
     /// let mut freqs: Vec<lorawan_encoding::maccommands::Frequency> = Vec::new();
-    /// freqs.push(lorawan_encoding::maccommands::Frequency::new(&[0x58, 0x6e, 0x84,]).unwrap()).unwrap();
-    /// freqs.push(lorawan_encoding::maccommands::Frequency::new(&[0x88, 0x66, 0x84,]).unwrap()).unwrap();
-    /// phy.set_c_f_list(freqs);
+    /// freqs.push(lorawan_encoding::maccommands::Frequency::new(&[0x58, 0x6e, 0x84,]).unwrap());
+    /// freqs.push(lorawan_encoding::maccommands::Frequency::new(&[0x88, 0x66, 0x84,]).unwrap());
+    /// phy.set_c_f_list(freqs).unwrap();
     /// let payload = phy.build(&key).unwrap();
     /// ```
     pub fn new() -> Self {
@@ -239,10 +329,10 @@ pub struct JoinRequestCreator<D, F> {
 
 impl<D: AsMut<[u8]>, F: CryptoFactory> JoinRequestCreator<D, F> {
     /// Creates a well initialized JoinRequestCreator with specific crypto functions.
-    pub fn with_options<'a>(mut data: D, factory: F) -> Result<Self, &'a str> {
+    pub fn with_options(mut data: D, factory: F) -> Result<Self, Error> {
         let d = data.as_mut();
         if d.len() < 23 {
-            return Err("data slice is too short");
+            return Err(Error::BufferTooShort);
         }
         d[0] = 0x00;
         Ok(Self { data, factory })
@@ -302,13 +392,149 @@ impl<D: AsMut<[u8]>, F: CryptoFactory> JoinRequestCreator<D, F> {
     /// # Argument
     ///
     /// * key - the key to be used for setting the MIC.
-    pub fn build(&mut self, key: &keys::AES128) -> Result<&[u8], &str> {
+    pub fn build(&mut self, key: &keys::AES128) -> Result<&[u8], Error> {
         let d = self.data.as_mut();
         set_mic(&mut d[..], key, &self.factory);
         Ok(&d[..])
     }
 }
 
+/// RejoinRequestCreator serves for creating binary representation of Physical
+/// Payload of RejoinRequest.
+#[derive(Default)]
+pub struct RejoinRequestCreator<D, F> {
+    data: D,
+    rejoin_type: u8,
+    net_id: [u8; 3],
+    join_eui: [u8; 8],
+    dev_eui: [u8; 8],
+    rj_count: u16,
+    factory: F,
+}
+
+impl<D: AsMut<[u8]>, F: CryptoFactory> RejoinRequestCreator<D, F> {
+    /// Creates a well initialized RejoinRequestCreator with specific crypto functions.
+    ///
+    /// By default the RejoinRequest is of type 0.
+    pub fn with_options(mut data: D, factory: F) -> Result<Self, Error> {
+        let d = data.as_mut();
+        if d.len() < 24 {
+            return Err(Error::BufferTooShort);
+        }
+        d[0] = 0xc0;
+        Ok(Self {
+            data,
+            rejoin_type: 0,
+            net_id: [0; 3],
+            join_eui: [0; 8],
+            dev_eui: [0; 8],
+            rj_count: 0,
+            factory,
+        })
+    }
+
+    /// Sets the type of the RejoinRequest.
+    ///
+    /// # Argument
+    ///
+    /// * rejoin_type - the type of the RejoinRequest.
+    pub fn set_rejoin_type(&mut self, rejoin_type: parser::RejoinType) -> &mut Self {
+        self.rejoin_type = rejoin_type.into();
+
+        self
+    }
+
+    /// Sets the NetID of the RejoinRequest to the provided value.
+    ///
+    /// It is used only by RejoinRequests of type 0 and 2.
+    ///
+    /// # Argument
+    ///
+    /// * net_id - instance of lorawan_encoding::parser::NwkAddr or anything that can
+    ///   be converted into it.
+    pub fn set_net_id<H: AsRef<[u8]>, T: Into<parser::NwkAddr<H>>>(
+        &mut self,
+        net_id: T,
+    ) -> &mut Self {
+        let converted = net_id.into();
+        self.net_id.copy_from_slice(converted.as_ref());
+
+        self
+    }
+
+    /// Sets the JoinEUI of the RejoinRequest to the provided value.
+    ///
+    /// It is used only by RejoinRequests of type 1.
+    ///
+    /// # Argument
+    ///
+    /// * join_eui - instance of lorawan_encoding::parser::EUI64 or anything that can
+    ///   be converted into it.
+    pub fn set_join_eui<H: AsRef<[u8]>, T: Into<parser::EUI64<H>>>(
+        &mut self,
+        join_eui: T,
+    ) -> &mut Self {
+        let converted = join_eui.into();
+        self.join_eui.copy_from_slice(converted.as_ref());
+
+        self
+    }
+
+    /// Sets the DevEUI of the RejoinRequest to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * dev_eui - instance of lorawan_encoding::parser::EUI64 or anything that can
+    ///   be converted into it.
+    pub fn set_dev_eui<H: AsRef<[u8]>, T: Into<parser::EUI64<H>>>(
+        &mut self,
+        dev_eui: T,
+    ) -> &mut Self {
+        let converted = dev_eui.into();
+        self.dev_eui.copy_from_slice(converted.as_ref());
+
+        self
+    }
+
+    /// Sets the RJcount0 or RJcount1 of the RejoinRequest depending on its type.
+    ///
+    /// # Argument
+    ///
+    /// * rj_count - the rejoin counter.
+    pub fn set_rj_count(&mut self, rj_count: u16) -> &mut Self {
+        self.rj_count = rj_count;
+
+        self
+    }
+
+    /// Provides the binary representation of the RejoinRequest physical payload
+    /// with the MIC set.
+    ///
+    /// # Argument
+    ///
+    /// * key - the key to be used for setting the MIC, SNwkSIntKey for RejoinRequests of type 0
+    ///   and 2 and JSIntKey for type 1.
+    pub fn build(&mut self, key: &keys::AES128) -> Result<&[u8], Error> {
+        let d = self.data.as_mut();
+        d[1] = self.rejoin_type;
+        let len = if self.rejoin_type == 0x01 {
+            d[2..10].copy_from_slice(&self.join_eui);
+            d[10..18].copy_from_slice(&self.dev_eui);
+            d[18] = self.rj_count as u8;
+            d[19] = (self.rj_count >> 8) as u8;
+            24
+        } else {
+            d[2..5].copy_from_slice(&self.net_id);
+            d[5..13].copy_from_slice(&self.dev_eui);
+            d[13] = self.rj_count as u8;
+            d[14] = (self.rj_count >> 8) as u8;
+            19
+        };
+        set_mic(&mut d[..len], key, &self.factory);
+        Ok(&d[..len])
+    }
+}
+
 /// DataPayloadCreator serves for creating binary representation of Physical
 /// Payload of DataUp or DataDown messages.
 ///
@@ -331,6 +557,9 @@ pub struct DataPayloadCreator<D, F> {
     data: D,
     data_f_port: Option<u8>,
     fcnt: u32,
+    conf_fcnt: u16,
+    tx_dr: u8,
+    tx_ch: u8,
     factory: F,
 }
 
@@ -338,16 +567,19 @@ impl<D: AsMut<[u8]>, F: CryptoFactory + Default> DataPayloadCreator<D, F> {
     /// Creates a well initialized DataPayloadCreator with specific crypto functions.
     ///
     /// By default the packet is unconfirmed data up packet.
-    pub fn with_options<'a>(mut data: D, factory: F) -> Result<Self, &'a str> {
+    pub fn with_options(mut data: D, factory: F) -> Result<Self, Error> {
         let d = data.as_mut();
         if d.len() < 255 {
-            return Err("data slice is too short");
+            return Err(Error::BufferTooShort);
         }
         d[0] = 0x40;
         Ok(DataPayloadCreator {
             data,
             data_f_port: None,
             fcnt: 0,
+            conf_fcnt: 0,
+            tx_dr: 0,
+            tx_ch: 0,
             factory,
         })
     }
@@ -439,6 +671,36 @@ impl<D: AsMut<[u8]>, F: CryptoFactory + Default> DataPayloadCreator<D, F> {
         self
     }
 
+    /// Sets the ConfFCnt used for the LoRaWAN 1.1 MIC when the packet acknowledges a confirmed
+    /// packet.
+    ///
+    /// It is ignored for LoRaWAN 1.0 packets and when the ACK bit is not set.
+    ///
+    /// # Argument
+    ///
+    /// * conf_fcnt - the FCnt of the confirmed packet being acknowledged.
+    pub fn set_conf_fcnt(&mut self, conf_fcnt: u16) -> &mut Self {
+        self.conf_fcnt = conf_fcnt;
+
+        self
+    }
+
+    /// Sets the data rate and the channel used for the transmission of a LoRaWAN 1.1 uplink.
+    ///
+    /// They are only used for the MIC computation and are ignored for LoRaWAN 1.0 packets and
+    /// downlinks.
+    ///
+    /// # Argument
+    ///
+    /// * tx_dr - the data rate of the transmission.
+    /// * tx_ch - the index of the channel of the transmission.
+    pub fn set_tx_dr_and_ch(&mut self, tx_dr: u8, tx_ch: u8) -> &mut Self {
+        self.tx_dr = tx_dr;
+        self.tx_ch = tx_ch;
+
+        self
+    }
+
     /// Whether a set of mac commands can be piggybacked.
     pub fn can_piggyback(cmds: &[&dyn SerializableMacCommand]) -> bool {
         mac_commands_len(cmds) <= PIGGYBACK_MAC_COMMANDS_MAX_LEN
@@ -474,78 +736,210 @@ impl<D: AsMut<[u8]>, F: CryptoFactory + Default> DataPayloadCreator<D, F> {
     /// let app_skey = lorawan_encoding::keys::AES128([1; 16]);
     /// phy.build(&[], &cmds[..], &nwk_skey, &app_skey).unwrap();
     /// ```
-    pub fn build<'a, 'b, 'c, 'd, 'e>(
+    pub fn build(
         &mut self,
         payload: &[u8],
-        cmds: &'a [&'b dyn SerializableMacCommand],
-        nwk_skey: &'c keys::AES128,
-        app_skey: &'d keys::AES128,
-    ) -> Result<&[u8], &'e str> {
+        cmds: &[&dyn SerializableMacCommand],
+        nwk_skey: &keys::AES128,
+        app_skey: &keys::AES128,
+    ) -> Result<&[u8], Error> {
+        let len = self.build_frame(payload, cmds, None, nwk_skey, app_skey)?;
         let d = self.data.as_mut();
-        let mut last_filled = 8; // MHDR + FHDR without the FOpts
-        let has_fport = self.data_f_port.is_some();
-        let has_fport_zero = has_fport && self.data_f_port.unwrap() == 0;
-        let mac_cmds_len = mac_commands_len(cmds);
 
-        // Set MAC Commands
-        if mac_cmds_len > PIGGYBACK_MAC_COMMANDS_MAX_LEN && !has_fport_zero {
-            return Err("mac commands are too big for FOpts");
-        }
+        // MIC set
+        let mic = securityhelpers::calculate_data_mic(
+            &d[..len],
+            self.factory.new_mac(nwk_skey),
+            self.fcnt,
+        );
+        d[len..len + 4].copy_from_slice(&mic.0[..]);
 
-        // Set FPort
-        let mut payload_len = payload.len();
-        if has_fport_zero && payload_len > 0 {
-            return Err("mac commands in payload can not be send together with payload");
-        }
-        if !has_fport && payload_len > 0 {
-            return Err("fport must be provided when there is FRMPayload");
-        }
-        // Set FOptsLen if present
-        if !has_fport_zero && mac_cmds_len > 0 {
-            d[5] |= mac_cmds_len as u8 & 0x0f;
-            maccommandcreator::build_mac_commands(
-                cmds,
-                &mut d[last_filled..last_filled + mac_cmds_len],
+        Ok(&d[..len + 4])
+    }
+
+    /// Provides the binary representation of the LoRaWAN 1.1 DataPayload physical payload
+    /// with the MIC set and the payload and FOpts encrypted.
+    ///
+    /// # Argument
+    ///
+    /// * payload - the FRMPayload (application) to be sent.
+    /// * cmds - the mac commands to be sent.
+    /// * f_nwk_s_int_key - the key used for the MIC of uplinks, ignored for downlinks.
+    /// * s_nwk_s_int_key - the key used for the MIC.
+    /// * nwk_s_enc_key - the key used for FOpts encryption and for the encryption of the mac
+    ///   commands when fport is 0.
+    /// * app_skey - the key to be used for payload encryption if fport not 0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut phy = lorawan_encoding::creator::DataPayloadCreator::new();
+    /// let mac_cmd = lorawan_encoding::maccommands::MacCommand::LinkCheckReq(
+    ///     lorawan_encoding::maccommands::LinkCheckReqPayload());
+    /// let f_nwk_s_int_key = lorawan_encoding::keys::AES128([1; 16]);
+    /// let s_nwk_s_int_key = lorawan_encoding::keys::AES128([2; 16]);
+    /// let nwk_s_enc_key = lorawan_encoding::keys::AES128([3; 16]);
+    /// let app_skey = lorawan_encoding::keys::AES128([4; 16]);
+    /// phy.set_f_port(1).set_fcnt(1).set_tx_dr_and_ch(5, 2);
+    /// phy.build_1_1(
+    ///     b"hello",
+    ///     &[&mac_cmd],
+    ///     &f_nwk_s_int_key,
+    ///     &s_nwk_s_int_key,
+    ///     &nwk_s_enc_key,
+    ///     &app_skey,
+    /// )
+    /// .unwrap();
+    /// ```
+    pub fn build_1_1(
+        &mut self,
+        payload: &[u8],
+        cmds: &[&dyn SerializableMacCommand],
+        f_nwk_s_int_key: &keys::AES128,
+        s_nwk_s_int_key: &keys::AES128,
+        nwk_s_enc_key: &keys::AES128,
+        app_skey: &keys::AES128,
+    ) -> Result<&[u8], Error> {
+        let len = self.build_frame(payload, cmds, Some(nwk_s_enc_key), nwk_s_enc_key, app_skey)?;
+        let d = self.data.as_mut();
+
+        // MIC set
+        let mic = if d[0] & 0x20 == 0 {
+            securityhelpers::calculate_uplink_data_mic_1_1(
+                &d[..len],
+                self.factory.new_mac(s_nwk_s_int_key),
+                self.factory.new_mac(f_nwk_s_int_key),
+                self.fcnt,
+                self.conf_fcnt,
+                self.tx_dr,
+                self.tx_ch,
             )
-            .unwrap();
-            last_filled += mac_cmds_len;
+        } else {
+            securityhelpers::calculate_downlink_data_mic_1_1(
+                &d[..len],
+                self.factory.new_mac(s_nwk_s_int_key),
+                self.fcnt,
+                self.conf_fcnt,
+            )
+        };
+        d[len..len + 4].copy_from_slice(&mic.0[..]);
+
+        Ok(&d[..len + 4])
+    }
+
+    /// Provides the binary representation of the DataPayload physical payload with the provided
+    /// MIC, leaving the FOpts and FRMPayload as they are given.
+    ///
+    /// It serves for rebuilding payloads that are already encrypted or kept decrypted, e.g. when
+    /// deserializing them, so no keys are needed.
+    #[cfg(feature = "serde")]
+    pub(crate) fn build_with_mic(
+        &mut self,
+        frm_payload: &[u8],
+        f_opts: &[&dyn SerializableMacCommand],
+        mic: &keys::MIC,
+    ) -> Result<&[u8], Error> {
+        let start = self.fill_frame(f_opts, frm_payload.len())?;
+        let end = start + frm_payload.len();
+        let d = self.data.as_mut();
+        d[start..end].copy_from_slice(frm_payload);
+        d[end..end + 4].copy_from_slice(&mic.0[..]);
+
+        Ok(&d[..end + 4])
+    }
+
+    // Fills in the packet without the MIC and returns its length.
+    fn build_frame(
+        &mut self,
+        payload: &[u8],
+        cmds: &[&dyn SerializableMacCommand],
+        f_opts_key: Option<&keys::AES128>,
+        nwk_skey: &keys::AES128,
+        app_skey: &keys::AES128,
+    ) -> Result<usize, Error> {
+        let has_fport_zero = self.data_f_port == Some(0);
+        if has_fport_zero && !payload.is_empty() {
+            return Err(Error::MacCommandsWithPayload);
         }
-        if has_fport {
-            d[last_filled] = self.data_f_port.unwrap();
-            last_filled += 1;
+        // the MAC commands are sent as FRMPayload when FPort is 0 and in FOpts otherwise
+        let (f_opts, frm_payload_len) = if has_fport_zero {
+            (&[][..], mac_commands_len(cmds))
+        } else {
+            (cmds, payload.len())
+        };
+        let start = self.fill_frame(f_opts, frm_payload_len)?;
+        let end = start + frm_payload_len;
+        let d = self.data.as_mut();
+
+        if !f_opts.is_empty() {
+            if let Some(key) = f_opts_key {
+                securityhelpers::encrypt_fopts(
+                    &mut d[..],
+                    self.data_f_port,
+                    self.fcnt,
+                    &self.factory.new_enc(key),
+                );
+            }
         }
 
-        let mut enc_key = app_skey;
-        if mac_cmds_len > 0 && has_fport_zero {
-            enc_key = nwk_skey;
-            payload_len = mac_cmds_len;
-            maccommandcreator::build_mac_commands(
-                cmds,
-                &mut d[last_filled..last_filled + payload_len],
-            )
-            .unwrap();
+        let enc_key = if has_fport_zero {
+            maccommandcreator::build_mac_commands(cmds, &mut d[start..end]).unwrap();
+            nwk_skey
         } else {
-            d[last_filled..last_filled + payload_len].copy_from_slice(payload);
+            d[start..end].copy_from_slice(payload);
+            app_skey
         };
 
         // Encrypt FRMPayload
         securityhelpers::encrypt_frm_data_payload(
             &mut d[..],
-            last_filled,
-            last_filled + payload_len,
+            start,
+            end,
             self.fcnt,
-            &self.factory.new_enc(&enc_key),
+            &self.factory.new_enc(enc_key),
         );
 
-        // MIC set
-        let mic = securityhelpers::calculate_data_mic(
-            &d[..last_filled + payload_len],
-            self.factory.new_mac(nwk_skey),
-            self.fcnt,
-        );
-        d[last_filled + payload_len..last_filled + payload_len + 4].copy_from_slice(&mic.0[..]);
+        Ok(end)
+    }
+
+    // Checks that the frame fits, fills in the FOpts and the FPort and returns the position of
+    // the FRMPayload.
+    fn fill_frame(
+        &mut self,
+        f_opts: &[&dyn SerializableMacCommand],
+        frm_payload_len: usize,
+    ) -> Result<usize, Error> {
+        let d = self.data.as_mut();
+        let mut last_filled = 8; // MHDR + FHDR without the FOpts
+        let f_opts_len = mac_commands_len(f_opts);
+
+        if f_opts_len > PIGGYBACK_MAC_COMMANDS_MAX_LEN {
+            return Err(Error::FOptsTooLarge);
+        }
+        if self.data_f_port.is_none() && frm_payload_len > 0 {
+            return Err(Error::MissingFPort);
+        }
+        // MHDR + FHDR + FPort if present + FRMPayload + MIC
+        let f_port_len = usize::from(self.data_f_port.is_some());
+        if last_filled + f_opts_len + f_port_len + frm_payload_len + 4 > d.len() {
+            return Err(Error::PayloadTooLarge);
+        }
+        // Set FOptsLen if present
+        if f_opts_len > 0 {
+            d[5] |= f_opts_len as u8 & 0x0f;
+            maccommandcreator::build_mac_commands(
+                f_opts,
+                &mut d[last_filled..last_filled + f_opts_len],
+            )
+            .unwrap();
+            last_filled += f_opts_len;
+        }
+        if let Some(f_port) = self.data_f_port {
+            d[last_filled] = f_port;
+            last_filled += 1;
+        }
 
-        Ok(&d[..last_filled + payload_len + 4])
+        Ok(last_filled)
     }
 }
 
@@ -577,7 +971,67 @@ impl DataPayloadCreator<GenericArray<u8, U256>, DefaultFactory> {
             data,
             data_f_port: None,
             fcnt: 0,
+            conf_fcnt: 0,
+            tx_dr: 0,
+            tx_ch: 0,
             factory: DefaultFactory,
         }
     }
 }
+
+/// ProprietaryPayloadCreator serves for creating binary representation of Physical
+/// Payload with MType Proprietary.
+#[derive(Default)]
+pub struct ProprietaryPayloadCreator<D> {
+    data: D,
+    len: usize,
+}
+
+impl<D: AsMut<[u8]>> ProprietaryPayloadCreator<D> {
+    /// Creates a well initialized ProprietaryPayloadCreator with specific data.
+    pub fn with_options(mut data: D) -> Result<Self, Error> {
+        let d = data.as_mut();
+        if d.is_empty() {
+            return Err(Error::BufferTooShort);
+        }
+        d[0] = 0xe0;
+        Ok(Self { data, len: 1 })
+    }
+
+    /// Sets the bytes that follow the MHDR to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * payload - the vendor specific content of the physical payload.
+    pub fn set_payload(&mut self, payload: &[u8]) -> Result<&mut Self, Error> {
+        let d = self.data.as_mut();
+        if payload.len() >= d.len() {
+            return Err(Error::BufferTooShort);
+        }
+        d[1..=payload.len()].copy_from_slice(payload);
+        self.len = payload.len() + 1;
+        Ok(self)
+    }
+
+    /// Provides the binary representation of the proprietary physical payload.
+    pub fn build(&mut self) -> &[u8] {
+        &self.data.as_mut()[..self.len]
+    }
+}
+
+impl ProprietaryPayloadCreator<GenericArray<u8, U256>> {
+    /// Creates a well initialized ProprietaryPayloadCreator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut phy = lorawan_encoding::creator::ProprietaryPayloadCreator::new();
+    /// phy.set_payload(&[0x01, 0x02, 0x03]).unwrap();
+    /// assert_eq!(phy.build(), &[0xe0, 0x01, 0x02, 0x03]);
+    /// ```
+    pub fn new() -> Self {
+        let mut data: GenericArray<u8, U256> = GenericArray::default();
+        data[0] = 0xe0;
+        Self { data, len: 1 }
+    }
+}
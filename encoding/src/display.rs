@@ -0,0 +1,383 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Provides human readable, single line summaries of the LoRaWAN payloads through
+//! `core::fmt::Display`.
+//!
+//! Multi-byte identifiers like DevAddr and EUIs are printed most significant byte first, which is
+//! the reverse of the order in which they are transmitted.
+
+use core::fmt;
+
+use super::keys::CryptoFactory;
+use super::maccommands::*;
+use super::parser::*;
+
+struct Hex<'a>(&'a [u8]);
+
+impl<'a> fmt::Display for Hex<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for b in self.0 {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+struct ReversedHex<'a>(&'a [u8]);
+
+impl<'a> fmt::Display for ReversedHex<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for b in self.0.iter().rev() {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_mac_commands<'a, I: Iterator<Item = MacCommand<'a>>>(
+    f: &mut fmt::Formatter,
+    cmds: I,
+) -> fmt::Result {
+    f.write_str("[")?;
+    for (i, cmd) in cmds.enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        write!(f, "{}", cmd)?;
+    }
+    f.write_str("]")
+}
+
+impl fmt::Display for MHDR {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.mtype())?;
+        if self.major() != Major::LoRaWANR1 {
+            f.write_str(" Major=RFU")?;
+        }
+        Ok(())
+    }
+}
+
+/// Only the flags that are set are printed.
+impl fmt::Display for FCtrl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let class_b = self.raw_value() & 0x10 != 0 && !self.f_pending();
+        let flags = [
+            (self.adr(), "ADR"),
+            (self.adr_ack_req(), "ADRACKReq"),
+            (self.ack(), "ACK"),
+            (self.f_pending(), "FPending"),
+            (class_b, "ClassB"),
+        ];
+        let mut sep = "";
+        for (_, name) in flags.iter().filter(|(set, _)| *set) {
+            write!(f, "{}{}", sep, name)?;
+            sep = " ";
+        }
+        Ok(())
+    }
+}
+
+impl<'a> fmt::Display for FHDR<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "DevAddr={} FCnt={}",
+            ReversedHex(self.dev_addr().as_ref()),
+            self.fcnt()
+        )?;
+        let fctrl = self.fctrl();
+        if fctrl.raw_value() & 0xf0 != 0 {
+            write!(f, " {}", fctrl)?;
+        }
+        if fctrl.f_opts_len() > 0 {
+            f.write_str(" FOpts=")?;
+            write_mac_commands(f, self.fopts())?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> fmt::Display for MacCommand<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MacCommand::LinkCheckReq(_) => f.write_str("LinkCheckReq"),
+            MacCommand::LinkCheckAns(v) => write!(
+                f,
+                "LinkCheckAns(Margin={}, GwCnt={})",
+                v.margin(),
+                v.gateway_count()
+            ),
+            MacCommand::LinkADRReq(v) => {
+                let mask = v.channel_mask();
+                let mask = mask.as_ref();
+                write!(
+                    f,
+                    "LinkADRReq(DataRate={}, TXPower={}, ChMask={:04x}, ChMaskCntl={}, NbTrans={})",
+                    v.data_rate(),
+                    v.tx_power(),
+                    u16::from(mask[1]) << 8 | u16::from(mask[0]),
+                    v.redundancy().channel_mask_control(),
+                    v.redundancy().number_of_transmissions()
+                )
+            }
+            MacCommand::LinkADRAns(v) => write!(
+                f,
+                "LinkADRAns(ChMaskACK={}, DataRateACK={}, PowerACK={})",
+                v.channel_mask_ack(),
+                v.data_rate_ack(),
+                v.powert_ack()
+            ),
+            MacCommand::DutyCycleReq(v) => {
+                write!(f, "DutyCycleReq(MaxDCycle={})", v.max_duty_cycle_raw())
+            }
+            MacCommand::DutyCycleAns(_) => f.write_str("DutyCycleAns"),
+            MacCommand::RXParamSetupReq(v) => {
+                let dl_settings = v.dl_settings();
+                write!(
+                    f,
+                    "RXParamSetupReq(RX1DROffset={}, RX2DataRate={}, Frequency={})",
+                    dl_settings.rx1_dr_offset(),
+                    dl_settings.rx2_data_rate(),
+                    v.frequency().value()
+                )
+            }
+            MacCommand::RXParamSetupAns(v) => write!(
+                f,
+                "RXParamSetupAns(ChannelACK={}, RX2DataRateACK={}, RX1DROffsetACK={})",
+                v.channel_ack(),
+                v.rx2_data_rate_ack(),
+                v.rx1_dr_offset_ack()
+            ),
+            MacCommand::DevStatusReq(_) => f.write_str("DevStatusReq"),
+            MacCommand::DevStatusAns(v) => write!(
+                f,
+                "DevStatusAns(Battery={}, Margin={})",
+                v.battery(),
+                v.margin()
+            ),
+            MacCommand::NewChannelReq(v) => {
+                let dr_range = v.data_rate_range();
+                write!(
+                    f,
+                    "NewChannelReq(ChIndex={}, Frequency={}, MinDR={}, MaxDR={})",
+                    v.channel_index(),
+                    v.frequency().value(),
+                    dr_range.min_data_range(),
+                    dr_range.max_data_rate()
+                )
+            }
+            MacCommand::NewChannelAns(v) => write!(
+                f,
+                "NewChannelAns(ChannelFreqACK={}, DataRateRangeACK={})",
+                v.channel_freq_ack(),
+                v.data_rate_range_ack()
+            ),
+            MacCommand::RXTimingSetupReq(v) => write!(f, "RXTimingSetupReq(Delay={})", v.delay()),
+            MacCommand::RXTimingSetupAns(_) => f.write_str("RXTimingSetupAns"),
+            MacCommand::TxParamSetupReq(v) => write!(
+                f,
+                "TxParamSetupReq(DownlinkDwellTime={}, UplinkDwellTime={}, MaxEIRP={})",
+                v.downlink_dwell_time(),
+                v.uplink_dwell_time(),
+                v.max_eirp()
+            ),
+            MacCommand::TxParamSetupAns(_) => f.write_str("TxParamSetupAns"),
+            MacCommand::DlChannelReq(v) => write!(
+                f,
+                "DlChannelReq(ChIndex={}, Frequency={})",
+                v.channel_index(),
+                v.frequency().value()
+            ),
+            MacCommand::DlChannelAns(v) => write!(
+                f,
+                "DlChannelAns(ChannelFreqACK={}, UplinkFreqExistsACK={})",
+                v.channel_freq_ack(),
+                v.uplink_freq_exists_ack()
+            ),
+            MacCommand::DeviceTimeReq(_) => f.write_str("DeviceTimeReq"),
+            MacCommand::DeviceTimeAns(v) => write!(
+                f,
+                "DeviceTimeAns(Seconds={}, Fraction={})",
+                v.seconds(),
+                v.fraction()
+            ),
+            MacCommand::ResetInd(v) => write!(f, "ResetInd(Minor={})", v.minor()),
+            MacCommand::ResetConf(v) => write!(f, "ResetConf(Minor={})", v.minor()),
+            MacCommand::RekeyInd(v) => write!(f, "RekeyInd(Minor={})", v.minor()),
+            MacCommand::RekeyConf(v) => write!(f, "RekeyConf(Minor={})", v.minor()),
+            MacCommand::ADRParamSetupReq(v) => write!(
+                f,
+                "ADRParamSetupReq(LimitExp={}, DelayExp={})",
+                v.limit_exp(),
+                v.delay_exp()
+            ),
+            MacCommand::ADRParamSetupAns(_) => f.write_str("ADRParamSetupAns"),
+            MacCommand::ForceRejoinReq(v) => write!(
+                f,
+                "ForceRejoinReq(Period={}, MaxRetries={}, RejoinType={}, DataRate={})",
+                v.period(),
+                v.max_retries(),
+                v.rejoin_type(),
+                v.data_rate()
+            ),
+            MacCommand::RejoinParamSetupReq(v) => write!(
+                f,
+                "RejoinParamSetupReq(MaxTimeN={}, MaxCountN={})",
+                v.max_time_n(),
+                v.max_count_n()
+            ),
+            MacCommand::RejoinParamSetupAns(v) => {
+                write!(f, "RejoinParamSetupAns(TimeOK={})", v.time_ack())
+            }
+            MacCommand::PingSlotInfoReq(v) => {
+                write!(f, "PingSlotInfoReq(Periodicity={})", v.periodicity())
+            }
+            MacCommand::PingSlotInfoAns(_) => f.write_str("PingSlotInfoAns"),
+            MacCommand::PingSlotChannelReq(v) => write!(
+                f,
+                "PingSlotChannelReq(Frequency={}, DataRate={})",
+                v.frequency().value(),
+                v.data_rate()
+            ),
+            MacCommand::PingSlotChannelAns(v) => write!(
+                f,
+                "PingSlotChannelAns(ChannelFreqACK={}, DataRateACK={})",
+                v.channel_freq_ack(),
+                v.data_rate_ack()
+            ),
+            MacCommand::BeaconTimingReq(_) => f.write_str("BeaconTimingReq"),
+            MacCommand::BeaconTimingAns(v) => write!(
+                f,
+                "BeaconTimingAns(Delay={}, Channel={})",
+                v.delay(),
+                v.channel()
+            ),
+            MacCommand::BeaconFreqReq(v) => {
+                write!(f, "BeaconFreqReq(Frequency={})", v.frequency().value())
+            }
+            MacCommand::BeaconFreqAns(v) => {
+                write!(f, "BeaconFreqAns(BeaconFreqACK={})", v.beacon_freq_ack())
+            }
+            MacCommand::Proprietary(v) => write!(
+                f,
+                "Proprietary(CID=0x{:02x}, Payload={})",
+                v.cid(),
+                Hex(v.payload())
+            ),
+            MacCommand::Unknown { cid, rest } => {
+                write!(f, "Unknown(CID=0x{:02x}, Rest={})", cid, Hex(rest))
+            }
+        }
+    }
+}
+
+impl<T: AsRef<[u8]>, F> fmt::Display for DecryptedJoinAcceptPayload<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let dl_settings = self.dl_settings();
+        write!(
+            f,
+            "{} AppNonce={} NetID={} DevAddr={} RX1DROffset={} RX2DataRate={} RXDelay={}",
+            self.mhdr(),
+            ReversedHex(self.app_nonce().as_ref()),
+            ReversedHex(self.net_id().as_ref()),
+            ReversedHex(self.dev_addr().as_ref()),
+            dl_settings.rx1_dr_offset(),
+            dl_settings.rx2_data_rate(),
+            self.rx_delay()
+        )?;
+        if dl_settings.opt_neg() {
+            f.write_str(" OptNeg")?;
+        }
+        match (self.c_f_list_type(), self.c_f_list()) {
+            (Some(0), Some(c_f_list)) => {
+                f.write_str(" CFList=[")?;
+                for (i, freq) in c_f_list.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", freq.value())?;
+                }
+                f.write_str("]")?;
+            }
+            // e.g. the channel masks of type 1, shown as they are sent
+            (Some(c_f_list_type), _) => write!(
+                f,
+                " CFList(type={})={}",
+                c_f_list_type,
+                Hex(&self.as_bytes()[13..28])
+            )?,
+            (None, _) => (),
+        }
+        Ok(())
+    }
+}
+
+fn write_data_payload<D: DataHeader>(f: &mut fmt::Formatter, data: &D) -> fmt::Result {
+    write!(f, "{} {}", data.mhdr(), data.fhdr())?;
+    if let Some(f_port) = data.f_port() {
+        let len = data.as_data_bytes().len() - data.fhdr_length() - 6;
+        write!(f, " FPort={} len={}", f_port, len)?;
+    }
+    Ok(())
+}
+
+impl<T: AsRef<[u8]>, F: CryptoFactory> fmt::Display for PhyPayload<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PhyPayload::JoinRequest(jr) => write!(
+                f,
+                "{} AppEUI={} DevEUI={} DevNonce={}",
+                jr.mhdr(),
+                ReversedHex(jr.app_eui().as_ref()),
+                ReversedHex(jr.dev_eui().as_ref()),
+                ReversedHex(jr.dev_nonce().as_ref())
+            ),
+            PhyPayload::JoinAccept(JoinAcceptPayload::Encrypted(ja)) => {
+                write!(
+                    f,
+                    "{} (encrypted) len={}",
+                    ja.mhdr(),
+                    ja.as_bytes().len() - 1
+                )
+            }
+            PhyPayload::JoinAccept(JoinAcceptPayload::Decrypted(ja)) => write!(f, "{}", ja),
+            PhyPayload::RejoinRequest(RejoinRequestPayload::Type02(rr)) => write!(
+                f,
+                "{} {:?} NetID={} DevEUI={} RJcount0={}",
+                rr.mhdr(),
+                rr.rejoin_type(),
+                ReversedHex(rr.net_id().as_ref()),
+                ReversedHex(rr.dev_eui().as_ref()),
+                rr.rj_count()
+            ),
+            PhyPayload::RejoinRequest(RejoinRequestPayload::Type1(rr)) => write!(
+                f,
+                "{} {:?} JoinEUI={} DevEUI={} RJcount1={}",
+                rr.mhdr(),
+                rr.rejoin_type(),
+                ReversedHex(rr.join_eui().as_ref()),
+                ReversedHex(rr.dev_eui().as_ref()),
+                rr.rj_count()
+            ),
+            PhyPayload::Data(DataPayload::Encrypted(data)) => write_data_payload(f, data),
+            PhyPayload::Data(DataPayload::Decrypted(data)) => {
+                write_data_payload(f, data)?;
+                if let Ok(FRMPayload::MACCommands(cmds)) = data.frm_payload() {
+                    f.write_str(" MACCommands=")?;
+                    write_mac_commands(f, cmds.mac_commands())?;
+                }
+                Ok(())
+            }
+            PhyPayload::Proprietary(p) => {
+                write!(f, "{} len={}", p.mhdr(), p.payload().len())
+            }
+        }
+    }
+}
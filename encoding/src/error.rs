@@ -0,0 +1,96 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Provides the error type returned by the fallible operations of the crate.
+
+use core::fmt;
+
+/// Error represents the reasons for which parsing or creating a LoRaWAN payload can fail.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Error {
+    /// The data is shorter than the smallest acceptable payload.
+    TooShort,
+    /// The data does not have the length required by the payload type.
+    InvalidLength,
+    /// The MType of the MHDR is not acceptable for the requested payload type.
+    InvalidMType,
+    /// The type of the RejoinRequest is not known.
+    InvalidRejoinType,
+    /// The Major of the MHDR is not a supported LoRaWAN version.
+    UnsupportedMajor,
+    /// The MIC of the payload does not match the computed one.
+    InvalidMic,
+    /// A key needed for the operation was not provided.
+    MissingKey,
+    /// The FOptsLen of the FCtrl points outside of the payload.
+    InvalidFOptsLen,
+    /// The MAC commands do not fit in FOpts.
+    FOptsTooLarge,
+    /// The payload does not fit in the provided buffer.
+    PayloadTooLarge,
+    /// MAC commands in FRMPayload can not be sent together with application payload.
+    MacCommandsWithPayload,
+    /// FPort is needed when there is FRMPayload.
+    MissingFPort,
+    /// The MAC command identifier is not known for the given direction.
+    UnknownMacCommand(u8),
+    /// The MAC command payload does not have the length of the given MAC command.
+    InvalidMacCommandLength,
+    /// The buffer provided for serialization is too small.
+    BufferTooShort,
+    /// The CFList can contain at most 5 frequencies.
+    TooManyFrequencies,
+    /// The maximum data rate of a DataRateRange is smaller than the minimum.
+    InvalidDataRateRange,
+    /// A value is out of the range admissible for the field.
+    ValueOutOfRange,
+    /// The frame counter is further ahead of the last one than allowed.
+    FCntGapTooLarge,
+    /// The string is not a valid hex representation of bytes.
+    InvalidHex,
+    /// The string is not a valid base64 representation of bytes.
+    InvalidBase64,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::TooShort => f.write_str("insufficient number of bytes"),
+            Error::InvalidLength => f.write_str("invalid payload length"),
+            Error::InvalidMType => f.write_str("unsupported message type"),
+            Error::InvalidRejoinType => f.write_str("unsupported rejoin type"),
+            Error::UnsupportedMajor => f.write_str("unsupported major version"),
+            Error::InvalidMic => f.write_str("MIC did not match"),
+            Error::MissingKey => f.write_str("key needed for the operation was None"),
+            Error::InvalidFOptsLen => f.write_str("FOpts length exceeds the payload"),
+            Error::FOptsTooLarge => f.write_str("mac commands are too big for FOpts"),
+            Error::PayloadTooLarge => f.write_str("payload is too big"),
+            Error::MacCommandsWithPayload => {
+                f.write_str("mac commands in payload can not be send together with payload")
+            }
+            Error::MissingFPort => f.write_str("fport must be provided when there is FRMPayload"),
+            Error::UnknownMacCommand(cid) => write!(f, "unknown mac command 0x{:02x}", cid),
+            Error::InvalidMacCommandLength => f.write_str("incorrect size for mac command"),
+            Error::BufferTooShort => f.write_str("data slice is too short"),
+            Error::TooManyFrequencies => f.write_str("too many frequences"),
+            Error::InvalidDataRateRange => {
+                f.write_str("data rate range can not have max data rate smaller than min data rate")
+            }
+            Error::ValueOutOfRange => f.write_str("value out of range"),
+            Error::FCntGapTooLarge => f.write_str("frame counter gap is too large"),
+            Error::InvalidHex => f.write_str("invalid hex string"),
+            Error::InvalidBase64 => f.write_str("invalid base64 string"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
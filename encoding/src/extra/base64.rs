@@ -0,0 +1,82 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Provides standard base64 encoding and decoding of byte sequences.
+//!
+//! # Examples
+//!
+//! ```
+//! use lorawan_encoding::extra::base64;
+//!
+//! assert_eq!(base64::encode(&[0xe0, 0x01]), "4AE=");
+//! assert_eq!(base64::decode("4AE="), Ok(vec![0xe0, 0x01]));
+//! assert_eq!(base64::decode("4AE"), Ok(vec![0xe0, 0x01]));
+//! assert!(base64::decode("4A_=").is_err());
+//! ```
+
+pub extern crate std;
+
+use std::string::String;
+use std::vec::Vec;
+
+use crate::error::Error;
+
+const BASE64_MAP: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Gives the padded base64 representation of the provided bytes.
+pub fn encode(data: &[u8]) -> String {
+    let mut res = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let indices = [
+            b[0] >> 2,
+            (b[0] & 0x03) << 4 | b[1] >> 4,
+            (b[1] & 0x0f) << 2 | b[2] >> 6,
+            b[2] & 0x3f,
+        ];
+        for (i, index) in indices.iter().enumerate() {
+            if i <= chunk.len() {
+                res.push(BASE64_MAP[*index as usize] as char);
+            } else {
+                res.push('=');
+            }
+        }
+    }
+    res
+}
+
+/// Parses a base64 string, with or without padding.
+pub fn decode(input: &str) -> Result<Vec<u8>, Error> {
+    let values: Vec<u8> = input
+        .trim_end_matches('=')
+        .bytes()
+        .map(|c| {
+            BASE64_MAP
+                .iter()
+                .position(|m| *m == c)
+                .map(|p| p as u8)
+                .ok_or(Error::InvalidBase64)
+        })
+        .collect::<Result<_, _>>()?;
+    if values.len() % 4 == 1 {
+        return Err(Error::InvalidBase64);
+    }
+    let mut res = Vec::with_capacity(values.len() * 3 / 4);
+    for chunk in values.chunks(4) {
+        let v = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, c)| acc | u32::from(*c) << (18 - 6 * i));
+        res.extend_from_slice(&v.to_be_bytes()[1..chunk.len()]);
+    }
+    Ok(res)
+}
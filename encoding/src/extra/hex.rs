@@ -0,0 +1,62 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Provides hex encoding and decoding of byte sequences.
+//!
+//! # Examples
+//!
+//! ```
+//! use lorawan_encoding::extra::hex;
+//!
+//! assert_eq!(hex::encode(&[0x01, 0xab]), "01ab");
+//! assert_eq!(hex::encode_upper(&[0x01, 0xab]), "01AB");
+//! assert_eq!(hex::decode("01Ab"), Ok(vec![0x01, 0xab]));
+//! assert!(hex::decode("01a").is_err());
+//! ```
+
+pub extern crate std;
+
+use std::string::String;
+use std::vec::Vec;
+
+use crate::error::Error;
+
+const INT_TO_HEX_MAP: &[u8] = b"0123456789abcdef";
+
+const INT_TO_UPPER_HEX_MAP: &[u8] = b"0123456789ABCDEF";
+
+fn encode_with_map(data: &[u8], map: &[u8]) -> String {
+    let mut res = String::with_capacity(2 * data.len());
+    for b in data {
+        res.push(map[(b >> 4) as usize] as char);
+        res.push(map[(b & 0x0f) as usize] as char);
+    }
+    res
+}
+
+/// Gives the lower case hex representation of the provided bytes.
+pub fn encode(data: &[u8]) -> String {
+    encode_with_map(data, INT_TO_HEX_MAP)
+}
+
+/// Gives the upper case hex representation of the provided bytes.
+pub fn encode_upper(data: &[u8]) -> String {
+    encode_with_map(data, INT_TO_UPPER_HEX_MAP)
+}
+
+/// Parses a hex string made of lower or upper case digits.
+pub fn decode(input: &str) -> Result<Vec<u8>, Error> {
+    let digits: Vec<u8> = input
+        .chars()
+        .map(|c| c.to_digit(16).map(|d| d as u8).ok_or(Error::InvalidHex))
+        .collect::<Result<_, _>>()?;
+    if digits.len() & 1 != 0 {
+        return Err(Error::InvalidHex);
+    }
+    Ok(digits.chunks(2).map(|c| (c[0] << 4) | c[1]).collect())
+}
@@ -8,6 +8,10 @@
 
 pub extern crate std;
 
+pub mod base64;
 pub mod hasher;
+pub mod hex;
+#[cfg(feature = "serde")]
+pub mod serde;
 #[cfg(feature = "with-to-string")]
 pub mod to_string;
@@ -0,0 +1,769 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Provides serde support for the parsed LoRaWAN payloads.
+//!
+//! PhyPayload is serialized into a structured representation in which the EUIs, addresses,
+//! nonces, MICs and opaque byte sequences are given as hex strings, while the rest of the fields
+//! are given as numbers and booleans. As in the Display output, the EUIs, addresses and nonces
+//! are given most significant byte first, which is the reverse of the order in which they are
+//! transmitted. Such a representation can be deserialized back into a PhyPayload owning the bytes
+//! of the frame.
+//!
+//! # Examples
+//!
+//! ```
+//! use lorawan_encoding::default_crypto::DefaultFactory;
+//! use lorawan_encoding::parser::{parse, PhyPayload};
+//!
+//! let data = vec![0x40, 0x04, 0x03, 0x02, 0x01, 0x80, 0x01, 0x00, 0x01,
+//!     0xa6, 0x94, 0x64, 0x26, 0x15, 0xd6, 0xc3, 0xb5, 0x82];
+//! let phy = parse(data.clone()).unwrap();
+//! let json = serde_json::to_string(&phy).unwrap();
+//! assert!(json.contains("\"dev_addr\":\"01020304\""));
+//!
+//! let restored: PhyPayload<Vec<u8>, DefaultFactory> = serde_json::from_str(&json).unwrap();
+//! assert_eq!(restored.as_ref(), &data[..]);
+//! ```
+
+pub extern crate std;
+
+use std::string::String;
+use std::vec::Vec;
+
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::creator::{DataPayloadCreator, JoinAcceptCreator};
+use crate::error::Error;
+use crate::extra::hex;
+use crate::keys::{CryptoFactory, MIC};
+use crate::maccommandcreator::build_mac_commands;
+use crate::maccommands::{
+    mac_commands_len, DLSettings, Frequency, MacCommand, SerializableMacCommand,
+};
+use crate::parser::*;
+
+struct HexVisitor;
+
+impl<'de> Visitor<'de> for HexVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str("a hex string")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        hex::decode(v).map_err(E::custom)
+    }
+}
+
+/// Sequence of bytes that is (de)serialized as hex string.
+#[derive(Debug, Default, PartialEq)]
+struct HexBytes(Vec<u8>);
+
+impl Serialize for HexBytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for HexBytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(HexVisitor).map(HexBytes)
+    }
+}
+
+// the fixed length structs are (de)serialized most significant byte first
+macro_rules! fixed_len_struct_impl_serde {
+    (
+        $type:ident[$size:expr];
+    ) => {
+        impl<T: AsRef<[u8]>> Serialize for $type<T> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut data = [0; $size];
+                data.copy_from_slice(self.as_ref());
+                data.reverse();
+                serializer.serialize_str(&hex::encode(&data))
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $type<[u8; $size]> {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let bytes = deserializer.deserialize_str(HexVisitor)?;
+                if bytes.len() != $size {
+                    return Err(de::Error::invalid_length(bytes.len(), &stringify!($size)));
+                }
+                let mut data = [0; $size];
+                data.copy_from_slice(&bytes);
+                data.reverse();
+                Ok($type::new(data).unwrap())
+            }
+        }
+    };
+}
+
+fixed_len_struct_impl_serde! {
+    EUI64[8];
+}
+
+fixed_len_struct_impl_serde! {
+    DevNonce[2];
+}
+
+fixed_len_struct_impl_serde! {
+    AppNonce[3];
+}
+
+fixed_len_struct_impl_serde! {
+    DevAddr[4];
+}
+
+fixed_len_struct_impl_serde! {
+    NwkAddr[3];
+}
+
+impl Serialize for MIC {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for MIC {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = deserializer.deserialize_str(HexVisitor)?;
+        if bytes.len() != 4 {
+            return Err(de::Error::invalid_length(bytes.len(), &"4"));
+        }
+        let mut mic = MIC::default();
+        mic.0.copy_from_slice(&bytes);
+        Ok(mic)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct MHDRDoc {
+    mtype: MType,
+    major: Major,
+}
+
+impl Serialize for MHDR {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        MHDRDoc {
+            mtype: self.mtype(),
+            major: self.major(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for MHDR {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let doc = MHDRDoc::deserialize(deserializer)?;
+        let mtype = match doc.mtype {
+            MType::JoinRequest => 0,
+            MType::JoinAccept => 1,
+            MType::UnconfirmedDataUp => 2,
+            MType::UnconfirmedDataDown => 3,
+            MType::ConfirmedDataUp => 4,
+            MType::ConfirmedDataDown => 5,
+            MType::RejoinRequest => 6,
+            MType::Proprietary => 7,
+        };
+        let major = match doc.major {
+            Major::LoRaWANR1 => 0,
+            Major::RFU => 1,
+        };
+        Ok(MHDR::new(mtype << 5 | major))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct DLSettingsDoc {
+    opt_neg: bool,
+    rx1_dr_offset: u8,
+    rx2_data_rate: u8,
+}
+
+impl Serialize for DLSettings {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        DLSettingsDoc {
+            opt_neg: self.opt_neg(),
+            rx1_dr_offset: self.rx1_dr_offset(),
+            rx2_data_rate: self.rx2_data_rate(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DLSettings {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let doc = DLSettingsDoc::deserialize(deserializer)?;
+        if doc.rx1_dr_offset > 0x07 || doc.rx2_data_rate > 0x0f {
+            return Err(de::Error::custom(Error::ValueOutOfRange));
+        }
+        let opt_neg = if doc.opt_neg { 0x80 } else { 0 };
+        Ok(DLSettings::new(
+            opt_neg | doc.rx1_dr_offset << 4 | doc.rx2_data_rate,
+        ))
+    }
+}
+
+/// Frequency is serialized as its value in Hz.
+impl<'a> Serialize for Frequency<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.value())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct FCtrlDoc {
+    adr: bool,
+    #[serde(default)]
+    adr_ack_req: bool,
+    ack: bool,
+    #[serde(default)]
+    f_pending: bool,
+    #[serde(default)]
+    class_b: bool,
+    /// Only informative, as it is computed from the FOpts during deserialization.
+    #[serde(default)]
+    f_opts_len: u8,
+}
+
+impl From<&FCtrl> for FCtrlDoc {
+    fn from(v: &FCtrl) -> Self {
+        let class_b_or_f_pending = v.raw_value() & 0x10 != 0;
+        Self {
+            adr: v.adr(),
+            adr_ack_req: v.adr_ack_req(),
+            ack: v.ack(),
+            f_pending: v.f_pending(),
+            class_b: class_b_or_f_pending && !v.f_pending(),
+            f_opts_len: v.f_opts_len(),
+        }
+    }
+}
+
+impl FCtrlDoc {
+    fn raw_value(&self, f_opts_len: usize) -> u8 {
+        let mut res = f_opts_len as u8;
+        if self.adr {
+            res |= 0x80;
+        }
+        if self.adr_ack_req {
+            res |= 0x40;
+        }
+        if self.ack {
+            res |= 0x20;
+        }
+        if self.f_pending || self.class_b {
+            res |= 0x10;
+        }
+        res
+    }
+}
+
+impl Serialize for FCtrl {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        FCtrlDoc::from(self).serialize(serializer)
+    }
+}
+
+macro_rules! mac_command_name {
+    (
+        $cmd:expr, [$($name:ident,)*]
+    ) => {
+        match $cmd {
+            $(
+                MacCommand::$name(_) => stringify!($name),
+            )*
+            MacCommand::Proprietary(_) => "Proprietary",
+            MacCommand::Unknown { .. } => "Unknown",
+        }
+    };
+}
+
+#[derive(Serialize, Deserialize)]
+struct MacCommandDoc {
+    cid: u8,
+    /// Only informative, as the CID is enough to rebuild the MAC command.
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    payload: HexBytes,
+}
+
+impl From<&MacCommand<'_>> for MacCommandDoc {
+    fn from(v: &MacCommand<'_>) -> Self {
+        let name = mac_command_name!(
+            v,
+            [
+                LinkCheckReq,
+                LinkCheckAns,
+                LinkADRReq,
+                LinkADRAns,
+                DutyCycleReq,
+                DutyCycleAns,
+                RXParamSetupReq,
+                RXParamSetupAns,
+                DevStatusReq,
+                DevStatusAns,
+                NewChannelReq,
+                NewChannelAns,
+                RXTimingSetupReq,
+                RXTimingSetupAns,
+                TxParamSetupReq,
+                TxParamSetupAns,
+                DlChannelReq,
+                DlChannelAns,
+                DeviceTimeReq,
+                DeviceTimeAns,
+                ResetInd,
+                ResetConf,
+                RekeyInd,
+                RekeyConf,
+                ADRParamSetupReq,
+                ADRParamSetupAns,
+                ForceRejoinReq,
+                RejoinParamSetupReq,
+                RejoinParamSetupAns,
+                PingSlotInfoReq,
+                PingSlotInfoAns,
+                PingSlotChannelReq,
+                PingSlotChannelAns,
+                BeaconTimingReq,
+                BeaconTimingAns,
+                BeaconFreqReq,
+                BeaconFreqAns,
+            ]
+        );
+        Self {
+            cid: v.cid(),
+            name: name.into(),
+            payload: HexBytes(v.bytes().to_vec()),
+        }
+    }
+}
+
+impl<'a> Serialize for MacCommand<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        MacCommandDoc::from(self).serialize(serializer)
+    }
+}
+
+impl SerializableMacCommand for MacCommandDoc {
+    fn payload_bytes(&self) -> &[u8] {
+        &self.payload.0
+    }
+
+    fn cid(&self) -> u8 {
+        self.cid
+    }
+
+    fn payload_len(&self) -> usize {
+        self.payload.0.len()
+    }
+}
+
+fn as_serializable(cmds: &[MacCommandDoc]) -> Vec<&dyn SerializableMacCommand> {
+    cmds.iter()
+        .map(|c| c as &dyn SerializableMacCommand)
+        .collect()
+}
+
+#[derive(Serialize, Deserialize)]
+struct FHDRDoc {
+    dev_addr: DevAddr<[u8; 4]>,
+    fctrl: FCtrlDoc,
+    fcnt: u16,
+    #[serde(default)]
+    fopts: Vec<MacCommandDoc>,
+}
+
+impl From<&FHDR<'_>> for FHDRDoc {
+    fn from(v: &FHDR<'_>) -> Self {
+        Self {
+            dev_addr: v.dev_addr().to_owned(),
+            fctrl: FCtrlDoc::from(&v.fctrl()),
+            fcnt: v.fcnt(),
+            fopts: v.fopts().map(|c| MacCommandDoc::from(&c)).collect(),
+        }
+    }
+}
+
+impl<'a> Serialize for FHDR<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        FHDRDoc::from(self).serialize(serializer)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum FRMPayloadDoc {
+    Encrypted(HexBytes),
+    Data(HexBytes),
+    MacCommands(Vec<MacCommandDoc>),
+    None,
+}
+
+impl FRMPayloadDoc {
+    fn is_encrypted(&self) -> bool {
+        matches!(self, FRMPayloadDoc::Encrypted(_))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct DataDoc {
+    mhdr: MHDR,
+    fhdr: FHDRDoc,
+    f_port: Option<u8>,
+    frm_payload: FRMPayloadDoc,
+    mic: MIC,
+}
+
+impl DataDoc {
+    fn new<T: DataHeader>(data: &T, frm_payload: FRMPayloadDoc) -> Self {
+        Self {
+            mhdr: data.mhdr(),
+            fhdr: FHDRDoc::from(&data.fhdr()),
+            f_port: data.f_port(),
+            frm_payload,
+            mic: data.mic(),
+        }
+    }
+
+    // The layout is left to DataPayloadCreator, which gets the FRMPayload and the MIC as they are
+    // instead of encrypting the payload and computing the MIC.
+    fn to_bytes<F: CryptoFactory + Default>(&self) -> Result<Vec<u8>, Error> {
+        if self.mhdr.major() != Major::LoRaWANR1 {
+            return Err(Error::UnsupportedMajor);
+        }
+        let (uplink, confirmed) = match self.mhdr.mtype() {
+            MType::UnconfirmedDataUp => (true, false),
+            MType::ConfirmedDataUp => (true, true),
+            MType::UnconfirmedDataDown => (false, false),
+            MType::ConfirmedDataDown => (false, true),
+            _ => return Err(Error::InvalidMType),
+        };
+        let mut phy = DataPayloadCreator::with_options([0; 256], F::default())?;
+        phy.set_uplink(uplink)
+            .set_confirmed(confirmed)
+            .set_dev_addr(self.fhdr.dev_addr)
+            .set_fctrl(&FCtrl::new(self.fhdr.fctrl.raw_value(0), uplink))
+            .set_fcnt(u32::from(self.fhdr.fcnt));
+        if let Some(f_port) = self.f_port {
+            phy.set_f_port(f_port);
+        }
+        let frm_payload = match &self.frm_payload {
+            FRMPayloadDoc::Encrypted(data) | FRMPayloadDoc::Data(data) => data.0.clone(),
+            FRMPayloadDoc::MacCommands(cmds) => {
+                let cmds = as_serializable(cmds);
+                let mut res = std::vec![0; mac_commands_len(&cmds)];
+                build_mac_commands(&cmds, &mut res[..])?;
+                res
+            }
+            FRMPayloadDoc::None => Vec::new(),
+        };
+        let f_opts = as_serializable(&self.fhdr.fopts);
+        Ok(phy
+            .build_with_mic(&frm_payload, &f_opts, &self.mic)?
+            .to_vec())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct JoinRequestDoc {
+    mhdr: MHDR,
+    app_eui: EUI64<[u8; 8]>,
+    dev_eui: EUI64<[u8; 8]>,
+    dev_nonce: DevNonce<[u8; 2]>,
+    mic: MIC,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum JoinAcceptDoc {
+    /// The encrypted bytes following the MHDR, including the MIC.
+    Encrypted { mhdr: MHDR, payload: HexBytes },
+    Decrypted {
+        mhdr: MHDR,
+        app_nonce: AppNonce<[u8; 3]>,
+        net_id: NwkAddr<[u8; 3]>,
+        dev_addr: DevAddr<[u8; 4]>,
+        dl_settings: DLSettings,
+        rx_delay: u8,
+        #[serde(default)]
+        cf_list: Option<CFListDoc>,
+        mic: MIC,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum CFListDoc {
+    /// The frequencies of a CFList of type 0, given in Hz.
+    Frequencies(Vec<u32>),
+    /// The 16 bytes of a CFList of any other type, e.g. the channel masks of US915.
+    Raw(HexBytes),
+}
+
+// converts a frequency in Hz to its three bytes representation in units of 100 Hz
+fn frequency_bytes(hz: u32) -> Result<[u8; 3], Error> {
+    let value = hz / 100;
+    if value * 100 != hz || value >= 1 << 24 {
+        return Err(Error::ValueOutOfRange);
+    }
+    let bytes = value.to_le_bytes();
+    Ok([bytes[0], bytes[1], bytes[2]])
+}
+
+impl CFListDoc {
+    fn new<T: AsRef<[u8]>, F>(ja: &DecryptedJoinAcceptPayload<T, F>) -> Option<Self> {
+        match ja.c_f_list_type()? {
+            0 => ja
+                .c_f_list()
+                .map(|l| CFListDoc::Frequencies(l.iter().map(|f| f.value()).collect())),
+            _ => {
+                let bytes = ja.as_bytes();
+                Some(CFListDoc::Raw(HexBytes(bytes[13..29].to_vec())))
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RejoinRequestDoc {
+    Type02 {
+        mhdr: MHDR,
+        rejoin_type: RejoinType,
+        net_id: NwkAddr<[u8; 3]>,
+        dev_eui: EUI64<[u8; 8]>,
+        rj_count: u16,
+        mic: MIC,
+    },
+    Type1 {
+        mhdr: MHDR,
+        join_eui: EUI64<[u8; 8]>,
+        dev_eui: EUI64<[u8; 8]>,
+        rj_count: u16,
+        mic: MIC,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProprietaryDoc {
+    mhdr: MHDR,
+    payload: HexBytes,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum PhyPayloadDoc {
+    JoinRequest(JoinRequestDoc),
+    JoinAccept(JoinAcceptDoc),
+    RejoinRequest(RejoinRequestDoc),
+    Data(DataDoc),
+    Proprietary(ProprietaryDoc),
+}
+
+impl<T: AsRef<[u8]>, F: CryptoFactory> From<&PhyPayload<T, F>> for PhyPayloadDoc {
+    fn from(v: &PhyPayload<T, F>) -> Self {
+        match v {
+            PhyPayload::JoinRequest(jr) => PhyPayloadDoc::JoinRequest(JoinRequestDoc {
+                mhdr: jr.mhdr(),
+                app_eui: jr.app_eui().to_owned(),
+                dev_eui: jr.dev_eui().to_owned(),
+                dev_nonce: jr.dev_nonce().to_owned(),
+                mic: jr.mic(),
+            }),
+            PhyPayload::JoinAccept(JoinAcceptPayload::Encrypted(ja)) => {
+                PhyPayloadDoc::JoinAccept(JoinAcceptDoc::Encrypted {
+                    mhdr: ja.mhdr(),
+                    payload: HexBytes(ja.as_bytes()[1..].to_vec()),
+                })
+            }
+            PhyPayload::JoinAccept(JoinAcceptPayload::Decrypted(ja)) => {
+                PhyPayloadDoc::JoinAccept(JoinAcceptDoc::Decrypted {
+                    mhdr: ja.mhdr(),
+                    app_nonce: ja.app_nonce().to_owned(),
+                    net_id: ja.net_id().to_owned(),
+                    dev_addr: ja.dev_addr().to_owned(),
+                    dl_settings: ja.dl_settings(),
+                    rx_delay: ja.rx_delay(),
+                    cf_list: CFListDoc::new(ja),
+                    mic: ja.mic(),
+                })
+            }
+            PhyPayload::RejoinRequest(RejoinRequestPayload::Type02(rr)) => {
+                PhyPayloadDoc::RejoinRequest(RejoinRequestDoc::Type02 {
+                    mhdr: rr.mhdr(),
+                    rejoin_type: rr.rejoin_type(),
+                    net_id: rr.net_id().to_owned(),
+                    dev_eui: rr.dev_eui().to_owned(),
+                    rj_count: rr.rj_count(),
+                    mic: rr.mic(),
+                })
+            }
+            PhyPayload::RejoinRequest(RejoinRequestPayload::Type1(rr)) => {
+                PhyPayloadDoc::RejoinRequest(RejoinRequestDoc::Type1 {
+                    mhdr: rr.mhdr(),
+                    join_eui: rr.join_eui().to_owned(),
+                    dev_eui: rr.dev_eui().to_owned(),
+                    rj_count: rr.rj_count(),
+                    mic: rr.mic(),
+                })
+            }
+            PhyPayload::Data(DataPayload::Encrypted(data)) => {
+                let bytes = data.as_data_bytes();
+                let start = 1 + data.fhdr_length() + if data.f_port().is_some() { 1 } else { 0 };
+                let frm_payload = HexBytes(bytes[start..bytes.len() - 4].to_vec());
+                PhyPayloadDoc::Data(DataDoc::new(data, FRMPayloadDoc::Encrypted(frm_payload)))
+            }
+            PhyPayload::Data(DataPayload::Decrypted(data)) => {
+                let frm_payload = match data.frm_payload() {
+                    Ok(FRMPayload::Data(d)) => FRMPayloadDoc::Data(HexBytes(d.to_vec())),
+                    Ok(FRMPayload::MACCommands(cmds)) => FRMPayloadDoc::MacCommands(
+                        cmds.mac_commands()
+                            .map(|c| MacCommandDoc::from(&c))
+                            .collect(),
+                    ),
+                    _ => FRMPayloadDoc::None,
+                };
+                PhyPayloadDoc::Data(DataDoc::new(data, frm_payload))
+            }
+            PhyPayload::Proprietary(p) => PhyPayloadDoc::Proprietary(ProprietaryDoc {
+                mhdr: p.mhdr(),
+                payload: HexBytes(p.payload().to_vec()),
+            }),
+        }
+    }
+}
+
+impl PhyPayloadDoc {
+    fn into_phy_payload<F: CryptoFactory + Default>(self) -> Result<PhyPayload<Vec<u8>, F>, Error> {
+        let mut res = Vec::new();
+        match self {
+            PhyPayloadDoc::JoinRequest(jr) => {
+                res.push(jr.mhdr.raw_value());
+                res.extend_from_slice(jr.app_eui.as_ref());
+                res.extend_from_slice(jr.dev_eui.as_ref());
+                res.extend_from_slice(jr.dev_nonce.as_ref());
+                res.extend_from_slice(&jr.mic.0);
+            }
+            PhyPayloadDoc::JoinAccept(JoinAcceptDoc::Encrypted { mhdr, payload }) => {
+                res.push(mhdr.raw_value());
+                res.extend_from_slice(&payload.0);
+            }
+            PhyPayloadDoc::JoinAccept(JoinAcceptDoc::Decrypted {
+                mhdr,
+                app_nonce,
+                net_id,
+                dev_addr,
+                dl_settings,
+                rx_delay,
+                cf_list,
+                mic,
+            }) => {
+                if mhdr.mtype() != MType::JoinAccept {
+                    return Err(Error::InvalidMType);
+                }
+                if mhdr.major() != Major::LoRaWANR1 {
+                    return Err(Error::UnsupportedMajor);
+                }
+                let mut phy = JoinAcceptCreator::with_options([0; 33], F::default())?;
+                phy.set_app_nonce(app_nonce)
+                    .set_net_id(net_id)
+                    .set_dev_addr(dev_addr)
+                    .set_dl_settings(dl_settings)
+                    .set_rx_delay(rx_delay);
+                match cf_list {
+                    Some(CFListDoc::Frequencies(freqs)) => {
+                        let bytes = freqs
+                            .iter()
+                            .map(|f| frequency_bytes(*f))
+                            .collect::<Result<Vec<_>, _>>()?;
+                        let freqs: Vec<Frequency> = bytes.iter().map(Frequency::from).collect();
+                        phy.set_c_f_list(freqs)?;
+                    }
+                    Some(CFListDoc::Raw(bytes)) => {
+                        phy.set_raw_c_f_list(&bytes.0)?;
+                    }
+                    None => (),
+                }
+                return Ok(PhyPayload::JoinAccept(JoinAcceptPayload::Decrypted(
+                    DecryptedJoinAcceptPayload::new_from_raw(
+                        phy.build_with_mic(&mic).to_vec(),
+                        F::default(),
+                    ),
+                )));
+            }
+            PhyPayloadDoc::RejoinRequest(RejoinRequestDoc::Type02 {
+                mhdr,
+                rejoin_type,
+                net_id,
+                dev_eui,
+                rj_count,
+                mic,
+            }) => {
+                res.push(mhdr.raw_value());
+                res.push(rejoin_type.into());
+                res.extend_from_slice(net_id.as_ref());
+                res.extend_from_slice(dev_eui.as_ref());
+                res.extend_from_slice(&rj_count.to_le_bytes());
+                res.extend_from_slice(&mic.0);
+            }
+            PhyPayloadDoc::RejoinRequest(RejoinRequestDoc::Type1 {
+                mhdr,
+                join_eui,
+                dev_eui,
+                rj_count,
+                mic,
+            }) => {
+                res.push(mhdr.raw_value());
+                res.push(RejoinType::Type1.into());
+                res.extend_from_slice(join_eui.as_ref());
+                res.extend_from_slice(dev_eui.as_ref());
+                res.extend_from_slice(&rj_count.to_le_bytes());
+                res.extend_from_slice(&mic.0);
+            }
+            PhyPayloadDoc::Data(data) => {
+                let res = data.to_bytes::<F>()?;
+                if !data.frm_payload.is_encrypted() {
+                    EncryptedDataPayload::new_with_factory(&res[..], F::default())?;
+                    return Ok(PhyPayload::Data(DataPayload::Decrypted(
+                        DecryptedDataPayload::new_from_raw(res),
+                    )));
+                }
+                return parse_with_factory(res, F::default());
+            }
+            PhyPayloadDoc::Proprietary(p) => {
+                res.push(p.mhdr.raw_value());
+                res.extend_from_slice(&p.payload.0);
+            }
+        }
+        parse_with_factory(res, F::default())
+    }
+}
+
+impl<T: AsRef<[u8]>, F: CryptoFactory> Serialize for PhyPayload<T, F> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        PhyPayloadDoc::from(self).serialize(serializer)
+    }
+}
+
+/// The bytes of the PhyPayload are rebuilt from the deserialized fields, including the MIC, so no
+/// keys are needed. Decrypted JoinAccept and data payloads are rebuilt as decrypted payloads.
+impl<'de, F: CryptoFactory + Default> Deserialize<'de> for PhyPayload<Vec<u8>, F> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        PhyPayloadDoc::deserialize(deserializer)?
+            .into_phy_payload()
+            .map_err(de::Error::custom)
+    }
+}
@@ -8,6 +8,9 @@
 
 use generic_array::{typenum::U16, GenericArray};
 
+use super::parser::EUI64;
+use super::securityhelpers;
+
 /// AES128 represents 128 bit AES key.
 #[derive(Debug, Default, PartialEq, Clone, Copy)]
 pub struct AES128(pub [u8; 16]);
@@ -62,3 +65,59 @@ pub trait CryptoFactory {
     /// Method that creates a MAC calculator.
     fn new_mac(&self, key: &AES128) -> Self::M;
 }
+
+/// Computes the JSIntKey used for the MIC of LoRaWAN 1.1 JoinAccept and RejoinRequest type 1.
+///
+/// # Argument
+///
+/// * nwk_key - the network key.
+/// * dev_eui - the DevEUI of the device.
+/// * factory - the factory that shall be used to create object for crypto functions.
+///
+/// # Examples
+///
+/// ```
+/// let nwk_key = lorawan_encoding::keys::AES128([1; 16]);
+/// let dev_eui = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+/// let js_int_key = lorawan_encoding::keys::derive_jsintkey(
+///     &nwk_key,
+///     &lorawan_encoding::parser::EUI64::from(&dev_eui),
+///     &lorawan_encoding::default_crypto::DefaultFactory,
+/// );
+/// ```
+pub fn derive_jsintkey<T: AsRef<[u8]>, F: CryptoFactory>(
+    nwk_key: &AES128,
+    dev_eui: &EUI64<T>,
+    factory: &F,
+) -> AES128 {
+    derive_join_server_key(0x06, nwk_key, dev_eui, factory)
+}
+
+/// Computes the JSEncKey used for the encryption of LoRaWAN 1.1 JoinAccept answering a
+/// RejoinRequest.
+///
+/// # Argument
+///
+/// * nwk_key - the network key.
+/// * dev_eui - the DevEUI of the device.
+/// * factory - the factory that shall be used to create object for crypto functions.
+pub fn derive_jsenckey<T: AsRef<[u8]>, F: CryptoFactory>(
+    nwk_key: &AES128,
+    dev_eui: &EUI64<T>,
+    factory: &F,
+) -> AES128 {
+    derive_join_server_key(0x05, nwk_key, dev_eui, factory)
+}
+
+fn derive_join_server_key<T: AsRef<[u8]>, F: CryptoFactory>(
+    first_byte: u8,
+    nwk_key: &AES128,
+    dev_eui: &EUI64<T>,
+    factory: &F,
+) -> AES128 {
+    let mut block = [0u8; 9];
+    block[0] = first_byte;
+    block[1..9].copy_from_slice(dev_eui.as_ref());
+
+    securityhelpers::derive_key(&factory.new_enc(nwk_key), &block[..])
+}
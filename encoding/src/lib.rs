@@ -9,16 +9,20 @@
 //! This module implements LoRaWAN packet handling and parsing.
 
 #![no_std]
+pub mod adr;
 pub mod creator;
+pub mod error;
 pub mod keys;
 pub mod maccommandcreator;
+pub mod maccommandqueue;
 pub mod maccommands;
 pub mod parser;
 
-#[cfg(feature = "full")]
+#[cfg(any(feature = "full", feature = "serde"))]
 pub mod extra;
 
 #[cfg(feature = "default-crypto")]
 pub mod default_crypto;
 
+mod display;
 mod securityhelpers;
@@ -6,6 +6,7 @@
 //
 // Author: Ivaylo Petrov <ivajloip@gmail.com>
 
+use super::error::Error;
 use super::maccommands::*;
 
 macro_rules! impl_mac_cmd_creator_boilerplate {
@@ -26,6 +27,11 @@ macro_rules! impl_mac_cmd_creator_boilerplate {
             pub fn build(&self) -> &[u8] {
                 &[$cid]
             }
+
+            /// Creates a new instance of the class from the payload of a parsed mac command.
+            pub(crate) fn from_payload(_: &[u8]) -> Self {
+                Self {}
+            }
         }
 
         impl_mac_cmd_payload!($type);
@@ -51,6 +57,16 @@ macro_rules! impl_mac_cmd_creator_boilerplate {
             pub fn build(&self) -> &[u8] {
                 &self.data[..]
             }
+
+            /// Creates a new instance of the class from the payload of a parsed mac command.
+            ///
+            /// Parsed payloads can be longer than the mac command, the extra bytes are ignored.
+            pub(crate) fn from_payload(payload: &[u8]) -> Self {
+                let mut data = [0; $len];
+                data[0] = $cid;
+                data[1..].copy_from_slice(&payload[..$len - 1]);
+                Self { data }
+            }
         }
 
         impl_mac_cmd_payload!($type);
@@ -78,6 +94,68 @@ macro_rules! impl_mac_cmd_payload {
     };
 }
 
+/// ResetIndCreator serves for creating ResetInd MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let mut creator = lorawan_encoding::maccommandcreator::ResetIndCreator::new();
+/// let res = creator.set_minor(1).unwrap().build();
+/// ```
+pub struct ResetIndCreator {
+    data: [u8; 2],
+}
+
+impl_mac_cmd_creator_boilerplate!(ResetIndCreator, 0x01, 2);
+
+impl ResetIndCreator {
+    /// Sets the minor version of the ResetInd to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * minor - the minor version of LoRaWAN supported by the end-device, 1 meaning LoRaWAN 1.1.
+    ///   The value must be between 0 and 15.
+    pub fn set_minor(&mut self, minor: u8) -> Result<&mut Self, Error> {
+        if minor > 0x0f {
+            return Err(Error::ValueOutOfRange);
+        }
+        self.data[1] = minor;
+
+        Ok(self)
+    }
+}
+
+/// ResetConfCreator serves for creating ResetConf MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let mut creator = lorawan_encoding::maccommandcreator::ResetConfCreator::new();
+/// let res = creator.set_minor(1).unwrap().build();
+/// ```
+pub struct ResetConfCreator {
+    data: [u8; 2],
+}
+
+impl_mac_cmd_creator_boilerplate!(ResetConfCreator, 0x01, 2);
+
+impl ResetConfCreator {
+    /// Sets the minor version of the ResetConf to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * minor - the minor version of LoRaWAN supported by the network server, 1 meaning LoRaWAN 1.1.
+    ///   The value must be between 0 and 15.
+    pub fn set_minor(&mut self, minor: u8) -> Result<&mut Self, Error> {
+        if minor > 0x0f {
+            return Err(Error::ValueOutOfRange);
+        }
+        self.data[1] = minor;
+
+        Ok(self)
+    }
+}
+
 /// LinkCheckReqCreator serves for creating LinkCheckReq MacCommand.
 ///
 /// # Examples
@@ -157,9 +235,9 @@ impl LinkADRReqCreator {
     /// # Argument
     ///
     /// * data_rate - data rate index of the ADR request. The value must be between 0 and 15.
-    pub fn set_data_rate(&mut self, data_rate: u8) -> Result<&mut Self, &str> {
+    pub fn set_data_rate(&mut self, data_rate: u8) -> Result<&mut Self, Error> {
         if data_rate > 0x0f {
-            return Err("data_rate out of range");
+            return Err(Error::ValueOutOfRange);
         }
         self.data[1] &= 0x0f;
         self.data[1] |= data_rate << 4;
@@ -172,9 +250,9 @@ impl LinkADRReqCreator {
     /// # Argument
     ///
     /// * tx_power - TX power index. The value must be between 0 and 15.
-    pub fn set_tx_power(&mut self, tx_power: u8) -> Result<&mut Self, &str> {
+    pub fn set_tx_power(&mut self, tx_power: u8) -> Result<&mut Self, Error> {
         if tx_power > 0x0f {
-            return Err("tx_power out of range");
+            return Err(Error::ValueOutOfRange);
         }
         self.data[1] &= 0xf0;
         self.data[1] |= tx_power & 0x0f;
@@ -287,7 +365,7 @@ impl DutyCycleReqCreator {
     ///
     /// * max_duty_cycle - the value used to determine the aggregated duty cycle using the formula
     /// `1 / (2 ** max_duty_cycle)`.
-    pub fn set_max_duty_cycle(&mut self, max_duty_cycle: u8) -> Result<&mut Self, &str> {
+    pub fn set_max_duty_cycle(&mut self, max_duty_cycle: u8) -> Result<&mut Self, Error> {
         self.data[1] = max_duty_cycle;
 
         Ok(self)
@@ -452,9 +530,9 @@ impl DevStatusAnsCreator {
     /// # Argument
     ///
     /// * margin - the value to be used as margin.
-    pub fn set_margin(&mut self, margin: i8) -> Result<&mut Self, &str> {
+    pub fn set_margin(&mut self, margin: i8) -> Result<&mut Self, Error> {
         if margin < -32 || margin > 31 {
-            return Err("margin out of range");
+            return Err(Error::ValueOutOfRange);
         }
         self.data[2] = ((margin << 2) as u8) >> 2;
 
@@ -581,9 +659,9 @@ impl RXTimingSetupReqCreator {
     /// # Argument
     ///
     /// * delay - the value to be used as delay.
-    pub fn set_delay(&mut self, delay: u8) -> Result<&mut Self, &str> {
+    pub fn set_delay(&mut self, delay: u8) -> Result<&mut Self, Error> {
         if delay > 0x0f {
-            return Err("delay out of range");
+            return Err(Error::ValueOutOfRange);
         }
         self.data[1] &= 0xf0;
         self.data[1] |= delay;
@@ -604,13 +682,754 @@ pub struct RXTimingSetupAnsCreator {}
 
 impl_mac_cmd_creator_boilerplate!(RXTimingSetupAnsCreator, 0x08);
 
-pub fn build_mac_commands<'a, 'b, 'c, T: AsMut<[u8]>>(
-    cmds: &'a [&'b dyn SerializableMacCommand],
+/// TxParamSetupReqCreator serves for creating TxParamSetupReq MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let mut creator = lorawan_encoding::maccommandcreator::TxParamSetupReqCreator::new();
+/// let res = creator
+///     .set_downlink_dwell_time(true)
+///     .set_uplink_dwell_time(true)
+///     .set_max_eirp(0x05)
+///     .unwrap()
+///     .build();
+/// ```
+pub struct TxParamSetupReqCreator {
+    data: [u8; 2],
+}
+
+impl_mac_cmd_creator_boilerplate!(TxParamSetupReqCreator, 0x09, 2);
+
+impl TxParamSetupReqCreator {
+    /// Sets the downlink dwell time of the TxParamSetupReq to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * limited - true meaning that the downlink dwell time is limited to 400 ms or false
+    ///   otherwise.
+    pub fn set_downlink_dwell_time(&mut self, limited: bool) -> &mut Self {
+        self.data[1] &= 0xdf;
+        self.data[1] |= (limited as u8) << 5;
+
+        self
+    }
+
+    /// Sets the uplink dwell time of the TxParamSetupReq to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * limited - true meaning that the uplink dwell time is limited to 400 ms or false
+    ///   otherwise.
+    pub fn set_uplink_dwell_time(&mut self, limited: bool) -> &mut Self {
+        self.data[1] &= 0xef;
+        self.data[1] |= (limited as u8) << 4;
+
+        self
+    }
+
+    /// Sets the max EIRP of the TxParamSetupReq to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * max_eirp - index in the table of maximum EIRP values, 0 meaning 8 dBm and 15 meaning
+    ///   36 dBm. The value must be between 0 and 15.
+    pub fn set_max_eirp(&mut self, max_eirp: u8) -> Result<&mut Self, Error> {
+        if max_eirp > 0x0f {
+            return Err(Error::ValueOutOfRange);
+        }
+        self.data[1] &= 0xf0;
+        self.data[1] |= max_eirp;
+
+        Ok(self)
+    }
+}
+
+/// TxParamSetupAnsCreator serves for creating TxParamSetupAns MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let creator = lorawan_encoding::maccommandcreator::TxParamSetupAnsCreator::new();
+/// let res = creator.build();
+/// ```
+pub struct TxParamSetupAnsCreator {}
+
+impl_mac_cmd_creator_boilerplate!(TxParamSetupAnsCreator, 0x09);
+
+/// DlChannelReqCreator serves for creating DlChannelReq MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let mut creator = lorawan_encoding::maccommandcreator::DlChannelReqCreator::new();
+/// let res = creator
+///     .set_channel_index(0x0f)
+///     .set_frequency(&[0x12, 0x34, 0x56])
+///     .build();
+/// ```
+pub struct DlChannelReqCreator {
+    data: [u8; 5],
+}
+
+impl_mac_cmd_creator_boilerplate!(DlChannelReqCreator, 0x0a, 5);
+
+impl DlChannelReqCreator {
+    /// Sets the channel index of the DlChannelReq to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * channel_index - the index of the channel whose downlink frequency is modified.
+    pub fn set_channel_index(&mut self, channel_index: u8) -> &mut Self {
+        self.data[1] = channel_index;
+
+        self
+    }
+
+    /// Sets the frequency of the DlChannelReq to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * frequency - instance of maccommands::Frequency or anything that can be converted
+    ///   into it.
+    pub fn set_frequency<'a, T: Into<Frequency<'a>>>(&mut self, frequency: T) -> &mut Self {
+        let converted = frequency.into();
+        self.data[2..5].copy_from_slice(converted.as_ref());
+
+        self
+    }
+}
+
+/// DlChannelAnsCreator serves for creating DlChannelAns MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let mut creator = lorawan_encoding::maccommandcreator::DlChannelAnsCreator::new();
+/// let res = creator
+///     .set_channel_frequency_ack(true)
+///     .set_uplink_frequency_exists_ack(true)
+///     .build();
+/// ```
+pub struct DlChannelAnsCreator {
+    data: [u8; 2],
+}
+
+impl_mac_cmd_creator_boilerplate!(DlChannelAnsCreator, 0x0a, 2);
+
+impl DlChannelAnsCreator {
+    /// Sets the channel frequency acknowledgement of the DlChannelAns to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * ack - true meaning that the downlink frequency is usable by the device or false
+    ///   otherwise.
+    pub fn set_channel_frequency_ack(&mut self, ack: bool) -> &mut Self {
+        self.data[1] &= 0xfe;
+        self.data[1] |= ack as u8;
+
+        self
+    }
+
+    /// Sets the uplink frequency exists acknowledgement of the DlChannelAns to the provided
+    /// value.
+    ///
+    /// # Argument
+    ///
+    /// * ack - true meaning that the uplink frequency of the channel is valid or false otherwise.
+    pub fn set_uplink_frequency_exists_ack(&mut self, ack: bool) -> &mut Self {
+        self.data[1] &= 0xfd;
+        self.data[1] |= (ack as u8) << 1;
+
+        self
+    }
+}
+
+/// RekeyIndCreator serves for creating RekeyInd MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let mut creator = lorawan_encoding::maccommandcreator::RekeyIndCreator::new();
+/// let res = creator.set_minor(1).unwrap().build();
+/// ```
+pub struct RekeyIndCreator {
+    data: [u8; 2],
+}
+
+impl_mac_cmd_creator_boilerplate!(RekeyIndCreator, 0x0b, 2);
+
+impl RekeyIndCreator {
+    /// Sets the minor version of the RekeyInd to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * minor - the minor version of LoRaWAN supported by the end-device, 1 meaning LoRaWAN 1.1.
+    ///   The value must be between 0 and 15.
+    pub fn set_minor(&mut self, minor: u8) -> Result<&mut Self, Error> {
+        if minor > 0x0f {
+            return Err(Error::ValueOutOfRange);
+        }
+        self.data[1] = minor;
+
+        Ok(self)
+    }
+}
+
+/// RekeyConfCreator serves for creating RekeyConf MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let mut creator = lorawan_encoding::maccommandcreator::RekeyConfCreator::new();
+/// let res = creator.set_minor(1).unwrap().build();
+/// ```
+pub struct RekeyConfCreator {
+    data: [u8; 2],
+}
+
+impl_mac_cmd_creator_boilerplate!(RekeyConfCreator, 0x0b, 2);
+
+impl RekeyConfCreator {
+    /// Sets the minor version of the RekeyConf to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * minor - the minor version of LoRaWAN supported by the network server, 1 meaning LoRaWAN 1.1.
+    ///   The value must be between 0 and 15.
+    pub fn set_minor(&mut self, minor: u8) -> Result<&mut Self, Error> {
+        if minor > 0x0f {
+            return Err(Error::ValueOutOfRange);
+        }
+        self.data[1] = minor;
+
+        Ok(self)
+    }
+}
+
+/// ADRParamSetupReqCreator serves for creating ADRParamSetupReq MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let mut creator = lorawan_encoding::maccommandcreator::ADRParamSetupReqCreator::new();
+/// let res = creator
+///     .set_limit_exp(0x06)
+///     .unwrap()
+///     .set_delay_exp(0x05)
+///     .unwrap()
+///     .build();
+/// ```
+pub struct ADRParamSetupReqCreator {
+    data: [u8; 2],
+}
+
+impl_mac_cmd_creator_boilerplate!(ADRParamSetupReqCreator, 0x0c, 2);
+
+impl ADRParamSetupReqCreator {
+    /// Sets the limit exponent of the ADRParamSetupReq to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * limit_exp - the exponent used to compute ADR_ACK_LIMIT as `2 ** limit_exp`. The value
+    ///   must be between 0 and 15.
+    pub fn set_limit_exp(&mut self, limit_exp: u8) -> Result<&mut Self, Error> {
+        if limit_exp > 0x0f {
+            return Err(Error::ValueOutOfRange);
+        }
+        self.data[1] &= 0x0f;
+        self.data[1] |= limit_exp << 4;
+
+        Ok(self)
+    }
+
+    /// Sets the delay exponent of the ADRParamSetupReq to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * delay_exp - the exponent used to compute ADR_ACK_DELAY as `2 ** delay_exp`. The value
+    ///   must be between 0 and 15.
+    pub fn set_delay_exp(&mut self, delay_exp: u8) -> Result<&mut Self, Error> {
+        if delay_exp > 0x0f {
+            return Err(Error::ValueOutOfRange);
+        }
+        self.data[1] &= 0xf0;
+        self.data[1] |= delay_exp;
+
+        Ok(self)
+    }
+}
+
+/// ADRParamSetupAnsCreator serves for creating ADRParamSetupAns MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let creator = lorawan_encoding::maccommandcreator::ADRParamSetupAnsCreator::new();
+/// let res = creator.build();
+/// ```
+pub struct ADRParamSetupAnsCreator {}
+
+impl_mac_cmd_creator_boilerplate!(ADRParamSetupAnsCreator, 0x0c);
+
+/// DeviceTimeReqCreator serves for creating DeviceTimeReq MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let creator = lorawan_encoding::maccommandcreator::DeviceTimeReqCreator::new();
+/// let res = creator.build();
+/// ```
+pub struct DeviceTimeReqCreator {}
+
+impl_mac_cmd_creator_boilerplate!(DeviceTimeReqCreator, 0x0d);
+
+/// DeviceTimeAnsCreator serves for creating DeviceTimeAns MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let mut creator = lorawan_encoding::maccommandcreator::DeviceTimeAnsCreator::new();
+/// let gps_time = lorawan_encoding::maccommands::GPSTime::from_unix(1_600_000_000, 0).unwrap();
+/// let res = creator.set_gps_time(gps_time).build();
+/// ```
+pub struct DeviceTimeAnsCreator {
+    data: [u8; 6],
+}
+
+impl_mac_cmd_creator_boilerplate!(DeviceTimeAnsCreator, 0x0d, 6);
+
+impl DeviceTimeAnsCreator {
+    /// Sets the time of the DeviceTimeAns to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * gps_time - the time at the end of the uplink transmission carrying the DeviceTimeReq.
+    pub fn set_gps_time(&mut self, gps_time: GPSTime) -> &mut Self {
+        let seconds = gps_time.seconds();
+        self.data[1] = seconds as u8;
+        self.data[2] = (seconds >> 8) as u8;
+        self.data[3] = (seconds >> 16) as u8;
+        self.data[4] = (seconds >> 24) as u8;
+        self.data[5] = gps_time.fraction();
+
+        self
+    }
+}
+
+/// ForceRejoinReqCreator serves for creating ForceRejoinReq MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let mut creator = lorawan_encoding::maccommandcreator::ForceRejoinReqCreator::new();
+/// let res = creator
+///     .set_period(0x03)
+///     .unwrap()
+///     .set_max_retries(0x02)
+///     .unwrap()
+///     .set_rejoin_type(0x02)
+///     .unwrap()
+///     .set_data_rate(0x05)
+///     .unwrap()
+///     .build();
+/// ```
+pub struct ForceRejoinReqCreator {
+    data: [u8; 3],
+}
+
+impl_mac_cmd_creator_boilerplate!(ForceRejoinReqCreator, 0x0e, 3);
+
+impl ForceRejoinReqCreator {
+    fn set_bits(&mut self, value: u8, max: u8, offset: u8) -> Result<&mut Self, Error> {
+        if value > max {
+            return Err(Error::ValueOutOfRange);
+        }
+        let mut v = (u16::from(self.data[2]) << 8) | u16::from(self.data[1]);
+        v &= !(u16::from(max) << offset);
+        v |= u16::from(value) << offset;
+        self.data[1] = v as u8;
+        self.data[2] = (v >> 8) as u8;
+
+        Ok(self)
+    }
+
+    /// Sets the period of the ForceRejoinReq to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * period - the value used to compute the delay between the retransmissions of the
+    ///   RejoinRequest as `32 seconds * 2 ** period + Rand32`. The value must be between 0 and 7.
+    pub fn set_period(&mut self, period: u8) -> Result<&mut Self, Error> {
+        self.set_bits(period, 0x07, 11)
+    }
+
+    /// Sets the max retries of the ForceRejoinReq to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * max_retries - the maximum number of times the RejoinRequest will be retransmitted. The
+    ///   value must be between 0 and 7.
+    pub fn set_max_retries(&mut self, max_retries: u8) -> Result<&mut Self, Error> {
+        self.set_bits(max_retries, 0x07, 8)
+    }
+
+    /// Sets the rejoin type of the ForceRejoinReq to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * rejoin_type - the type of the RejoinRequest that the device should transmit. The value
+    ///   must be 0 or 2, the other values are RFU.
+    pub fn set_rejoin_type(&mut self, rejoin_type: u8) -> Result<&mut Self, Error> {
+        if rejoin_type != 0 && rejoin_type != 2 {
+            return Err(Error::ValueOutOfRange);
+        }
+        self.set_bits(rejoin_type, 0x07, 4)
+    }
+
+    /// Sets the data rate of the ForceRejoinReq to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * data_rate - the data rate that should be used for the RejoinRequest. The value must be
+    ///   between 0 and 15.
+    pub fn set_data_rate(&mut self, data_rate: u8) -> Result<&mut Self, Error> {
+        self.set_bits(data_rate, 0x0f, 0)
+    }
+}
+
+/// RejoinParamSetupReqCreator serves for creating RejoinParamSetupReq MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let mut creator = lorawan_encoding::maccommandcreator::RejoinParamSetupReqCreator::new();
+/// let res = creator
+///     .set_max_time_n(0x0a)
+///     .unwrap()
+///     .set_max_count_n(0x04)
+///     .unwrap()
+///     .build();
+/// ```
+pub struct RejoinParamSetupReqCreator {
+    data: [u8; 2],
+}
+
+impl_mac_cmd_creator_boilerplate!(RejoinParamSetupReqCreator, 0x0f, 2);
+
+impl RejoinParamSetupReqCreator {
+    /// Sets the max time exponent of the RejoinParamSetupReq to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * max_time_n - the exponent of the maximum time between type 0 RejoinRequests computed
+    ///   as `2 ** (max_time_n + 10)` seconds. The value must be between 0 and 15.
+    pub fn set_max_time_n(&mut self, max_time_n: u8) -> Result<&mut Self, Error> {
+        if max_time_n > 0x0f {
+            return Err(Error::ValueOutOfRange);
+        }
+        self.data[1] &= 0x0f;
+        self.data[1] |= max_time_n << 4;
+
+        Ok(self)
+    }
+
+    /// Sets the max count exponent of the RejoinParamSetupReq to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * max_count_n - the exponent of the maximum number of uplinks between type 0
+    ///   RejoinRequests computed as `2 ** (max_count_n + 4)`. The value must be between 0 and 15.
+    pub fn set_max_count_n(&mut self, max_count_n: u8) -> Result<&mut Self, Error> {
+        if max_count_n > 0x0f {
+            return Err(Error::ValueOutOfRange);
+        }
+        self.data[1] &= 0xf0;
+        self.data[1] |= max_count_n;
+
+        Ok(self)
+    }
+}
+
+/// RejoinParamSetupAnsCreator serves for creating RejoinParamSetupAns MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let mut creator = lorawan_encoding::maccommandcreator::RejoinParamSetupAnsCreator::new();
+/// let res = creator.set_time_ack(true).build();
+/// ```
+pub struct RejoinParamSetupAnsCreator {
+    data: [u8; 2],
+}
+
+impl_mac_cmd_creator_boilerplate!(RejoinParamSetupAnsCreator, 0x0f, 2);
+
+impl RejoinParamSetupAnsCreator {
+    /// Sets the time acknowledgement of the RejoinParamSetupAns to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * ack - true meaning that the device is able to use time based periodic RejoinRequests
+    ///   or false otherwise.
+    pub fn set_time_ack(&mut self, ack: bool) -> &mut Self {
+        self.data[1] &= 0xfe;
+        self.data[1] |= ack as u8;
+
+        self
+    }
+}
+
+/// PingSlotInfoReqCreator serves for creating PingSlotInfoReq MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let mut creator = lorawan_encoding::maccommandcreator::PingSlotInfoReqCreator::new();
+/// let res = creator.set_periodicity(0x05).unwrap().build();
+/// ```
+pub struct PingSlotInfoReqCreator {
+    data: [u8; 2],
+}
+
+impl_mac_cmd_creator_boilerplate!(PingSlotInfoReqCreator, 0x10, 2);
+
+impl PingSlotInfoReqCreator {
+    /// Sets the periodicity of the PingSlotInfoReq to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * periodicity - the value used to compute the period between ping slots as
+    ///   `2 ** periodicity` seconds. The value must be between 0 and 7.
+    pub fn set_periodicity(&mut self, periodicity: u8) -> Result<&mut Self, Error> {
+        if periodicity > 0x07 {
+            return Err(Error::ValueOutOfRange);
+        }
+        self.data[1] = periodicity;
+
+        Ok(self)
+    }
+}
+
+/// PingSlotInfoAnsCreator serves for creating PingSlotInfoAns MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let creator = lorawan_encoding::maccommandcreator::PingSlotInfoAnsCreator::new();
+/// let res = creator.build();
+/// ```
+pub struct PingSlotInfoAnsCreator {}
+
+impl_mac_cmd_creator_boilerplate!(PingSlotInfoAnsCreator, 0x10);
+
+/// PingSlotChannelReqCreator serves for creating PingSlotChannelReq MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let mut creator = lorawan_encoding::maccommandcreator::PingSlotChannelReqCreator::new();
+/// let res = creator
+///     .set_frequency(&[0x12, 0x34, 0x56])
+///     .set_data_rate(0x03)
+///     .unwrap()
+///     .build();
+/// ```
+pub struct PingSlotChannelReqCreator {
+    data: [u8; 5],
+}
+
+impl_mac_cmd_creator_boilerplate!(PingSlotChannelReqCreator, 0x11, 5);
+
+impl PingSlotChannelReqCreator {
+    /// Sets the frequency of the PingSlotChannelReq to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * frequency - instance of maccommands::Frequency or anything that can be converted
+    ///   into it.
+    pub fn set_frequency<'a, T: Into<Frequency<'a>>>(&mut self, frequency: T) -> &mut Self {
+        let converted = frequency.into();
+        self.data[1..4].copy_from_slice(converted.as_ref());
+
+        self
+    }
+
+    /// Sets the data rate of the PingSlotChannelReq to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * data_rate - the data rate index used by the ping slots. The value must be between 0
+    ///   and 15.
+    pub fn set_data_rate(&mut self, data_rate: u8) -> Result<&mut Self, Error> {
+        if data_rate > 0x0f {
+            return Err(Error::ValueOutOfRange);
+        }
+        self.data[4] = data_rate;
+
+        Ok(self)
+    }
+}
+
+/// PingSlotChannelAnsCreator serves for creating PingSlotChannelAns MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let mut creator = lorawan_encoding::maccommandcreator::PingSlotChannelAnsCreator::new();
+/// let res = creator
+///     .set_channel_frequency_ack(true)
+///     .set_data_rate_ack(true)
+///     .build();
+/// ```
+pub struct PingSlotChannelAnsCreator {
+    data: [u8; 2],
+}
+
+impl_mac_cmd_creator_boilerplate!(PingSlotChannelAnsCreator, 0x11, 2);
+
+impl PingSlotChannelAnsCreator {
+    /// Sets the channel frequency acknowledgement of the PingSlotChannelAns to the provided
+    /// value.
+    ///
+    /// # Argument
+    ///
+    /// * ack - true meaning that the ping slot frequency was acceptable or false otherwise.
+    pub fn set_channel_frequency_ack(&mut self, ack: bool) -> &mut Self {
+        self.data[1] &= 0xfe;
+        self.data[1] |= ack as u8;
+
+        self
+    }
+
+    /// Sets the data rate acknowledgement of the PingSlotChannelAns to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * ack - true meaning that the ping slot data rate was acceptable or false otherwise.
+    pub fn set_data_rate_ack(&mut self, ack: bool) -> &mut Self {
+        self.data[1] &= 0xfd;
+        self.data[1] |= (ack as u8) << 1;
+
+        self
+    }
+}
+
+/// BeaconTimingReqCreator serves for creating BeaconTimingReq MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let creator = lorawan_encoding::maccommandcreator::BeaconTimingReqCreator::new();
+/// let res = creator.build();
+/// ```
+pub struct BeaconTimingReqCreator {}
+
+impl_mac_cmd_creator_boilerplate!(BeaconTimingReqCreator, 0x12);
+
+/// BeaconTimingAnsCreator serves for creating BeaconTimingAns MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let mut creator = lorawan_encoding::maccommandcreator::BeaconTimingAnsCreator::new();
+/// let res = creator.set_delay(0x1234).set_channel(0x02).build();
+/// ```
+pub struct BeaconTimingAnsCreator {
+    data: [u8; 4],
+}
+
+impl_mac_cmd_creator_boilerplate!(BeaconTimingAnsCreator, 0x12, 4);
+
+impl BeaconTimingAnsCreator {
+    /// Sets the delay of the BeaconTimingAns to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * delay - the time until the next beacon in 30 ms steps.
+    pub fn set_delay(&mut self, delay: u16) -> &mut Self {
+        self.data[1] = delay as u8;
+        self.data[2] = (delay >> 8) as u8;
+
+        self
+    }
+
+    /// Sets the channel of the BeaconTimingAns to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * channel - the index of the beaconing channel of the next beacon.
+    pub fn set_channel(&mut self, channel: u8) -> &mut Self {
+        self.data[3] = channel;
+
+        self
+    }
+}
+
+/// BeaconFreqReqCreator serves for creating BeaconFreqReq MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let mut creator = lorawan_encoding::maccommandcreator::BeaconFreqReqCreator::new();
+/// let res = creator.set_frequency(&[0x12, 0x34, 0x56]).build();
+/// ```
+pub struct BeaconFreqReqCreator {
+    data: [u8; 4],
+}
+
+impl_mac_cmd_creator_boilerplate!(BeaconFreqReqCreator, 0x13, 4);
+
+impl BeaconFreqReqCreator {
+    /// Sets the frequency of the BeaconFreqReq to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * frequency - instance of maccommands::Frequency or anything that can be converted
+    ///   into it.
+    pub fn set_frequency<'a, T: Into<Frequency<'a>>>(&mut self, frequency: T) -> &mut Self {
+        let converted = frequency.into();
+        self.data[1..4].copy_from_slice(converted.as_ref());
+
+        self
+    }
+}
+
+/// BeaconFreqAnsCreator serves for creating BeaconFreqAns MacCommand.
+///
+/// # Examples
+///
+/// ```
+/// let mut creator = lorawan_encoding::maccommandcreator::BeaconFreqAnsCreator::new();
+/// let res = creator.set_beacon_frequency_ack(true).build();
+/// ```
+pub struct BeaconFreqAnsCreator {
+    data: [u8; 2],
+}
+
+impl_mac_cmd_creator_boilerplate!(BeaconFreqAnsCreator, 0x13, 2);
+
+impl BeaconFreqAnsCreator {
+    /// Sets the beacon frequency acknowledgement of the BeaconFreqAns to the provided value.
+    ///
+    /// # Argument
+    ///
+    /// * ack - true meaning that the beacon frequency was acceptable or false otherwise.
+    pub fn set_beacon_frequency_ack(&mut self, ack: bool) -> &mut Self {
+        self.data[1] &= 0xfe;
+        self.data[1] |= ack as u8;
+
+        self
+    }
+}
+
+pub fn build_mac_commands<T: AsMut<[u8]>>(
+    cmds: &[&dyn SerializableMacCommand],
     mut out: T,
-) -> Result<usize, &'c str> {
+) -> Result<usize, Error> {
     let res = out.as_mut();
     if mac_commands_len(cmds) > res.len() {
-        return Err("failed to serialize mac commands in provided buffer: too small");
+        return Err(Error::BufferTooShort);
     }
     let mut i = 0;
     for mc in cmds {
@@ -0,0 +1,352 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Provides owned mac commands and a bounded queue for them.
+
+use core::convert::TryFrom;
+use core::fmt;
+
+use heapless::{ArrayLength, Vec};
+
+use super::creator::PIGGYBACK_MAC_COMMANDS_MAX_LEN;
+use super::error::Error;
+use super::maccommandcreator::*;
+use super::maccommands::*;
+
+macro_rules! owned_mac_cmds {
+    (
+        $(#[$outer:meta])*
+        enum $type:ident[uplink=$uplink:expr] {
+            $(
+                $name:ident($creator:ident, $payload:ident),
+            )*
+        }
+    ) => {
+        $(#[$outer])*
+        pub enum $type {
+            $(
+                $name($creator),
+            )*
+        }
+
+        impl $type {
+            /// Whether the mac commands are sent by the end device or by the network server.
+            pub const fn uplink() -> bool {
+                $uplink
+            }
+
+            /// Provides a borrowed view of the mac command, giving access to its fields.
+            pub fn to_mac_command(&self) -> MacCommand<'_> {
+                match self {
+                    $(
+                        $type::$name(c) => {
+                            // the payload of a creator always has the right length
+                            $payload::new_as_mac_cmd(c.payload_bytes()).unwrap().0
+                        }
+                    )*
+                }
+            }
+
+            fn as_serializable(&self) -> &dyn SerializableMacCommand {
+                match self {
+                    $(
+                        $type::$name(c) => c,
+                    )*
+                }
+            }
+        }
+
+        impl SerializableMacCommand for $type {
+            fn payload_bytes(&self) -> &[u8] {
+                self.as_serializable().payload_bytes()
+            }
+
+            fn cid(&self) -> u8 {
+                self.as_serializable().cid()
+            }
+
+            fn payload_len(&self) -> usize {
+                self.as_serializable().payload_len()
+            }
+        }
+
+        impl<'a> TryFrom<&MacCommand<'a>> for $type {
+            type Error = Error;
+
+            fn try_from(cmd: &MacCommand<'a>) -> Result<Self, Self::Error> {
+                match cmd {
+                    $(
+                        MacCommand::$name(_) => Ok($type::$name($creator::from_payload(cmd.bytes()))),
+                    )*
+                    _ => Err(Error::UnknownMacCommand(cmd.cid())),
+                }
+            }
+        }
+
+        impl<'a> TryFrom<MacCommand<'a>> for $type {
+            type Error = Error;
+
+            fn try_from(cmd: MacCommand<'a>) -> Result<Self, Self::Error> {
+                Self::try_from(&cmd)
+            }
+        }
+
+        $(
+            impl From<$creator> for $type {
+                fn from(c: $creator) -> Self {
+                    $type::$name(c)
+                }
+            }
+        )*
+
+        impl Clone for $type {
+            fn clone(&self) -> Self {
+                match self {
+                    $(
+                        $type::$name(c) => $type::$name($creator::from_payload(c.payload_bytes())),
+                    )*
+                }
+            }
+        }
+
+        impl PartialEq for $type {
+            fn eq(&self, other: &Self) -> bool {
+                self.cid() == other.cid() && self.payload_bytes() == other.payload_bytes()
+            }
+        }
+
+        impl fmt::Debug for $type {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                self.to_mac_command().fmt(f)
+            }
+        }
+    };
+}
+
+owned_mac_cmds! {
+    /// UplinkMacCommand is an owned mac command sent by the end device.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::convert::TryFrom;
+    /// use lorawan_encoding::maccommandcreator::LinkADRAnsCreator;
+    /// use lorawan_encoding::maccommandqueue::UplinkMacCommand;
+    /// use lorawan_encoding::maccommands::parse_mac_commands;
+    ///
+    /// let mut creator = LinkADRAnsCreator::new();
+    /// creator.set_channel_mask_ack(true);
+    /// let cmd = UplinkMacCommand::from(creator);
+    ///
+    /// let data = [0x03, 0x01];
+    /// let parsed = parse_mac_commands(&data[..], true).next().unwrap();
+    /// assert_eq!(UplinkMacCommand::try_from(parsed), Ok(cmd));
+    /// ```
+    enum UplinkMacCommand[uplink=true] {
+        LinkCheckReq(LinkCheckReqCreator, LinkCheckReqPayload),
+        LinkADRAns(LinkADRAnsCreator, LinkADRAnsPayload),
+        DutyCycleAns(DutyCycleAnsCreator, DutyCycleAnsPayload),
+        RXParamSetupAns(RXParamSetupAnsCreator, RXParamSetupAnsPayload),
+        DevStatusAns(DevStatusAnsCreator, DevStatusAnsPayload),
+        NewChannelAns(NewChannelAnsCreator, NewChannelAnsPayload),
+        RXTimingSetupAns(RXTimingSetupAnsCreator, RXTimingSetupAnsPayload),
+        TxParamSetupAns(TxParamSetupAnsCreator, TxParamSetupAnsPayload),
+        DlChannelAns(DlChannelAnsCreator, DlChannelAnsPayload),
+        DeviceTimeReq(DeviceTimeReqCreator, DeviceTimeReqPayload),
+        ResetInd(ResetIndCreator, ResetIndPayload),
+        RekeyInd(RekeyIndCreator, RekeyIndPayload),
+        ADRParamSetupAns(ADRParamSetupAnsCreator, ADRParamSetupAnsPayload),
+        RejoinParamSetupAns(RejoinParamSetupAnsCreator, RejoinParamSetupAnsPayload),
+        PingSlotInfoReq(PingSlotInfoReqCreator, PingSlotInfoReqPayload),
+        PingSlotChannelAns(PingSlotChannelAnsCreator, PingSlotChannelAnsPayload),
+        BeaconTimingReq(BeaconTimingReqCreator, BeaconTimingReqPayload),
+        BeaconFreqAns(BeaconFreqAnsCreator, BeaconFreqAnsPayload),
+    }
+}
+
+owned_mac_cmds! {
+    /// DownlinkMacCommand is an owned mac command sent by the network server.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lorawan_encoding::maccommandcreator::RXTimingSetupReqCreator;
+    /// use lorawan_encoding::maccommandqueue::DownlinkMacCommand;
+    /// use lorawan_encoding::maccommands::MacCommand;
+    ///
+    /// let mut creator = RXTimingSetupReqCreator::new();
+    /// creator.set_delay(0x02).unwrap();
+    /// let cmd = DownlinkMacCommand::from(creator);
+    /// if let MacCommand::RXTimingSetupReq(payload) = cmd.to_mac_command() {
+    ///     assert_eq!(payload.delay(), 2);
+    /// }
+    /// ```
+    enum DownlinkMacCommand[uplink=false] {
+        LinkCheckAns(LinkCheckAnsCreator, LinkCheckAnsPayload),
+        LinkADRReq(LinkADRReqCreator, LinkADRReqPayload),
+        DutyCycleReq(DutyCycleReqCreator, DutyCycleReqPayload),
+        RXParamSetupReq(RXParamSetupReqCreator, RXParamSetupReqPayload),
+        DevStatusReq(DevStatusReqCreator, DevStatusReqPayload),
+        NewChannelReq(NewChannelReqCreator, NewChannelReqPayload),
+        RXTimingSetupReq(RXTimingSetupReqCreator, RXTimingSetupReqPayload),
+        TxParamSetupReq(TxParamSetupReqCreator, TxParamSetupReqPayload),
+        DlChannelReq(DlChannelReqCreator, DlChannelReqPayload),
+        DeviceTimeAns(DeviceTimeAnsCreator, DeviceTimeAnsPayload),
+        ResetConf(ResetConfCreator, ResetConfPayload),
+        RekeyConf(RekeyConfCreator, RekeyConfPayload),
+        ADRParamSetupReq(ADRParamSetupReqCreator, ADRParamSetupReqPayload),
+        ForceRejoinReq(ForceRejoinReqCreator, ForceRejoinReqPayload),
+        RejoinParamSetupReq(RejoinParamSetupReqCreator, RejoinParamSetupReqPayload),
+        PingSlotInfoAns(PingSlotInfoAnsCreator, PingSlotInfoAnsPayload),
+        PingSlotChannelReq(PingSlotChannelReqCreator, PingSlotChannelReqPayload),
+        BeaconTimingAns(BeaconTimingAnsCreator, BeaconTimingAnsPayload),
+        BeaconFreqReq(BeaconFreqReqCreator, BeaconFreqReqPayload),
+    }
+}
+
+/// MacCommandQueue is a bounded queue of mac commands waiting to be sent.
+///
+/// It helps choosing the commands that fit either in FOpts or in FRMPayload with FPort 0.
+///
+/// # Examples
+///
+/// ```
+/// use lorawan_encoding::maccommandcreator::{DevStatusReqCreator, LinkADRReqCreator};
+/// use lorawan_encoding::maccommandqueue::{DownlinkMacCommand, MacCommandQueue};
+///
+/// let mut queue: MacCommandQueue<DownlinkMacCommand, heapless::consts::U8> =
+///     MacCommandQueue::new();
+/// queue.push(LinkADRReqCreator::new().into()).unwrap();
+/// queue.push(DevStatusReqCreator::new().into()).unwrap();
+/// assert!(queue.fits_in_fopts());
+///
+/// let mut phy = lorawan_encoding::creator::DataPayloadCreator::new();
+/// let nwk_skey = lorawan_encoding::keys::AES128([2; 16]);
+/// let app_skey = lorawan_encoding::keys::AES128([1; 16]);
+/// phy.set_uplink(false).set_dev_addr(&[4, 3, 2, 1]);
+/// let sent = {
+///     let cmds = queue.fopts_commands();
+///     phy.build(&[], &cmds[..], &nwk_skey, &app_skey).unwrap();
+///     cmds.len()
+/// };
+/// queue.remove_first(sent);
+/// assert!(queue.is_empty());
+/// ```
+#[derive(Debug)]
+pub struct MacCommandQueue<T, N: ArrayLength<T>> {
+    cmds: Vec<T, N>,
+}
+
+impl<T: SerializableMacCommand, N: ArrayLength<T>> MacCommandQueue<T, N> {
+    /// Creates an empty MacCommandQueue.
+    pub fn new() -> Self {
+        MacCommandQueue { cmds: Vec::new() }
+    }
+
+    /// Adds a mac command at the end of the queue, giving it back if the queue is full.
+    ///
+    /// # Argument
+    ///
+    /// * cmd - the mac command to be queued.
+    pub fn push(&mut self, cmd: T) -> Result<(), T> {
+        self.cmds.push(cmd)
+    }
+
+    /// The queued mac commands in the order in which they were added.
+    pub fn commands(&self) -> &[T] {
+        &self.cmds[..]
+    }
+
+    /// The number of queued mac commands.
+    pub fn len(&self) -> usize {
+        self.cmds.len()
+    }
+
+    /// Whether the queue has no mac commands.
+    pub fn is_empty(&self) -> bool {
+        self.cmds.is_empty()
+    }
+
+    /// Removes all queued mac commands.
+    pub fn clear(&mut self) {
+        self.cmds.clear();
+    }
+
+    /// Removes the first `count` mac commands, usually after they have been sent.
+    ///
+    /// # Argument
+    ///
+    /// * count - the number of mac commands to be removed.
+    pub fn remove_first(&mut self, count: usize) {
+        let count = count.min(self.cmds.len());
+        self.cmds.reverse();
+        for _ in 0..count {
+            self.cmds.pop();
+        }
+        self.cmds.reverse();
+    }
+
+    /// The length in bytes of all queued mac commands, including the CIDs.
+    pub fn bytes_len(&self) -> usize {
+        self.cmds.iter().map(|c| c.payload_len() + 1).sum()
+    }
+
+    /// Whether all queued mac commands fit in FOpts.
+    pub fn fits_in_fopts(&self) -> bool {
+        self.bytes_len() <= PIGGYBACK_MAC_COMMANDS_MAX_LEN
+    }
+
+    /// Provides the longest sequence of queued mac commands, starting from the first one, that
+    /// fits in FOpts.
+    pub fn fopts_commands<'a>(&'a self) -> Vec<&'a dyn SerializableMacCommand, N>
+    where
+        N: ArrayLength<&'a dyn SerializableMacCommand>,
+    {
+        self.commands_up_to(PIGGYBACK_MAC_COMMANDS_MAX_LEN)
+    }
+
+    /// Provides the longest sequence of queued mac commands, starting from the first one, that
+    /// fits in FRMPayload with FPort 0.
+    ///
+    /// # Argument
+    ///
+    /// * max_len - the maximum length of FRMPayload, which depends on the region and the data
+    ///   rate.
+    pub fn frm_payload_commands<'a>(
+        &'a self,
+        max_len: usize,
+    ) -> Vec<&'a dyn SerializableMacCommand, N>
+    where
+        N: ArrayLength<&'a dyn SerializableMacCommand>,
+    {
+        self.commands_up_to(max_len)
+    }
+
+    fn commands_up_to<'a>(&'a self, max_len: usize) -> Vec<&'a dyn SerializableMacCommand, N>
+    where
+        N: ArrayLength<&'a dyn SerializableMacCommand>,
+    {
+        let mut res = Vec::new();
+        let mut len = 0;
+        for c in self.cmds.iter() {
+            len += c.payload_len() + 1;
+            if len > max_len {
+                break;
+            }
+            // res can hold as many elements as self.cmds
+            let _ = res.push(c as &dyn SerializableMacCommand);
+        }
+        res
+    }
+}
+
+impl<T: SerializableMacCommand, N: ArrayLength<T>> Default for MacCommandQueue<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -6,6 +6,8 @@
 //
 // author: Ivaylo Petrov <ivajloip@gmail.com>
 
+use super::error::Error;
+
 /// MacCommand represents the enumeration of all LoRaWAN MACCommands.
 #[derive(Debug, PartialEq)]
 pub enum MacCommand<'a> {
@@ -23,6 +25,37 @@ pub enum MacCommand<'a> {
     NewChannelAns(NewChannelAnsPayload<'a>),
     RXTimingSetupReq(RXTimingSetupReqPayload<'a>),
     RXTimingSetupAns(RXTimingSetupAnsPayload),
+    TxParamSetupReq(TxParamSetupReqPayload<'a>),
+    TxParamSetupAns(TxParamSetupAnsPayload),
+    DlChannelReq(DlChannelReqPayload<'a>),
+    DlChannelAns(DlChannelAnsPayload<'a>),
+    DeviceTimeReq(DeviceTimeReqPayload),
+    DeviceTimeAns(DeviceTimeAnsPayload<'a>),
+    ResetInd(ResetIndPayload<'a>),
+    ResetConf(ResetConfPayload<'a>),
+    RekeyInd(RekeyIndPayload<'a>),
+    RekeyConf(RekeyConfPayload<'a>),
+    ADRParamSetupReq(ADRParamSetupReqPayload<'a>),
+    ADRParamSetupAns(ADRParamSetupAnsPayload),
+    ForceRejoinReq(ForceRejoinReqPayload<'a>),
+    RejoinParamSetupReq(RejoinParamSetupReqPayload<'a>),
+    RejoinParamSetupAns(RejoinParamSetupAnsPayload<'a>),
+    PingSlotInfoReq(PingSlotInfoReqPayload<'a>),
+    PingSlotInfoAns(PingSlotInfoAnsPayload),
+    PingSlotChannelReq(PingSlotChannelReqPayload<'a>),
+    PingSlotChannelAns(PingSlotChannelAnsPayload<'a>),
+    BeaconTimingReq(BeaconTimingReqPayload),
+    BeaconTimingAns(BeaconTimingAnsPayload<'a>),
+    BeaconFreqReq(BeaconFreqReqPayload<'a>),
+    BeaconFreqAns(BeaconFreqAnsPayload<'a>),
+    Proprietary(ProprietaryMacCommand<'a>),
+    /// A MAC command that could not be parsed, either because its CID is not known or because
+    /// there is not enough data for it. As its length is not known, `rest` contains all the
+    /// bytes following the CID.
+    Unknown {
+        cid: u8,
+        rest: &'a [u8],
+    },
 }
 
 impl<'a> MacCommand<'a> {
@@ -43,6 +76,31 @@ impl<'a> MacCommand<'a> {
             MacCommand::NewChannelAns(_) => NewChannelAnsPayload::len(),
             MacCommand::RXTimingSetupReq(_) => RXTimingSetupReqPayload::len(),
             MacCommand::RXTimingSetupAns(_) => RXTimingSetupAnsPayload::len(),
+            MacCommand::TxParamSetupReq(_) => TxParamSetupReqPayload::len(),
+            MacCommand::TxParamSetupAns(_) => TxParamSetupAnsPayload::len(),
+            MacCommand::DlChannelReq(_) => DlChannelReqPayload::len(),
+            MacCommand::DlChannelAns(_) => DlChannelAnsPayload::len(),
+            MacCommand::DeviceTimeReq(_) => DeviceTimeReqPayload::len(),
+            MacCommand::DeviceTimeAns(_) => DeviceTimeAnsPayload::len(),
+            MacCommand::ResetInd(_) => ResetIndPayload::len(),
+            MacCommand::ResetConf(_) => ResetConfPayload::len(),
+            MacCommand::RekeyInd(_) => RekeyIndPayload::len(),
+            MacCommand::RekeyConf(_) => RekeyConfPayload::len(),
+            MacCommand::ADRParamSetupReq(_) => ADRParamSetupReqPayload::len(),
+            MacCommand::ADRParamSetupAns(_) => ADRParamSetupAnsPayload::len(),
+            MacCommand::ForceRejoinReq(_) => ForceRejoinReqPayload::len(),
+            MacCommand::RejoinParamSetupReq(_) => RejoinParamSetupReqPayload::len(),
+            MacCommand::RejoinParamSetupAns(_) => RejoinParamSetupAnsPayload::len(),
+            MacCommand::PingSlotInfoReq(_) => PingSlotInfoReqPayload::len(),
+            MacCommand::PingSlotInfoAns(_) => PingSlotInfoAnsPayload::len(),
+            MacCommand::PingSlotChannelReq(_) => PingSlotChannelReqPayload::len(),
+            MacCommand::PingSlotChannelAns(_) => PingSlotChannelAnsPayload::len(),
+            MacCommand::BeaconTimingReq(_) => BeaconTimingReqPayload::len(),
+            MacCommand::BeaconTimingAns(_) => BeaconTimingAnsPayload::len(),
+            MacCommand::BeaconFreqReq(_) => BeaconFreqReqPayload::len(),
+            MacCommand::BeaconFreqAns(_) => BeaconFreqAnsPayload::len(),
+            MacCommand::Proprietary(ref v) => v.payload().len(),
+            MacCommand::Unknown { rest, .. } => rest.len(),
         }
     }
 
@@ -62,6 +120,31 @@ impl<'a> MacCommand<'a> {
             MacCommand::NewChannelAns(ref v) => &v.0[..],
             MacCommand::RXTimingSetupReq(ref v) => &v.0[..],
             MacCommand::RXTimingSetupAns(_) => &[],
+            MacCommand::TxParamSetupReq(ref v) => &v.0[..],
+            MacCommand::TxParamSetupAns(_) => &[],
+            MacCommand::DlChannelReq(ref v) => &v.0[..],
+            MacCommand::DlChannelAns(ref v) => &v.0[..],
+            MacCommand::DeviceTimeReq(_) => &[],
+            MacCommand::DeviceTimeAns(ref v) => &v.0[..],
+            MacCommand::ResetInd(ref v) => &v.0[..],
+            MacCommand::ResetConf(ref v) => &v.0[..],
+            MacCommand::RekeyInd(ref v) => &v.0[..],
+            MacCommand::RekeyConf(ref v) => &v.0[..],
+            MacCommand::ADRParamSetupReq(ref v) => &v.0[..],
+            MacCommand::ADRParamSetupAns(_) => &[],
+            MacCommand::ForceRejoinReq(ref v) => &v.0[..],
+            MacCommand::RejoinParamSetupReq(ref v) => &v.0[..],
+            MacCommand::RejoinParamSetupAns(ref v) => &v.0[..],
+            MacCommand::PingSlotInfoReq(ref v) => &v.0[..],
+            MacCommand::PingSlotInfoAns(_) => &[],
+            MacCommand::PingSlotChannelReq(ref v) => &v.0[..],
+            MacCommand::PingSlotChannelAns(ref v) => &v.0[..],
+            MacCommand::BeaconTimingReq(_) => &[],
+            MacCommand::BeaconTimingAns(ref v) => &v.0[..],
+            MacCommand::BeaconFreqReq(ref v) => &v.0[..],
+            MacCommand::BeaconFreqAns(ref v) => &v.0[..],
+            MacCommand::Proprietary(ref v) => v.payload(),
+            MacCommand::Unknown { rest, .. } => rest,
         }
     }
 }
@@ -93,6 +176,31 @@ impl<'a> SerializableMacCommand for MacCommand<'a> {
             MacCommand::NewChannelAns(_) => NewChannelAnsPayload::cid(),
             MacCommand::RXTimingSetupReq(_) => RXTimingSetupReqPayload::cid(),
             MacCommand::RXTimingSetupAns(_) => RXTimingSetupAnsPayload::cid(),
+            MacCommand::TxParamSetupReq(_) => TxParamSetupReqPayload::cid(),
+            MacCommand::TxParamSetupAns(_) => TxParamSetupAnsPayload::cid(),
+            MacCommand::DlChannelReq(_) => DlChannelReqPayload::cid(),
+            MacCommand::DlChannelAns(_) => DlChannelAnsPayload::cid(),
+            MacCommand::DeviceTimeReq(_) => DeviceTimeReqPayload::cid(),
+            MacCommand::DeviceTimeAns(_) => DeviceTimeAnsPayload::cid(),
+            MacCommand::ResetInd(_) => ResetIndPayload::cid(),
+            MacCommand::ResetConf(_) => ResetConfPayload::cid(),
+            MacCommand::RekeyInd(_) => RekeyIndPayload::cid(),
+            MacCommand::RekeyConf(_) => RekeyConfPayload::cid(),
+            MacCommand::ADRParamSetupReq(_) => ADRParamSetupReqPayload::cid(),
+            MacCommand::ADRParamSetupAns(_) => ADRParamSetupAnsPayload::cid(),
+            MacCommand::ForceRejoinReq(_) => ForceRejoinReqPayload::cid(),
+            MacCommand::RejoinParamSetupReq(_) => RejoinParamSetupReqPayload::cid(),
+            MacCommand::RejoinParamSetupAns(_) => RejoinParamSetupAnsPayload::cid(),
+            MacCommand::PingSlotInfoReq(_) => PingSlotInfoReqPayload::cid(),
+            MacCommand::PingSlotInfoAns(_) => PingSlotInfoAnsPayload::cid(),
+            MacCommand::PingSlotChannelReq(_) => PingSlotChannelReqPayload::cid(),
+            MacCommand::PingSlotChannelAns(_) => PingSlotChannelAnsPayload::cid(),
+            MacCommand::BeaconTimingReq(_) => BeaconTimingReqPayload::cid(),
+            MacCommand::BeaconTimingAns(_) => BeaconTimingAnsPayload::cid(),
+            MacCommand::BeaconFreqReq(_) => BeaconFreqReqPayload::cid(),
+            MacCommand::BeaconFreqAns(_) => BeaconFreqAnsPayload::cid(),
+            MacCommand::Proprietary(ref v) => v.cid(),
+            MacCommand::Unknown { cid, .. } => cid,
         }
     }
 
@@ -119,11 +227,11 @@ macro_rules! mac_cmd_zero_len {
             pub struct $type();
 
             impl $type {
-                pub fn new(_: &[u8]) -> Result<$type, &str> {
+                pub fn new(_: &[u8]) -> Result<$type, Error> {
                     Ok($type())
                 }
 
-                pub fn new_as_mac_cmd<'a>(data: &[u8]) -> Result<(MacCommand<'a>, usize), &str> {
+                pub fn new_as_mac_cmd<'a>(data: &[u8]) -> Result<(MacCommand<'a>, usize), Error> {
                     Ok((MacCommand::$name($type::new(data)?), 0))
                 }
 
@@ -141,12 +249,12 @@ macro_rules! mac_cmd_zero_len {
             }
         )*
 
-        fn parse_zero_len_mac_cmd<'a, 'b>(data: &'a [u8], uplink: bool) -> Result<(usize, MacCommand<'a>), &'b str> {
+        fn parse_zero_len_mac_cmd(data: &[u8], uplink: bool) -> Result<(usize, MacCommand<'_>), Error> {
             match (data[0], uplink) {
                 $(
                     ($cid, $uplink) => Ok((0, MacCommand::$name($type::new(&[])?))),
                 )*
-                _ => Err("uknown mac command")
+                _ => Err(Error::UnknownMacCommand(data[0]))
             }
         }
     }
@@ -166,15 +274,15 @@ macro_rules! mac_cmds {
 
             impl<'a> $type<'a> {
                 /// Creates a new instance of the mac command if there is enought data.
-                pub fn new<'b>(data: &'a [u8]) -> Result<$type<'a>, &'b str> {
+                pub fn new(data: &'a [u8]) -> Result<$type<'a>, Error> {
                     if data.len() < $size {
-                        Err("incorrect size for")
+                        Err(Error::InvalidMacCommandLength)
                     } else {
                         Ok($type(&data[..]))
                     }
                 }
 
-                pub fn new_as_mac_cmd<'b>(data: &'a [u8]) -> Result<(MacCommand<'a>, usize), &'b str> {
+                pub fn new_as_mac_cmd(data: &'a [u8]) -> Result<(MacCommand<'a>, usize), Error> {
                     Ok((MacCommand::$name($type::new(data)?), $size))
                 }
 
@@ -195,7 +303,7 @@ macro_rules! mac_cmds {
             }
         )*
 
-        fn parse_one_mac_cmd<'a, 'b>(data: &'a [u8], uplink: bool) -> Result<(usize, MacCommand<'a>), &'b str> {
+        fn parse_one_mac_cmd(data: &[u8], uplink: bool) -> Result<(usize, MacCommand<'_>), Error> {
             match (data[0], uplink) {
                 $(
                     ($cid, $uplink) if data.len() > $size => Ok(($size, MacCommand::$name($type::new(&data[1.. 1 + $size])?))),
@@ -222,9 +330,39 @@ mac_cmd_zero_len! {
     /// RXTimingSetupAnsPayload represents the RXTimingSetupAns LoRaWAN MACCommand.
     #[derive(Debug, PartialEq)]
     struct RXTimingSetupAnsPayload[cmd=RXTimingSetupAns, cid=0x08, uplink=true]
+
+    /// TxParamSetupAnsPayload represents the TxParamSetupAns LoRaWAN MACCommand.
+    #[derive(Debug, PartialEq)]
+    struct TxParamSetupAnsPayload[cmd=TxParamSetupAns, cid=0x09, uplink=true]
+
+    /// ADRParamSetupAnsPayload represents the ADRParamSetupAns LoRaWAN MACCommand.
+    #[derive(Debug, PartialEq)]
+    struct ADRParamSetupAnsPayload[cmd=ADRParamSetupAns, cid=0x0c, uplink=true]
+
+    /// DeviceTimeReqPayload represents the DeviceTimeReq LoRaWAN MACCommand.
+    #[derive(Debug, PartialEq)]
+    struct DeviceTimeReqPayload[cmd=DeviceTimeReq, cid=0x0d, uplink=true]
+
+    /// PingSlotInfoAnsPayload represents the PingSlotInfoAns LoRaWAN MACCommand.
+    #[derive(Debug, PartialEq)]
+    struct PingSlotInfoAnsPayload[cmd=PingSlotInfoAns, cid=0x10, uplink=false]
+
+    /// BeaconTimingReqPayload represents the BeaconTimingReq LoRaWAN MACCommand.
+    ///
+    /// Note: BeaconTimingReq is deprecated since LoRaWAN 1.0.3.
+    #[derive(Debug, PartialEq)]
+    struct BeaconTimingReqPayload[cmd=BeaconTimingReq, cid=0x12, uplink=true]
 }
 
 mac_cmds! {
+    /// ResetIndPayload represents the ResetInd LoRaWAN MACCommand.
+    #[derive(Debug, PartialEq)]
+    struct ResetIndPayload[cmd=ResetInd, cid=0x01, uplink=true, size=1]
+
+    /// ResetConfPayload represents the ResetConf LoRaWAN MACCommand.
+    #[derive(Debug, PartialEq)]
+    struct ResetConfPayload[cmd=ResetConf, cid=0x01, uplink=false, size=1]
+
     /// LinkCheckAnsPayload represents the LinkCheckAns LoRaWAN MACCommand.
     #[derive(Debug, PartialEq)]
     struct LinkCheckAnsPayload[cmd=LinkCheckAns, cid=0x02, uplink=false, size=2]
@@ -264,6 +402,72 @@ mac_cmds! {
     /// RXTimingSetupReqPayload represents the RXTimingSetupReq LoRaWAN MACCommand.
     #[derive(Debug, PartialEq)]
     struct RXTimingSetupReqPayload[cmd=RXTimingSetupReq, cid=0x08, uplink=false, size=1]
+
+    /// TxParamSetupReqPayload represents the TxParamSetupReq LoRaWAN MACCommand.
+    #[derive(Debug, PartialEq)]
+    struct TxParamSetupReqPayload[cmd=TxParamSetupReq, cid=0x09, uplink=false, size=1]
+
+    /// DlChannelReqPayload represents the DlChannelReq LoRaWAN MACCommand.
+    #[derive(Debug, PartialEq)]
+    struct DlChannelReqPayload[cmd=DlChannelReq, cid=0x0a, uplink=false, size=4]
+
+    /// DlChannelAnsPayload represents the DlChannelAns LoRaWAN MACCommand.
+    #[derive(Debug, PartialEq)]
+    struct DlChannelAnsPayload[cmd=DlChannelAns, cid=0x0a, uplink=true, size=1]
+
+    /// RekeyIndPayload represents the RekeyInd LoRaWAN MACCommand.
+    #[derive(Debug, PartialEq)]
+    struct RekeyIndPayload[cmd=RekeyInd, cid=0x0b, uplink=true, size=1]
+
+    /// RekeyConfPayload represents the RekeyConf LoRaWAN MACCommand.
+    #[derive(Debug, PartialEq)]
+    struct RekeyConfPayload[cmd=RekeyConf, cid=0x0b, uplink=false, size=1]
+
+    /// ADRParamSetupReqPayload represents the ADRParamSetupReq LoRaWAN MACCommand.
+    #[derive(Debug, PartialEq)]
+    struct ADRParamSetupReqPayload[cmd=ADRParamSetupReq, cid=0x0c, uplink=false, size=1]
+
+    /// DeviceTimeAnsPayload represents the DeviceTimeAns LoRaWAN MACCommand.
+    #[derive(Debug, PartialEq)]
+    struct DeviceTimeAnsPayload[cmd=DeviceTimeAns, cid=0x0d, uplink=false, size=5]
+
+    /// ForceRejoinReqPayload represents the ForceRejoinReq LoRaWAN MACCommand.
+    #[derive(Debug, PartialEq)]
+    struct ForceRejoinReqPayload[cmd=ForceRejoinReq, cid=0x0e, uplink=false, size=2]
+
+    /// RejoinParamSetupReqPayload represents the RejoinParamSetupReq LoRaWAN MACCommand.
+    #[derive(Debug, PartialEq)]
+    struct RejoinParamSetupReqPayload[cmd=RejoinParamSetupReq, cid=0x0f, uplink=false, size=1]
+
+    /// RejoinParamSetupAnsPayload represents the RejoinParamSetupAns LoRaWAN MACCommand.
+    #[derive(Debug, PartialEq)]
+    struct RejoinParamSetupAnsPayload[cmd=RejoinParamSetupAns, cid=0x0f, uplink=true, size=1]
+
+    /// PingSlotInfoReqPayload represents the PingSlotInfoReq LoRaWAN MACCommand.
+    #[derive(Debug, PartialEq)]
+    struct PingSlotInfoReqPayload[cmd=PingSlotInfoReq, cid=0x10, uplink=true, size=1]
+
+    /// PingSlotChannelReqPayload represents the PingSlotChannelReq LoRaWAN MACCommand.
+    #[derive(Debug, PartialEq)]
+    struct PingSlotChannelReqPayload[cmd=PingSlotChannelReq, cid=0x11, uplink=false, size=4]
+
+    /// PingSlotChannelAnsPayload represents the PingSlotChannelAns LoRaWAN MACCommand.
+    #[derive(Debug, PartialEq)]
+    struct PingSlotChannelAnsPayload[cmd=PingSlotChannelAns, cid=0x11, uplink=true, size=1]
+
+    /// BeaconTimingAnsPayload represents the BeaconTimingAns LoRaWAN MACCommand.
+    ///
+    /// Note: BeaconTimingAns is deprecated since LoRaWAN 1.0.3.
+    #[derive(Debug, PartialEq)]
+    struct BeaconTimingAnsPayload[cmd=BeaconTimingAns, cid=0x12, uplink=false, size=3]
+
+    /// BeaconFreqReqPayload represents the BeaconFreqReq LoRaWAN MACCommand.
+    #[derive(Debug, PartialEq)]
+    struct BeaconFreqReqPayload[cmd=BeaconFreqReq, cid=0x13, uplink=false, size=3]
+
+    /// BeaconFreqAnsPayload represents the BeaconFreqAns LoRaWAN MACCommand.
+    #[derive(Debug, PartialEq)]
+    struct BeaconFreqAnsPayload[cmd=BeaconFreqAns, cid=0x13, uplink=true, size=1]
 }
 
 macro_rules! create_ack_fn {
@@ -292,8 +496,10 @@ macro_rules! create_value_reader_fn {
 
 /// Parses bytes to mac commands if possible.
 ///
-/// Could return error if some values are out of range or the payload does not end at mac command
-/// boundry.
+/// If a mac command can not be parsed, `MacCommand::Unknown` holding the rest of the data is
+/// returned as last element. Proprietary mac commands can be parsed after registering them with
+/// [with_proprietary](struct.MacCommandIterator.html#method.with_proprietary).
+///
 /// # Argument
 ///
 /// * bytes - the data from which the MAC commands are to be built.
@@ -311,6 +517,7 @@ pub fn parse_mac_commands(data: &[u8], uplink: bool) -> MacCommandIterator {
         index: 0,
         data,
         uplink,
+        proprietary: &[],
     }
 }
 
@@ -319,19 +526,172 @@ pub struct MacCommandIterator<'a> {
     data: &'a [u8],
     index: usize,
     uplink: bool,
+    proprietary: &'a [ProprietaryMacCommandSpec],
+}
+
+impl<'a> MacCommandIterator<'a> {
+    /// Registers proprietary mac commands so that they are parsed as
+    /// `MacCommand::Proprietary` instead of `MacCommand::Unknown`.
+    ///
+    /// # Argument
+    ///
+    /// * proprietary - the specifications of the proprietary mac commands.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lorawan_encoding::maccommands::{parse_mac_commands, ProprietaryMacCommandSpec};
+    ///
+    /// let specs = [ProprietaryMacCommandSpec::new(0x80, true, 2).unwrap()];
+    /// let data = vec![0x80, 0x01, 0x02, 0x02];
+    /// let mac_cmds: Vec<lorawan_encoding::maccommands::MacCommand> =
+    ///     parse_mac_commands(&data[..], true).with_proprietary(&specs).collect();
+    /// assert_eq!(mac_cmds.len(), 2);
+    /// ```
+    pub fn with_proprietary(mut self, proprietary: &'a [ProprietaryMacCommandSpec]) -> Self {
+        self.proprietary = proprietary;
+        self
+    }
+
+    fn parse_proprietary(&self, data: &'a [u8]) -> Option<(usize, MacCommand<'a>)> {
+        let spec = self
+            .proprietary
+            .iter()
+            .find(|s| s.cid == data[0] && s.uplink == self.uplink)?;
+        if data.len() <= spec.len {
+            return None;
+        }
+        let cmd = ProprietaryMacCommand::new_from_raw(data[0], &data[1..=spec.len]);
+        Some((spec.len, MacCommand::Proprietary(cmd)))
+    }
 }
 
 impl<'a> Iterator for MacCommandIterator<'a> {
     type Item = MacCommand<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index < self.data.len() {
-            if let Ok((l, v)) = parse_one_mac_cmd(&self.data[self.index..], self.uplink) {
-                self.index += 1 + l;
-                return Some(v);
-            }
+        if self.index >= self.data.len() {
+            return None;
+        }
+        let data = &self.data[self.index..];
+        let parsed = parse_one_mac_cmd(data, self.uplink)
+            .ok()
+            .or_else(|| self.parse_proprietary(data));
+        if let Some((l, v)) = parsed {
+            self.index += 1 + l;
+            return Some(v);
         }
-        None
+        self.index = self.data.len();
+        Some(MacCommand::Unknown {
+            cid: data[0],
+            rest: &data[1..],
+        })
+    }
+}
+
+/// ProprietaryMacCommandSpec describes a proprietary mac command, so that it can be parsed.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ProprietaryMacCommandSpec {
+    cid: u8,
+    uplink: bool,
+    len: usize,
+}
+
+impl ProprietaryMacCommandSpec {
+    /// Creates a new ProprietaryMacCommandSpec.
+    ///
+    /// # Argument
+    ///
+    /// * cid - the command identifier, which must be between 0x80 and 0xff.
+    /// * uplink - whether the mac command is sent by the end device or by the network server.
+    /// * len - the length of the payload of the mac command.
+    pub fn new(cid: u8, uplink: bool, len: usize) -> Result<Self, Error> {
+        if cid < 0x80 {
+            return Err(Error::ValueOutOfRange);
+        }
+        Ok(ProprietaryMacCommandSpec { cid, uplink, len })
+    }
+
+    /// Command identifier.
+    pub fn cid(&self) -> u8 {
+        self.cid
+    }
+
+    /// Sent by end device or sent by network server.
+    pub fn uplink(&self) -> bool {
+        self.uplink
+    }
+
+    /// length of the payload of the mac command.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the mac command has no payload.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// ProprietaryMacCommand represents a vendor specific mac command with CID between 0x80 and 0xff.
+///
+/// # Examples
+///
+/// ```
+/// use lorawan_encoding::maccommandcreator::build_mac_commands;
+/// use lorawan_encoding::maccommands::{ProprietaryMacCommand, SerializableMacCommand};
+///
+/// let cmd = ProprietaryMacCommand::new(0x80, &[0x01, 0x02]).unwrap();
+/// let mut res = [0; 3];
+/// build_mac_commands(&[&cmd as &dyn SerializableMacCommand], &mut res[..]).unwrap();
+/// assert_eq!(res, [0x80, 0x01, 0x02]);
+/// ```
+#[derive(Debug, PartialEq)]
+pub struct ProprietaryMacCommand<'a> {
+    cid: u8,
+    payload: &'a [u8],
+}
+
+impl<'a> ProprietaryMacCommand<'a> {
+    /// Creates a new ProprietaryMacCommand.
+    ///
+    /// # Argument
+    ///
+    /// * cid - the command identifier, which must be between 0x80 and 0xff.
+    /// * payload - the payload of the mac command.
+    pub fn new(cid: u8, payload: &'a [u8]) -> Result<Self, Error> {
+        if cid < 0x80 {
+            return Err(Error::ValueOutOfRange);
+        }
+        Ok(Self::new_from_raw(cid, payload))
+    }
+
+    fn new_from_raw(cid: u8, payload: &'a [u8]) -> Self {
+        ProprietaryMacCommand { cid, payload }
+    }
+
+    /// Command identifier.
+    pub fn cid(&self) -> u8 {
+        self.cid
+    }
+
+    /// The payload of the mac command.
+    pub fn payload(&self) -> &'a [u8] {
+        self.payload
+    }
+}
+
+impl<'a> SerializableMacCommand for ProprietaryMacCommand<'a> {
+    fn payload_bytes(&self) -> &[u8] {
+        self.payload
+    }
+
+    fn cid(&self) -> u8 {
+        self.cid
+    }
+
+    fn payload_len(&self) -> usize {
+        self.payload.len()
     }
 }
 
@@ -384,9 +744,9 @@ pub struct ChannelMask([u8; 2]);
 
 impl ChannelMask {
     /// Constructs a new ChannelMask from the provided data.
-    pub fn new(data: &[u8]) -> Result<Self, &str> {
+    pub fn new(data: &[u8]) -> Result<Self, Error> {
         if data.len() < 2 {
-            return Err("not enough bytes to read");
+            return Err(Error::TooShort);
         }
         Ok(Self::new_from_raw(data))
     }
@@ -405,9 +765,9 @@ impl ChannelMask {
     }
 
     /// Verifies if a given channel is enabled.
-    pub fn is_enabled(&self, index: usize) -> Result<bool, &str> {
+    pub fn is_enabled(&self, index: usize) -> Result<bool, Error> {
         if index > 15 {
-            return Err("index should be between 0 and 15");
+            return Err(Error::ValueOutOfRange);
         }
         Ok(self.channel_enabled(index))
     }
@@ -526,6 +886,11 @@ impl DLSettings {
         DLSettings(byte)
     }
 
+    /// Whether the network uses LoRaWAN 1.1 or later, which is signaled by the OptNeg bit.
+    pub fn opt_neg(&self) -> bool {
+        self.0 & 0x80 != 0
+    }
+
     /// The offset between the uplink data rate and the downlink data rate used to communicate with
     /// the end-device on the first reception slot (RX1).
     pub fn rx1_dr_offset(&self) -> u8 {
@@ -661,18 +1026,16 @@ impl DataRateRange {
     }
 
     /// Constructs a new DataRateRange from the provided byte.
-    pub fn new(byte: u8) -> Result<DataRateRange, &'static str> {
-        if let Err(err) = Self::can_build_from(byte) {
-            return Err(err);
-        }
+    pub fn new(byte: u8) -> Result<DataRateRange, Error> {
+        Self::can_build_from(byte)?;
 
         Ok(Self::new_from_raw(byte))
     }
 
     /// Check if the byte can be used to create DataRateRange.
-    pub fn can_build_from(byte: u8) -> Result<(), &'static str> {
+    pub fn can_build_from(byte: u8) -> Result<(), Error> {
         if (byte >> 4) < (byte & 0x0f) {
-            return Err("data rate range can not have max data rate smaller than min data rate");
+            return Err(Error::InvalidDataRateRange);
         }
         Ok(())
     }
@@ -724,3 +1087,363 @@ impl<'a> RXTimingSetupReqPayload<'a> {
         self.0[0] & 0x0f
     }
 }
+
+/// The values in dBm of the MaxEIRP field of the TxParamSetupReq.
+const MAX_EIRP_TABLE: [u8; 16] = [
+    8, 10, 12, 13, 14, 16, 18, 20, 21, 24, 26, 27, 29, 30, 33, 36,
+];
+
+impl<'a> TxParamSetupReqPayload<'a> {
+    /// Whether the maximum dwell time of 400 ms applies to downlinks.
+    pub fn downlink_dwell_time(&self) -> bool {
+        self.0[0] & 0x20 != 0
+    }
+
+    /// Whether the maximum dwell time of 400 ms applies to uplinks.
+    pub fn uplink_dwell_time(&self) -> bool {
+        self.0[0] & 0x10 != 0
+    }
+
+    /// Integer value of the max EIRP field.
+    pub fn max_eirp_raw(&self) -> u8 {
+        self.0[0] & 0x0f
+    }
+
+    /// The maximum allowed EIRP in dBm.
+    pub fn max_eirp(&self) -> u8 {
+        MAX_EIRP_TABLE[usize::from(self.max_eirp_raw())]
+    }
+}
+
+impl<'a> DlChannelReqPayload<'a> {
+    create_value_reader_fn!(
+        /// The index of the channel whose downlink frequency is modified.
+        channel_index,
+        0
+    );
+
+    /// The downlink frequency to be used in RX1 for the channel.
+    pub fn frequency(&self) -> Frequency<'_> {
+        Frequency::new_from_raw(&self.0[1..4])
+    }
+}
+
+impl<'a> DlChannelAnsPayload<'a> {
+    create_ack_fn!(
+        /// Whether the device is able to use the downlink frequency.
+        channel_freq_ack,
+        0
+    );
+
+    create_ack_fn!(
+        /// Whether the uplink frequency of the channel is valid.
+        uplink_freq_exists_ack,
+        1
+    );
+
+    /// Whether the device has accepted the new downlink frequency.
+    pub fn ack(&self) -> bool {
+        self.0[0] == 0x03
+    }
+}
+
+impl<'a> ResetIndPayload<'a> {
+    /// The minor version of LoRaWAN supported by the end-device (1 means LoRaWAN 1.1).
+    pub fn minor(&self) -> u8 {
+        self.0[0] & 0x0f
+    }
+}
+
+impl<'a> ResetConfPayload<'a> {
+    /// The minor version of LoRaWAN supported by the network server (1 means LoRaWAN 1.1).
+    pub fn minor(&self) -> u8 {
+        self.0[0] & 0x0f
+    }
+}
+
+impl<'a> RekeyIndPayload<'a> {
+    /// The minor version of LoRaWAN supported by the end-device (1 means LoRaWAN 1.1).
+    pub fn minor(&self) -> u8 {
+        self.0[0] & 0x0f
+    }
+}
+
+impl<'a> RekeyConfPayload<'a> {
+    /// The minor version of LoRaWAN supported by the network server (1 means LoRaWAN 1.1).
+    pub fn minor(&self) -> u8 {
+        self.0[0] & 0x0f
+    }
+}
+
+impl<'a> ADRParamSetupReqPayload<'a> {
+    /// The exponent used to compute ADR_ACK_LIMIT.
+    pub fn limit_exp(&self) -> u8 {
+        self.0[0] >> 4
+    }
+
+    /// The exponent used to compute ADR_ACK_DELAY.
+    pub fn delay_exp(&self) -> u8 {
+        self.0[0] & 0x0f
+    }
+
+    /// The value of ADR_ACK_LIMIT, i.e. `2 ** limit_exp`.
+    pub fn adr_ack_limit(&self) -> u16 {
+        1 << self.limit_exp()
+    }
+
+    /// The value of ADR_ACK_DELAY, i.e. `2 ** delay_exp`.
+    pub fn adr_ack_delay(&self) -> u16 {
+        1 << self.delay_exp()
+    }
+}
+
+impl<'a> DeviceTimeAnsPayload<'a> {
+    /// The number of seconds since the GPS epoch at the end of the uplink transmission carrying
+    /// the DeviceTimeReq.
+    pub fn seconds(&self) -> u32 {
+        (u32::from(self.0[3]) << 24)
+            | (u32::from(self.0[2]) << 16)
+            | (u32::from(self.0[1]) << 8)
+            | u32::from(self.0[0])
+    }
+
+    /// The fractional part of the second in 1/256 s steps.
+    pub fn fraction(&self) -> u8 {
+        self.0[4]
+    }
+
+    /// The time provided by the network as GPSTime.
+    pub fn gps_time(&self) -> GPSTime {
+        GPSTime::new(self.seconds(), self.fraction())
+    }
+}
+
+/// Unix time of the GPS epoch, 1980-01-06T00:00:00Z.
+const GPS_EPOCH_UNIX: u64 = 315_964_800;
+
+/// Unix times at which leap seconds took effect after the GPS epoch.
+const LEAP_SECONDS_UNIX: [u64; 18] = [
+    362_793_600,   // 1981-07-01
+    394_329_600,   // 1982-07-01
+    425_865_600,   // 1983-07-01
+    489_024_000,   // 1985-07-01
+    567_993_600,   // 1988-01-01
+    631_152_000,   // 1990-01-01
+    662_688_000,   // 1991-01-01
+    709_948_800,   // 1992-07-01
+    741_484_800,   // 1993-07-01
+    773_020_800,   // 1994-07-01
+    820_454_400,   // 1996-01-01
+    867_715_200,   // 1997-07-01
+    915_148_800,   // 1999-01-01
+    1_136_073_600, // 2006-01-01
+    1_230_768_000, // 2009-01-01
+    1_341_100_800, // 2012-07-01
+    1_435_708_800, // 2015-07-01
+    1_483_228_800, // 2017-01-01
+];
+
+/// GPSTime represents a point in time as used by LoRaWAN, namely the number of seconds since the
+/// GPS epoch (1980-01-06T00:00:00Z) together with a fractional part in 1/256 s steps.
+///
+/// Unlike Unix time, GPS time is not adjusted for leap seconds, so the conversions take into
+/// account the leap seconds known at the time of writing.
+///
+/// # Examples
+///
+/// ```
+/// let time = lorawan_encoding::maccommands::GPSTime::from_unix(1_483_228_800, 0x80).unwrap();
+/// assert_eq!(time.seconds(), 1_167_264_018);
+/// assert_eq!(time.to_unix(), 1_483_228_800);
+/// ```
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct GPSTime {
+    seconds: u32,
+    fraction: u8,
+}
+
+impl GPSTime {
+    /// Constructs a new GPSTime from seconds since the GPS epoch and a fraction of a second.
+    ///
+    /// # Argument
+    ///
+    /// * seconds - the number of seconds since the GPS epoch.
+    /// * fraction - the fractional part of the second in 1/256 s steps.
+    pub fn new(seconds: u32, fraction: u8) -> Self {
+        GPSTime { seconds, fraction }
+    }
+
+    /// Constructs a new GPSTime from a Unix time.
+    ///
+    /// Fails if the time is before the GPS epoch or can not be represented with 32 bits.
+    ///
+    /// # Argument
+    ///
+    /// * unix_seconds - the number of seconds since the Unix epoch.
+    /// * fraction - the fractional part of the second in 1/256 s steps.
+    pub fn from_unix(unix_seconds: u64, fraction: u8) -> Result<Self, Error> {
+        if unix_seconds < GPS_EPOCH_UNIX {
+            return Err(Error::ValueOutOfRange);
+        }
+        let leap_seconds = LEAP_SECONDS_UNIX
+            .iter()
+            .filter(|&&t| t <= unix_seconds)
+            .count() as u64;
+        let seconds = unix_seconds - GPS_EPOCH_UNIX + leap_seconds;
+        if seconds > u64::from(u32::MAX) {
+            return Err(Error::ValueOutOfRange);
+        }
+
+        Ok(Self::new(seconds as u32, fraction))
+    }
+
+    /// The number of seconds since the GPS epoch.
+    pub fn seconds(&self) -> u32 {
+        self.seconds
+    }
+
+    /// The fractional part of the second in 1/256 s steps.
+    pub fn fraction(&self) -> u8 {
+        self.fraction
+    }
+
+    /// The number of seconds since the Unix epoch.
+    ///
+    /// A time falling on an inserted leap second is mapped to the first second after it.
+    pub fn to_unix(&self) -> u64 {
+        let seconds = u64::from(self.seconds);
+        let leap_seconds = LEAP_SECONDS_UNIX
+            .iter()
+            .enumerate()
+            .filter(|&(i, &t)| t - GPS_EPOCH_UNIX + (i as u64) < seconds)
+            .count() as u64;
+        seconds + GPS_EPOCH_UNIX - leap_seconds
+    }
+}
+
+impl<'a> ForceRejoinReqPayload<'a> {
+    fn value(&self) -> u16 {
+        (u16::from(self.0[1]) << 8) | u16::from(self.0[0])
+    }
+
+    /// The period used to compute the delay between retransmissions of the RejoinRequest.
+    ///
+    /// The delay is `32 seconds * 2 ** period + Rand32`.
+    pub fn period(&self) -> u8 {
+        ((self.value() >> 11) & 0x07) as u8
+    }
+
+    /// The maximum number of times the RejoinRequest will be retransmitted.
+    pub fn max_retries(&self) -> u8 {
+        ((self.value() >> 8) & 0x07) as u8
+    }
+
+    /// The type of the RejoinRequest that should be transmitted.
+    pub fn rejoin_type(&self) -> u8 {
+        ((self.value() >> 4) & 0x07) as u8
+    }
+
+    /// The data rate that should be used for the RejoinRequest transmission.
+    pub fn data_rate(&self) -> u8 {
+        (self.value() & 0x0f) as u8
+    }
+}
+
+impl<'a> RejoinParamSetupReqPayload<'a> {
+    /// The exponent of the maximum time between type 0 RejoinRequests, i.e.
+    /// `2 ** (max_time_n + 10)` seconds.
+    pub fn max_time_n(&self) -> u8 {
+        self.0[0] >> 4
+    }
+
+    /// The exponent of the maximum number of uplinks between type 0 RejoinRequests, i.e.
+    /// `2 ** (max_count_n + 4)` uplinks.
+    pub fn max_count_n(&self) -> u8 {
+        self.0[0] & 0x0f
+    }
+}
+
+impl<'a> RejoinParamSetupAnsPayload<'a> {
+    create_ack_fn!(
+        /// Whether the device is able to use the time based periodic RejoinRequests.
+        time_ack,
+        0
+    );
+}
+
+impl<'a> PingSlotInfoReqPayload<'a> {
+    /// Integer value of the periodicity field.
+    pub fn periodicity(&self) -> u8 {
+        self.0[0] & 0x07
+    }
+
+    /// The period in seconds between two consecutive ping slots, i.e. `2 ** periodicity`.
+    pub fn ping_slot_period(&self) -> u8 {
+        1 << self.periodicity()
+    }
+
+    /// The number of ping slots per beacon period, i.e. `2 ** (7 - periodicity)`.
+    pub fn ping_nb(&self) -> u8 {
+        1 << (7 - self.periodicity())
+    }
+}
+
+impl<'a> PingSlotChannelReqPayload<'a> {
+    /// The frequency used by the ping slots, 0 meaning the default frequency of the region.
+    pub fn frequency(&self) -> Frequency<'_> {
+        Frequency::new_from_raw(&self.0[0..3])
+    }
+
+    /// The data rate used by the ping slots.
+    pub fn data_rate(&self) -> u8 {
+        self.0[3] & 0x0f
+    }
+}
+
+impl<'a> PingSlotChannelAnsPayload<'a> {
+    create_ack_fn!(
+        /// Whether the device is able to use the ping slot frequency.
+        channel_freq_ack,
+        0
+    );
+
+    create_ack_fn!(
+        /// Whether the device is able to use the ping slot data rate.
+        data_rate_ack,
+        1
+    );
+
+    /// Whether the device has accepted the new ping slot parameters.
+    pub fn ack(&self) -> bool {
+        self.0[0] == 0x03
+    }
+}
+
+impl<'a> BeaconTimingAnsPayload<'a> {
+    /// The time until the next beacon in 30 ms steps, measured from the end of the downlink
+    /// carrying the BeaconTimingAns.
+    pub fn delay(&self) -> u16 {
+        (u16::from(self.0[1]) << 8) | u16::from(self.0[0])
+    }
+
+    create_value_reader_fn!(
+        /// The index of the beaconing channel on which the next beacon will be broadcast.
+        channel,
+        2
+    );
+}
+
+impl<'a> BeaconFreqReqPayload<'a> {
+    /// The frequency of the beacons, 0 meaning the default frequency of the region.
+    pub fn frequency(&self) -> Frequency<'_> {
+        Frequency::new_from_raw(&self.0[0..3])
+    }
+}
+
+impl<'a> BeaconFreqAnsPayload<'a> {
+    create_ack_fn!(
+        /// Whether the device is able to use the beacon frequency.
+        beacon_freq_ack,
+        0
+    );
+}
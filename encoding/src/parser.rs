@@ -28,6 +28,7 @@
 //! }
 //! ```
 
+use super::error::Error;
 use super::keys::{CryptoFactory, Encrypter, AES128, MIC};
 use super::maccommands::{parse_mac_commands, DLSettings, Frequency, MacCommandIterator};
 use super::securityhelpers;
@@ -35,6 +36,11 @@ use super::securityhelpers;
 #[cfg(feature = "default-crypto")]
 use super::default_crypto::DefaultFactory;
 
+use heapless::ArrayLength;
+
+#[cfg(feature = "std")]
+extern crate std;
+
 macro_rules! fixed_len_struct {
     (
         $(#[$outer:meta])*
@@ -105,14 +111,114 @@ macro_rules! fixed_len_struct {
     };
 }
 
+macro_rules! owned_payload {
+    (
+        $type:ident
+    ) => {
+        impl<T: AsRef<[u8]>, F> $type<T, F> {
+            /// Converts the payload into one that owns a copy of its bytes.
+            ///
+            /// See [OwnedBuffer](trait.OwnedBuffer.html) for the possible buffer types.
+            pub fn into_owned<B: OwnedBuffer>(self) -> Result<$type<B, F>, Error> {
+                Ok($type(B::from_bytes(self.0.as_ref())?, self.1))
+            }
+        }
+
+        impl<T: AsRef<[u8]>, F: Clone> $type<T, F> {
+            /// Gives a copy of the payload that owns its bytes.
+            ///
+            /// See [OwnedBuffer](trait.OwnedBuffer.html) for the possible buffer types.
+            pub fn to_owned<B: OwnedBuffer>(&self) -> Result<$type<B, F>, Error> {
+                Ok($type(B::from_bytes(self.0.as_ref())?, self.1.clone()))
+            }
+        }
+    };
+}
+
+macro_rules! owned_payload_enum {
+    (
+        $type:ident { $($variant:ident),* }
+    ) => {
+        impl<T: AsRef<[u8]>, F> $type<T, F> {
+            /// Converts the payload into one that owns a copy of its bytes.
+            ///
+            /// See [OwnedBuffer](trait.OwnedBuffer.html) for the possible buffer types.
+            pub fn into_owned<B: OwnedBuffer>(self) -> Result<$type<B, F>, Error> {
+                match self {
+                    $(
+                        $type::$variant(p) => Ok($type::$variant(p.into_owned()?)),
+                    )*
+                }
+            }
+        }
+
+        impl<T: AsRef<[u8]>, F: Clone> $type<T, F> {
+            /// Gives a copy of the payload that owns its bytes.
+            ///
+            /// See [OwnedBuffer](trait.OwnedBuffer.html) for the possible buffer types.
+            pub fn to_owned<B: OwnedBuffer>(&self) -> Result<$type<B, F>, Error> {
+                match self {
+                    $(
+                        $type::$variant(p) => Ok($type::$variant(p.to_owned()?)),
+                    )*
+                }
+            }
+        }
+    };
+}
+
+/// Buffer type that can hold a copy of the bytes of a payload, so that the payload no longer
+/// borrows the data it was parsed from.
+///
+/// It is implemented for `heapless::Vec<u8, N>` and, with the `std` feature, for `Vec<u8>`.
+///
+/// # Examples
+///
+/// ```
+/// use heapless::consts::U256;
+/// use lorawan_encoding::parser::{parse, PhyPayload};
+///
+/// let mut data = vec![0x40, 0x04, 0x03, 0x02, 0x01, 0x80, 0x01, 0x00, 0x01,
+///     0xa6, 0x94, 0x64, 0x26, 0x15, 0xd6, 0xc3, 0xb5, 0x82];
+/// let phy = parse(&mut data[..]).unwrap();
+/// let owned: PhyPayload<heapless::Vec<u8, U256>, _> = phy.into_owned().unwrap();
+/// assert_eq!(owned.as_ref(), &data[..]);
+/// ```
+pub trait OwnedBuffer: AsRef<[u8]> + AsMut<[u8]> + Sized {
+    /// Creates a buffer with a copy of the provided bytes.
+    ///
+    /// # Argument
+    ///
+    /// * bytes - the bytes to be copied.
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error>;
+}
+
+impl<N: ArrayLength<u8>> OwnedBuffer for heapless::Vec<u8, N> {
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut res = heapless::Vec::new();
+        res.extend_from_slice(bytes)
+            .map_err(|_| Error::BufferTooShort)?;
+        Ok(res)
+    }
+}
+
+#[cfg(feature = "std")]
+impl OwnedBuffer for std::vec::Vec<u8> {
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(bytes.to_vec())
+    }
+}
+
 /// PhyPayload is a type that represents a physical LoRaWAN payload.
 ///
-/// It can either be JoinRequest, JoinAccept, or DataPayload.
+/// It can either be JoinRequest, JoinAccept, RejoinRequest, DataPayload or Proprietary.
 #[derive(Debug, PartialEq)]
 pub enum PhyPayload<T, F> {
     JoinRequest(JoinRequestPayload<T, F>),
     JoinAccept(JoinAcceptPayload<T, F>),
+    RejoinRequest(RejoinRequestPayload<T, F>),
     Data(DataPayload<T, F>),
+    Proprietary(ProprietaryPayload<T>),
 }
 
 impl<T: AsRef<[u8]>, F> AsRef<[u8]> for PhyPayload<T, F> {
@@ -120,11 +226,43 @@ impl<T: AsRef<[u8]>, F> AsRef<[u8]> for PhyPayload<T, F> {
         match self {
             PhyPayload::JoinRequest(jr) => jr.as_bytes(),
             PhyPayload::JoinAccept(ja) => ja.as_bytes(),
+            PhyPayload::RejoinRequest(rr) => rr.as_bytes(),
             PhyPayload::Data(data) => data.as_bytes(),
+            PhyPayload::Proprietary(p) => p.as_ref(),
         }
     }
 }
 
+impl<T: AsRef<[u8]>, F> PhyPayload<T, F> {
+    /// Converts the payload into one that owns a copy of its bytes.
+    ///
+    /// See [OwnedBuffer](trait.OwnedBuffer.html) for the possible buffer types.
+    pub fn into_owned<B: OwnedBuffer>(self) -> Result<PhyPayload<B, F>, Error> {
+        Ok(match self {
+            PhyPayload::JoinRequest(jr) => PhyPayload::JoinRequest(jr.into_owned()?),
+            PhyPayload::JoinAccept(ja) => PhyPayload::JoinAccept(ja.into_owned()?),
+            PhyPayload::RejoinRequest(rr) => PhyPayload::RejoinRequest(rr.into_owned()?),
+            PhyPayload::Data(data) => PhyPayload::Data(data.into_owned()?),
+            PhyPayload::Proprietary(p) => PhyPayload::Proprietary(p.into_owned()?),
+        })
+    }
+}
+
+impl<T: AsRef<[u8]>, F: Clone> PhyPayload<T, F> {
+    /// Gives a copy of the payload that owns its bytes.
+    ///
+    /// See [OwnedBuffer](trait.OwnedBuffer.html) for the possible buffer types.
+    pub fn to_owned<B: OwnedBuffer>(&self) -> Result<PhyPayload<B, F>, Error> {
+        Ok(match self {
+            PhyPayload::JoinRequest(jr) => PhyPayload::JoinRequest(jr.to_owned()?),
+            PhyPayload::JoinAccept(ja) => PhyPayload::JoinAccept(ja.to_owned()?),
+            PhyPayload::RejoinRequest(rr) => PhyPayload::RejoinRequest(rr.to_owned()?),
+            PhyPayload::Data(data) => PhyPayload::Data(data.to_owned()?),
+            PhyPayload::Proprietary(p) => PhyPayload::Proprietary(p.to_owned()?),
+        })
+    }
+}
+
 /// JoinAcceptPayload is a type that represents a JoinAccept.
 ///
 /// It can either be encrypted for example as a result from the [parse](fn.parse.html)
@@ -135,6 +273,11 @@ pub enum JoinAcceptPayload<T, F> {
     Decrypted(DecryptedJoinAcceptPayload<T, F>),
 }
 
+owned_payload_enum!(JoinAcceptPayload {
+    Encrypted,
+    Decrypted
+});
+
 impl<T: AsRef<[u8]>, F> AsPhyPayloadBytes for JoinAcceptPayload<T, F> {
     fn as_bytes(&self) -> &[u8] {
         match self {
@@ -144,6 +287,295 @@ impl<T: AsRef<[u8]>, F> AsPhyPayloadBytes for JoinAcceptPayload<T, F> {
     }
 }
 
+/// RejoinRequestPayload is a type that represents a LoRaWAN 1.1 RejoinRequest.
+///
+/// Type 0 and type 2 RejoinRequests share the same format, while type 1 RejoinRequest has its
+/// own.
+#[derive(Debug, PartialEq)]
+pub enum RejoinRequestPayload<T, F> {
+    Type02(RejoinRequestType02Payload<T, F>),
+    Type1(RejoinRequestType1Payload<T, F>),
+}
+
+owned_payload_enum!(RejoinRequestPayload { Type02, Type1 });
+
+impl<T: AsRef<[u8]>, F> AsPhyPayloadBytes for RejoinRequestPayload<T, F> {
+    fn as_bytes(&self) -> &[u8] {
+        match self {
+            RejoinRequestPayload::Type02(r) => r.as_bytes(),
+            RejoinRequestPayload::Type1(r) => r.as_bytes(),
+        }
+    }
+}
+
+impl<T: AsRef<[u8]>, F: CryptoFactory> RejoinRequestPayload<T, F> {
+    /// Creates a new RejoinRequestPayload of the right type if the provided data is acceptable.
+    ///
+    /// # Argument
+    ///
+    /// * data - the bytes for the payload.
+    /// * factory - the factory that shall be used to create object for crypto functions.
+    pub fn new_with_factory(data: T, factory: F) -> Result<Self, Error> {
+        let bytes = data.as_ref();
+        if bytes.len() < 2 {
+            return Err(Error::TooShort);
+        }
+        match bytes[1] {
+            0x00 | 0x02 => Ok(RejoinRequestPayload::Type02(
+                RejoinRequestType02Payload::new_with_factory(data, factory)?,
+            )),
+            0x01 => Ok(RejoinRequestPayload::Type1(
+                RejoinRequestType1Payload::new_with_factory(data, factory)?,
+            )),
+            _ => Err(Error::InvalidRejoinType),
+        }
+    }
+}
+
+impl<T: AsRef<[u8]>, F> RejoinRequestPayload<T, F> {
+    /// Gives the type of the RejoinRequest.
+    pub fn rejoin_type(&self) -> RejoinType {
+        match self {
+            RejoinRequestPayload::Type02(r) => r.rejoin_type(),
+            RejoinRequestPayload::Type1(r) => r.rejoin_type(),
+        }
+    }
+
+    /// Gives the DevEUI of the RejoinRequest.
+    pub fn dev_eui(&self) -> EUI64<&[u8]> {
+        match self {
+            RejoinRequestPayload::Type02(r) => r.dev_eui(),
+            RejoinRequestPayload::Type1(r) => r.dev_eui(),
+        }
+    }
+
+    /// Gives the RJcount0 or RJcount1 of the RejoinRequest depending on its type.
+    pub fn rj_count(&self) -> u16 {
+        match self {
+            RejoinRequestPayload::Type02(r) => r.rj_count(),
+            RejoinRequestPayload::Type1(r) => r.rj_count(),
+        }
+    }
+}
+
+/// RejoinRequestType02Payload represents a RejoinRequest of type 0 or 2.
+///
+/// It can be built either directly through the [new](#method.new) or using the
+/// [parse](fn.parse.html) function.
+#[derive(Debug, PartialEq)]
+pub struct RejoinRequestType02Payload<T, F>(T, F);
+
+owned_payload!(RejoinRequestType02Payload);
+
+impl<T: AsRef<[u8]>, F> AsPhyPayloadBytes for RejoinRequestType02Payload<T, F> {
+    fn as_bytes(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+impl<T: AsRef<[u8]>, F: CryptoFactory> RejoinRequestType02Payload<T, F> {
+    /// Creates a new RejoinRequestType02Payload if the provided data is acceptable.
+    ///
+    /// # Argument
+    ///
+    /// * data - the bytes for the payload.
+    /// * factory - the factory that shall be used to create object for crypto functions.
+    pub fn new_with_factory(data: T, factory: F) -> Result<Self, Error> {
+        Self::can_build_from(data.as_ref())?;
+        Ok(Self(data, factory))
+    }
+
+    fn can_build_from(bytes: &[u8]) -> Result<(), Error> {
+        if bytes.len() != 19 {
+            return Err(Error::InvalidLength);
+        }
+        if MHDR(bytes[0]).mtype() != MType::RejoinRequest {
+            return Err(Error::InvalidMType);
+        }
+        if bytes[1] != 0x00 && bytes[1] != 0x02 {
+            return Err(Error::InvalidRejoinType);
+        }
+        Ok(())
+    }
+
+    /// Verifies that the RejoinRequest has correct MIC.
+    ///
+    /// # Argument
+    ///
+    /// * key - the SNwkSIntKey of the device.
+    pub fn validate_mic(&self, key: &AES128) -> bool {
+        let d = self.0.as_ref();
+        self.mic() == securityhelpers::calculate_mic(&d[..d.len() - 4], self.1.new_mac(key))
+    }
+}
+
+impl<T: AsRef<[u8]>, F> RejoinRequestType02Payload<T, F> {
+    /// Gives the type of the RejoinRequest.
+    pub fn rejoin_type(&self) -> RejoinType {
+        if self.0.as_ref()[1] == 0x00 {
+            RejoinType::Type0
+        } else {
+            RejoinType::Type2
+        }
+    }
+
+    /// Gives the NetID of the RejoinRequest.
+    pub fn net_id(&self) -> NwkAddr<&[u8]> {
+        NwkAddr::new_from_raw(&self.0.as_ref()[2..5])
+    }
+
+    /// Gives the DevEUI of the RejoinRequest.
+    pub fn dev_eui(&self) -> EUI64<&[u8]> {
+        EUI64::new_from_raw(&self.0.as_ref()[5..13])
+    }
+
+    /// Gives the RJcount0 of the RejoinRequest.
+    pub fn rj_count(&self) -> u16 {
+        let d = self.0.as_ref();
+        (u16::from(d[14]) << 8) | u16::from(d[13])
+    }
+}
+
+/// RejoinRequestType1Payload represents a RejoinRequest of type 1.
+///
+/// It can be built either directly through the [new](#method.new) or using the
+/// [parse](fn.parse.html) function.
+#[derive(Debug, PartialEq)]
+pub struct RejoinRequestType1Payload<T, F>(T, F);
+
+owned_payload!(RejoinRequestType1Payload);
+
+impl<T: AsRef<[u8]>, F> AsPhyPayloadBytes for RejoinRequestType1Payload<T, F> {
+    fn as_bytes(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+impl<T: AsRef<[u8]>, F: CryptoFactory> RejoinRequestType1Payload<T, F> {
+    /// Creates a new RejoinRequestType1Payload if the provided data is acceptable.
+    ///
+    /// # Argument
+    ///
+    /// * data - the bytes for the payload.
+    /// * factory - the factory that shall be used to create object for crypto functions.
+    pub fn new_with_factory(data: T, factory: F) -> Result<Self, Error> {
+        Self::can_build_from(data.as_ref())?;
+        Ok(Self(data, factory))
+    }
+
+    fn can_build_from(bytes: &[u8]) -> Result<(), Error> {
+        if bytes.len() != 24 {
+            return Err(Error::InvalidLength);
+        }
+        if MHDR(bytes[0]).mtype() != MType::RejoinRequest {
+            return Err(Error::InvalidMType);
+        }
+        if bytes[1] != 0x01 {
+            return Err(Error::InvalidRejoinType);
+        }
+        Ok(())
+    }
+
+    /// Verifies that the RejoinRequest has correct MIC.
+    ///
+    /// # Argument
+    ///
+    /// * key - the JSIntKey of the device, see
+    ///   [derive_jsintkey](../keys/fn.derive_jsintkey.html).
+    pub fn validate_mic(&self, key: &AES128) -> bool {
+        let d = self.0.as_ref();
+        self.mic() == securityhelpers::calculate_mic(&d[..d.len() - 4], self.1.new_mac(key))
+    }
+}
+
+impl<T: AsRef<[u8]>, F> RejoinRequestType1Payload<T, F> {
+    /// Gives the type of the RejoinRequest.
+    pub fn rejoin_type(&self) -> RejoinType {
+        RejoinType::Type1
+    }
+
+    /// Gives the JoinEUI of the RejoinRequest.
+    pub fn join_eui(&self) -> EUI64<&[u8]> {
+        EUI64::new_from_raw(&self.0.as_ref()[2..10])
+    }
+
+    /// Gives the DevEUI of the RejoinRequest.
+    pub fn dev_eui(&self) -> EUI64<&[u8]> {
+        EUI64::new_from_raw(&self.0.as_ref()[10..18])
+    }
+
+    /// Gives the RJcount1 of the RejoinRequest.
+    pub fn rj_count(&self) -> u16 {
+        let d = self.0.as_ref();
+        (u16::from(d[19]) << 8) | u16::from(d[18])
+    }
+}
+
+/// ProprietaryPayload represents a PhyPayload with MType Proprietary.
+///
+/// The format of such payloads is not defined by LoRaWAN, so apart from the MHDR the bytes are
+/// exposed as they are. In particular no MIC is assumed to be present.
+#[derive(Debug, PartialEq)]
+pub struct ProprietaryPayload<T>(T);
+
+impl<T: AsRef<[u8]>> ProprietaryPayload<T> {
+    /// Converts the payload into one that owns a copy of its bytes.
+    ///
+    /// See [OwnedBuffer](trait.OwnedBuffer.html) for the possible buffer types.
+    pub fn into_owned<B: OwnedBuffer>(self) -> Result<ProprietaryPayload<B>, Error> {
+        self.to_owned()
+    }
+
+    /// Gives a copy of the payload that owns its bytes.
+    ///
+    /// See [OwnedBuffer](trait.OwnedBuffer.html) for the possible buffer types.
+    pub fn to_owned<B: OwnedBuffer>(&self) -> Result<ProprietaryPayload<B>, Error> {
+        Ok(ProprietaryPayload(B::from_bytes(self.0.as_ref())?))
+    }
+}
+
+impl<T: AsRef<[u8]>> AsRef<[u8]> for ProprietaryPayload<T> {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+impl<T: AsRef<[u8]>> ProprietaryPayload<T> {
+    /// Creates a new ProprietaryPayload if the provided data is acceptable.
+    ///
+    /// # Argument
+    ///
+    /// * data - the bytes for the payload, including the MHDR.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let data = vec![0xe0, 0x01, 0x02, 0x03];
+    /// let phy = lorawan_encoding::parser::ProprietaryPayload::new(data).unwrap();
+    /// assert_eq!(phy.payload(), &[0x01, 0x02, 0x03]);
+    /// ```
+    pub fn new(data: T) -> Result<Self, Error> {
+        let bytes = data.as_ref();
+        if bytes.is_empty() {
+            return Err(Error::TooShort);
+        }
+        if MHDR(bytes[0]).mtype() != MType::Proprietary {
+            return Err(Error::InvalidMType);
+        }
+        Ok(Self(data))
+    }
+
+    /// Gives the MHDR of the ProprietaryPayload.
+    pub fn mhdr(&self) -> MHDR {
+        MHDR(self.0.as_ref()[0])
+    }
+
+    /// Gives the bytes following the MHDR.
+    pub fn payload(&self) -> &[u8] {
+        &self.0.as_ref()[1..]
+    }
+}
+
 /// DataPayload is a type that represents a ConfirmedDataUp, ConfirmedDataDown,
 /// UnconfirmedDataUp or UnconfirmedDataDown.
 ///
@@ -155,6 +587,11 @@ pub enum DataPayload<T, F> {
     Decrypted(DecryptedDataPayload<T>),
 }
 
+owned_payload_enum!(DataPayload {
+    Encrypted,
+    Decrypted
+});
+
 impl<T: AsRef<[u8]>, F> DataHeader for DataPayload<T, F> {
     fn as_data_bytes(&self) -> &[u8] {
         match self {
@@ -212,6 +649,8 @@ impl<T: AsPhyPayloadBytes> MHDRAble for T {
 #[derive(Debug, PartialEq)]
 pub struct JoinRequestPayload<T, F>(T, F);
 
+owned_payload!(JoinRequestPayload);
+
 impl<T: AsRef<[u8]>, F> AsPhyPayloadBytes for JoinRequestPayload<T, F> {
     fn as_bytes(&self) -> &[u8] {
         self.0.as_ref()
@@ -233,16 +672,19 @@ impl<T: AsRef<[u8]>, F: CryptoFactory> JoinRequestPayload<T, F> {
     /// let phy = lorawan_encoding::parser::JoinRequestPayload::new_with_factory(data,
     ///     lorawan_encoding::default_crypto::DefaultFactory);
     /// ```
-    pub fn new_with_factory<'a>(data: T, factory: F) -> Result<Self, &'a str> {
-        if !Self::can_build_from(data.as_ref()) {
-            Err("can not build JoinRequestPayload from the provided data")
-        } else {
-            Ok(Self(data, factory))
-        }
+    pub fn new_with_factory(data: T, factory: F) -> Result<Self, Error> {
+        Self::can_build_from(data.as_ref())?;
+        Ok(Self(data, factory))
     }
 
-    fn can_build_from(bytes: &[u8]) -> bool {
-        bytes.len() == 23 && MHDR(bytes[0]).mtype() == MType::JoinRequest
+    fn can_build_from(bytes: &[u8]) -> Result<(), Error> {
+        if bytes.len() != 23 {
+            return Err(Error::InvalidLength);
+        }
+        if MHDR(bytes[0]).mtype() != MType::JoinRequest {
+            return Err(Error::InvalidMType);
+        }
+        Ok(())
     }
 
     /// Gives the APP EUI of the JoinRequest.
@@ -278,6 +720,8 @@ impl<T: AsRef<[u8]>, F: CryptoFactory> JoinRequestPayload<T, F> {
 #[derive(Debug, PartialEq)]
 pub struct EncryptedJoinAcceptPayload<T, F>(T, F);
 
+owned_payload!(EncryptedJoinAcceptPayload);
+
 impl<T: AsRef<[u8]>, F> AsPhyPayloadBytes for EncryptedJoinAcceptPayload<T, F> {
     fn as_bytes(&self) -> &[u8] {
         self.0.as_ref()
@@ -291,16 +735,19 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>, F: CryptoFactory> EncryptedJoinAcceptPayload<
     ///
     /// * data - the bytes for the payload.
     /// * factory - the factory that shall be used to create object for crypto functions.
-    pub fn new_with_factory<'a>(data: T, factory: F) -> Result<Self, &'a str> {
-        if Self::can_build_from(data.as_ref()) {
-            Ok(Self(data, factory))
-        } else {
-            Err("can not build EncryptedJoinAcceptPayload from the provided data")
-        }
+    pub fn new_with_factory(data: T, factory: F) -> Result<Self, Error> {
+        Self::can_build_from(data.as_ref())?;
+        Ok(Self(data, factory))
     }
 
-    fn can_build_from(bytes: &[u8]) -> bool {
-        (bytes.len() == 17 || bytes.len() == 33) && MHDR(bytes[0]).mtype() == MType::JoinAccept
+    fn can_build_from(bytes: &[u8]) -> Result<(), Error> {
+        if bytes.len() != 17 && bytes.len() != 33 {
+            return Err(Error::InvalidLength);
+        }
+        if MHDR(bytes[0]).mtype() != MType::JoinAccept {
+            return Err(Error::InvalidMType);
+        }
+        Ok(())
     }
 
     /// Decrypts the EncryptedJoinAcceptPayload producing a DecryptedJoinAcceptPayload.
@@ -346,6 +793,17 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>, F: CryptoFactory> EncryptedJoinAcceptPayload<
 #[derive(Debug, PartialEq)]
 pub struct DecryptedJoinAcceptPayload<T, F>(T, F);
 
+owned_payload!(DecryptedJoinAcceptPayload);
+
+impl<T: AsRef<[u8]>, F> DecryptedJoinAcceptPayload<T, F> {
+    /// Creates a DecryptedJoinAcceptPayload from bytes that are already known to be a valid and
+    /// decrypted JoinAccept.
+    #[cfg(feature = "serde")]
+    pub(crate) fn new_from_raw(data: T, factory: F) -> Self {
+        DecryptedJoinAcceptPayload(data, factory)
+    }
+}
+
 impl<T: AsRef<[u8]>, F> AsPhyPayloadBytes for DecryptedJoinAcceptPayload<T, F> {
     fn as_bytes(&self) -> &[u8] {
         self.0.as_ref()
@@ -433,31 +891,144 @@ impl<T: AsRef<[u8]>, F: CryptoFactory> DecryptedJoinAcceptPayload<T, F> {
         dev_nonce: &DevNonce<TT>,
         key: &AES128,
     ) -> AES128 {
-        let cipher = self.1.new_enc(key);
-
         // note: AppNonce is 24 bit, NetId is 24 bit, DevNonce is 16 bit
-        let app_nonce = self.app_nonce();
-        let nwk_addr = self.net_id();
-        let (app_nonce_arr, nwk_addr_arr, dev_nonce_arr) =
-            (app_nonce.as_ref(), nwk_addr.as_ref(), dev_nonce.as_ref());
-
-        let mut block = [0u8; 16];
+        let mut block = [0u8; 9];
         block[0] = first_byte;
-        block[1] = app_nonce_arr[0];
-        block[2] = app_nonce_arr[1];
-        block[3] = app_nonce_arr[2];
-        block[4] = nwk_addr_arr[0];
-        block[5] = nwk_addr_arr[1];
-        block[6] = nwk_addr_arr[2];
-        block[7] = dev_nonce_arr[0];
-        block[8] = dev_nonce_arr[1];
+        block[1..4].copy_from_slice(self.app_nonce().as_ref());
+        block[4..7].copy_from_slice(self.net_id().as_ref());
+        block[7..9].copy_from_slice(dev_nonce.as_ref());
 
-        let mut input = generic_array::GenericArray::clone_from_slice(&block);
-        cipher.encrypt_block(&mut input);
+        securityhelpers::derive_key(&self.1.new_enc(key), &block[..])
+    }
 
-        let mut output_key = [0u8; 16];
-        output_key.copy_from_slice(&input[0..16]);
-        AES128(output_key)
+    /// Verifies that the LoRaWAN 1.1 JoinAccept has correct MIC.
+    ///
+    /// This MIC is used when the OptNeg bit of the DLSettings is set, otherwise the JoinAccept
+    /// uses the LoRaWAN 1.0 MIC computed with NwkKey and [validate_mic](#method.validate_mic)
+    /// should be used.
+    ///
+    /// # Argument
+    ///
+    /// * js_int_key - the JSIntKey derived from NwkKey, see
+    ///   [derive_jsintkey](../keys/fn.derive_jsintkey.html).
+    /// * join_req_type - the type of the request that the JoinAccept answers.
+    /// * join_eui - the JoinEUI of the device.
+    /// * dev_nonce - the nonce from the device.
+    pub fn validate_mic_1_1<TT: AsRef<[u8]>, TU: AsRef<[u8]>>(
+        &self,
+        js_int_key: &AES128,
+        join_req_type: JoinReqType,
+        join_eui: &EUI64<TT>,
+        dev_nonce: &DevNonce<TU>,
+    ) -> bool {
+        let d = self.0.as_ref();
+        self.mic()
+            == securityhelpers::calculate_join_accept_mic_1_1(
+                &d[..d.len() - 4],
+                self.1.new_mac(js_int_key),
+                join_req_type.into(),
+                join_eui.as_ref(),
+                dev_nonce.as_ref(),
+            )
+    }
+
+    /// Computes the LoRaWAN 1.1 forwarding network session integrity key for a given device.
+    ///
+    /// # Argument
+    ///
+    /// * join_eui - the JoinEUI of the device.
+    /// * dev_nonce - the nonce from the device.
+    /// * nwk_key - the network key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let dev_nonce = vec![0xcc, 0xdd];
+    /// let join_eui = vec![0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+    /// let data = vec![0x20, 0x49, 0x3e, 0xeb, 0x51, 0xfb, 0xa2, 0x11, 0x6f, 0x81, 0x0e, 0xdb, 0x37,
+    ///     0x42, 0x97, 0x51, 0x42];
+    /// let nwk_key = lorawan_encoding::keys::AES128([0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88,
+    ///     0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff]);
+    /// let join_accept = lorawan_encoding::parser::DecryptedJoinAcceptPayload::new(data, &nwk_key).unwrap();
+    ///
+    /// let f_nwk_s_int_key = join_accept.derive_fnwksintkey(
+    ///     &lorawan_encoding::parser::EUI64::new(&join_eui[..]).unwrap(),
+    ///     &lorawan_encoding::parser::DevNonce::new(&dev_nonce[..]).unwrap(),
+    ///     &nwk_key,
+    /// );
+    /// ```
+    pub fn derive_fnwksintkey<TT: AsRef<[u8]>, TU: AsRef<[u8]>>(
+        &self,
+        join_eui: &EUI64<TT>,
+        dev_nonce: &DevNonce<TU>,
+        nwk_key: &AES128,
+    ) -> AES128 {
+        self.derive_session_key_1_1(0x1, join_eui, dev_nonce, nwk_key)
+    }
+
+    /// Computes the LoRaWAN 1.1 serving network session integrity key for a given device.
+    ///
+    /// # Argument
+    ///
+    /// * join_eui - the JoinEUI of the device.
+    /// * dev_nonce - the nonce from the device.
+    /// * nwk_key - the network key.
+    pub fn derive_snwksintkey<TT: AsRef<[u8]>, TU: AsRef<[u8]>>(
+        &self,
+        join_eui: &EUI64<TT>,
+        dev_nonce: &DevNonce<TU>,
+        nwk_key: &AES128,
+    ) -> AES128 {
+        self.derive_session_key_1_1(0x3, join_eui, dev_nonce, nwk_key)
+    }
+
+    /// Computes the LoRaWAN 1.1 network session encryption key for a given device.
+    ///
+    /// # Argument
+    ///
+    /// * join_eui - the JoinEUI of the device.
+    /// * dev_nonce - the nonce from the device.
+    /// * nwk_key - the network key.
+    pub fn derive_nwksenckey<TT: AsRef<[u8]>, TU: AsRef<[u8]>>(
+        &self,
+        join_eui: &EUI64<TT>,
+        dev_nonce: &DevNonce<TU>,
+        nwk_key: &AES128,
+    ) -> AES128 {
+        self.derive_session_key_1_1(0x4, join_eui, dev_nonce, nwk_key)
+    }
+
+    /// Computes the LoRaWAN 1.1 application session key for a given device.
+    ///
+    /// # Argument
+    ///
+    /// * join_eui - the JoinEUI of the device.
+    /// * dev_nonce - the nonce from the device.
+    /// * app_key - the application key.
+    pub fn derive_appskey_1_1<TT: AsRef<[u8]>, TU: AsRef<[u8]>>(
+        &self,
+        join_eui: &EUI64<TT>,
+        dev_nonce: &DevNonce<TU>,
+        app_key: &AES128,
+    ) -> AES128 {
+        self.derive_session_key_1_1(0x2, join_eui, dev_nonce, app_key)
+    }
+
+    fn derive_session_key_1_1<TT: AsRef<[u8]>, TU: AsRef<[u8]>>(
+        &self,
+        first_byte: u8,
+        join_eui: &EUI64<TT>,
+        dev_nonce: &DevNonce<TU>,
+        key: &AES128,
+    ) -> AES128 {
+        // note: JoinNonce is 24 bit, JoinEUI is 64 bit, DevNonce is 16 bit
+        let mut block = [0u8; 14];
+        block[0] = first_byte;
+        block[1..4].copy_from_slice(self.app_nonce().as_ref());
+        block[4..12].copy_from_slice(join_eui.as_ref());
+        block[12..14].copy_from_slice(dev_nonce.as_ref());
+
+        securityhelpers::derive_key(&self.1.new_enc(key), &block[..])
     }
 }
 
@@ -487,7 +1058,21 @@ impl<T: AsRef<[u8]>, F> DecryptedJoinAcceptPayload<T, F> {
         self.0.as_ref()[12] & 0x0f
     }
 
+    /// Gives the CFListType of the JoinAccept, if it has a CFList.
+    ///
+    /// The CFList is a list of frequencies for type 0 and a list of channel masks for type 1.
+    pub fn c_f_list_type(&self) -> Option<u8> {
+        let d = self.0.as_ref();
+        if d.len() == 17 {
+            return None;
+        }
+        Some(d[28])
+    }
+
     /// Gives the channel frequency list of the JoinAccept.
+    ///
+    /// The CFList is read as a frequency list whatever its type, see
+    /// [c_f_list_type](#method.c_f_list_type).
     pub fn c_f_list(&self) -> Option<[Frequency; 5]> {
         if self.0.as_ref().len() == 17 {
             return None;
@@ -515,13 +1100,13 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>, F: CryptoFactory> DecryptedJoinAcceptPayload<
     /// * bytes - the data from which the PhyPayload is to be built.
     /// * key - the key that is to be used to decrypt the payload.
     /// * factory - the factory that shall be used to create object for crypto functions.
-    pub fn new_with_factory<'a, 'b>(data: T, key: &'a AES128, factory: F) -> Result<Self, &'b str> {
+    pub fn new_with_factory(data: T, key: &AES128, factory: F) -> Result<Self, Error> {
         let t = EncryptedJoinAcceptPayload::new_with_factory(data, factory)?;
         let res = t.decrypt(key);
         if res.validate_mic(key) {
             Ok(res)
         } else {
-            Err("MIC did not match")
+            Err(Error::InvalidMic)
         }
     }
 }
@@ -582,6 +1167,8 @@ impl<T: DataHeader> AsPhyPayloadBytes for T {
 #[derive(Debug, PartialEq)]
 pub struct EncryptedDataPayload<T, F>(T, F);
 
+owned_payload!(EncryptedDataPayload);
+
 impl<T: AsRef<[u8]>, F> DataHeader for EncryptedDataPayload<T, F> {
     fn as_data_bytes(&self) -> &[u8] {
         self.0.as_ref()
@@ -595,39 +1182,169 @@ impl<T: AsRef<[u8]>, F: CryptoFactory> EncryptedDataPayload<T, F> {
     ///
     /// * data - the bytes for the payload.
     /// * factory - the factory that shall be used to create object for crypto functions.
-    pub fn new_with_factory<'a>(data: T, factory: F) -> Result<Self, &'a str> {
-        if Self::can_build_from(data.as_ref()) {
-            Ok(Self(data, factory))
-        } else {
-            Err("can not build EncryptedDataPayload from the provided data")
-        }
+    pub fn new_with_factory(data: T, factory: F) -> Result<Self, Error> {
+        Self::can_build_from(data.as_ref())?;
+        Ok(Self(data, factory))
     }
 
-    fn can_build_from(bytes: &[u8]) -> bool {
-        let has_acceptable_len = bytes.len() >= 12 &&
-            // TODO: Bug related to possibly insufficient number of bytes
-            fhdr_length(bytes[5]) <= bytes.len();
-        if !has_acceptable_len {
-            return false;
+    fn can_build_from(bytes: &[u8]) -> Result<(), Error> {
+        if bytes.len() < 12 {
+            return Err(Error::TooShort);
+        }
+        // MHDR + FHDR + MIC must fit in the payload
+        if 1 + fhdr_length(bytes[5]) + 4 > bytes.len() {
+            return Err(Error::InvalidFOptsLen);
         }
         match MHDR(bytes[0]).mtype() {
             MType::ConfirmedDataUp
             | MType::ConfirmedDataDown
             | MType::UnconfirmedDataUp
-            | MType::UnconfirmedDataDown => true,
-            _ => false,
+            | MType::UnconfirmedDataDown => Ok(()),
+            _ => Err(Error::InvalidMType),
         }
     }
 
     /// Verifies that the DataPayload has correct MIC.
+    ///
+    /// # Argument
+    ///
+    /// * key - the Network Session key.
+    /// * fcnt - the full 32 bit FCnt of the payload or the last one seen, from which the full FCnt
+    ///   is reconstructed with [reconstruct_fcnt](fn.reconstruct_fcnt.html).
+    ///
+    /// Note that `fcnt` is the lower bound of the reconstructed FCnt. Before 0.6.0 only its upper
+    /// 16 bits were used, now a frame whose FCnt is below the lower 16 bits of `fcnt` is taken as
+    /// a rollover. Passing the next expected FCnt instead of the last one seen hence makes a
+    /// repeated frame, e.g. an NbTrans retransmission, fail the check.
     pub fn validate_mic(&self, key: &AES128, fcnt: u32) -> bool {
-        self.mic() == self.calculate_mic(key, fcnt)
+        match self.full_fcnt(fcnt) {
+            Ok(fcnt) => self.mic() == self.calculate_mic(key, fcnt),
+            Err(_) => false,
+        }
+    }
+
+    fn full_fcnt(&self, fcnt: u32) -> Result<u32, Error> {
+        reconstruct_fcnt(fcnt, self.fhdr().fcnt(), u32::MAX).map(|f| f.fcnt)
     }
 
     fn calculate_mic(&self, key: &AES128, fcnt: u32) -> MIC {
         let d = self.0.as_ref();
         securityhelpers::calculate_data_mic(&d[..d.len() - 4], self.1.new_mac(key), fcnt)
     }
+
+    /// Finds the session of the payload among the sessions sharing its DevAddr.
+    ///
+    /// Each candidate gives the NwkSKey of a session and the last FCnt seen for it. The MIC is
+    /// checked with the upper 16 bits of the last FCnt followed by the FCnt of the payload and,
+    /// when that is smaller than the last FCnt, with the FCnt following a rollover of the 16 bit
+    /// counter. The CMAC state of each key is derived only once for all its FCnt candidates.
+    ///
+    /// Returns the index of the first matching candidate together with the full 32 bit FCnt of
+    /// the payload, which can be given to [decrypt](#method.decrypt).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let data = vec![0x40, 0x04, 0x03, 0x02, 0x01, 0x80, 0x01, 0x00, 0x01,
+    ///     0xa6, 0x94, 0x64, 0x26, 0x15, 0xd6, 0xc3, 0xb5, 0x82];
+    /// let other_nwk_skey = lorawan_encoding::keys::AES128([1; 16]);
+    /// let nwk_skey = lorawan_encoding::keys::AES128([2; 16]);
+    /// let phy = lorawan_encoding::parser::EncryptedDataPayload::new(data).unwrap();
+    /// let found = phy.find_session(vec![(&other_nwk_skey, 0), (&nwk_skey, 0)]).unwrap();
+    /// assert_eq!(found.index, 1);
+    /// assert_eq!(found.fcnt, 1);
+    /// ```
+    pub fn find_session<'a, I>(&self, candidates: I) -> Option<SessionMatch>
+    where
+        I: IntoIterator<Item = (&'a AES128, u32)>,
+        F::M: Clone,
+    {
+        let d = self.0.as_ref();
+        let data = &d[..d.len() - 4];
+        let mic = self.mic();
+        let fcnt = self.fhdr().fcnt();
+        candidates
+            .into_iter()
+            .enumerate()
+            .find_map(|(index, (key, last_fcnt))| {
+                let same_epoch = (last_fcnt & 0xffff_0000) | u32::from(fcnt);
+                let rolled_over = reconstruct_fcnt(last_fcnt, fcnt, u32::MAX)
+                    .ok()
+                    .filter(|f| f.rolled_over);
+                let fcnts = [same_epoch, rolled_over.map_or(same_epoch, |f| f.fcnt)];
+                let len = if rolled_over.is_some() { 2 } else { 1 };
+                let key = self.1.new_mac(key);
+                securityhelpers::find_data_mic_fcnt(data, &mic, key, &fcnts[..len])
+                    .map(|fcnt| SessionMatch { index, fcnt })
+            })
+    }
+
+    /// Verifies that the LoRaWAN 1.1 uplink DataPayload has correct MIC.
+    ///
+    /// # Argument
+    ///
+    /// * s_nwk_s_int_key - the Serving Network session integrity key.
+    /// * f_nwk_s_int_key - the Forwarding Network session integrity key.
+    /// * fcnt - the full 32 bit FCntUp of the payload or the last one seen, see
+    ///   [validate_mic](#method.validate_mic).
+    /// * conf_fcnt - the FCnt of the confirmed downlink being acknowledged. It is ignored if the
+    ///   ACK bit is not set.
+    /// * tx_dr - the data rate used for the transmission of the payload.
+    /// * tx_ch - the index of the channel used for the transmission of the payload.
+    pub fn validate_uplink_mic_1_1(
+        &self,
+        s_nwk_s_int_key: &AES128,
+        f_nwk_s_int_key: &AES128,
+        fcnt: u32,
+        conf_fcnt: u16,
+        tx_dr: u8,
+        tx_ch: u8,
+    ) -> bool {
+        let fcnt = match self.full_fcnt(fcnt) {
+            Ok(fcnt) => fcnt,
+            Err(_) => return false,
+        };
+        let d = self.0.as_ref();
+        self.mic()
+            == securityhelpers::calculate_uplink_data_mic_1_1(
+                &d[..d.len() - 4],
+                self.1.new_mac(s_nwk_s_int_key),
+                self.1.new_mac(f_nwk_s_int_key),
+                fcnt,
+                conf_fcnt,
+                tx_dr,
+                tx_ch,
+            )
+    }
+
+    /// Verifies that the LoRaWAN 1.1 downlink DataPayload has correct MIC.
+    ///
+    /// # Argument
+    ///
+    /// * s_nwk_s_int_key - the Serving Network session integrity key.
+    /// * fcnt - the full 32 bit NFCntDown or AFCntDown of the payload or the last one seen, see
+    ///   [validate_mic](#method.validate_mic).
+    /// * conf_fcnt - the FCntUp of the confirmed uplink being acknowledged. It is ignored if the
+    ///   ACK bit is not set.
+    pub fn validate_downlink_mic_1_1(
+        &self,
+        s_nwk_s_int_key: &AES128,
+        fcnt: u32,
+        conf_fcnt: u16,
+    ) -> bool {
+        let fcnt = match self.full_fcnt(fcnt) {
+            Ok(fcnt) => fcnt,
+            Err(_) => return false,
+        };
+        let d = self.0.as_ref();
+        self.mic()
+            == securityhelpers::calculate_downlink_data_mic_1_1(
+                &d[..d.len() - 4],
+                self.1.new_mac(s_nwk_s_int_key),
+                fcnt,
+                conf_fcnt,
+            )
+    }
 }
 
 impl<T: AsRef<[u8]> + AsMut<[u8]>, F: CryptoFactory> EncryptedDataPayload<T, F> {
@@ -647,7 +1364,11 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>, F: CryptoFactory> EncryptedDataPayload<T, F>
     ///     is transporting those.
     /// * app_skey - the Application Session key used to decrypt the application payload in case
     ///     the payload is transporting that.
-    /// * fcnt - the counter used to encrypt the payload.
+    /// * fcnt - the full 32 bit FCnt used to encrypt the payload or the last one seen, from which
+    ///   the full FCnt is reconstructed with [reconstruct_fcnt](fn.reconstruct_fcnt.html).
+    ///
+    /// As for [validate_mic](#method.validate_mic), `fcnt` must not be above the FCnt of the
+    /// payload, otherwise it is decrypted as if the 16 bit counter rolled over.
     ///
     /// # Examples
     ///
@@ -658,22 +1379,21 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>, F: CryptoFactory> EncryptedDataPayload<T, F>
     /// let enc_phy = lorawan_encoding::parser::EncryptedDataPayload::new(data).unwrap();
     /// let dec_phy = enc_phy.decrypt(None, Some(&key), 1);
     /// ```
-    pub fn decrypt<'a, 'b>(
+    pub fn decrypt(
         mut self,
-        nwk_skey: Option<&'a AES128>,
-        app_skey: Option<&'a AES128>,
+        nwk_skey: Option<&AES128>,
+        app_skey: Option<&AES128>,
         fcnt: u32,
-    ) -> Result<DecryptedDataPayload<T>, &'b str> {
+    ) -> Result<DecryptedDataPayload<T>, Error> {
         let fhdr_length = self.fhdr_length();
-        let fhdr = self.fhdr();
-        let full_fcnt = compute_fcnt(fcnt, fhdr.fcnt());
+        let full_fcnt = self.full_fcnt(fcnt)?;
         let key = if self.f_port().is_some() && self.f_port().unwrap() != 0 {
             app_skey
         } else {
             nwk_skey
         };
         if key.is_none() {
-            return Err("key needed to decrypt the frm data payload was None");
+            return Err(Error::MissingKey);
         }
         let data = self.0.as_mut();
         let len = data.len();
@@ -692,6 +1412,42 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>, F: CryptoFactory> EncryptedDataPayload<T, F>
         Ok(DecryptedDataPayload(self.0))
     }
 
+    /// Decrypts the LoRaWAN 1.1 EncryptedDataPayload payload and FOpts.
+    ///
+    /// This method consumes the EncryptedDataPayload as it reuses the underlying memory. Please
+    /// note that it does not verify the mic.
+    ///
+    /// The FOpts and the mac commands transported in FRMPayload are decrypted with nwk_s_enc_key,
+    /// while the application payload is decrypted with app_skey. Either of the keys can be None
+    /// if it is not needed for the given payload, otherwise an Err is returned.
+    ///
+    /// # Argument
+    ///
+    /// * nwk_s_enc_key - the Network session encryption key.
+    /// * app_skey - the Application Session key.
+    /// * fcnt - the full 32 bit FCnt used to encrypt the payload or the last one seen, see
+    ///   [validate_mic](#method.validate_mic).
+    pub fn decrypt_1_1(
+        mut self,
+        nwk_s_enc_key: Option<&AES128>,
+        app_skey: Option<&AES128>,
+        fcnt: u32,
+    ) -> Result<DecryptedDataPayload<T>, Error> {
+        let f_opts_len = self.fhdr().fctrl().f_opts_len();
+        if f_opts_len > 0 {
+            let key = nwk_s_enc_key.ok_or(Error::MissingKey)?;
+            let full_fcnt = self.full_fcnt(fcnt)?;
+            let f_port = self.f_port();
+            securityhelpers::encrypt_fopts(
+                self.0.as_mut(),
+                f_port,
+                full_fcnt,
+                &self.1.new_enc(key),
+            );
+        }
+        self.decrypt(nwk_s_enc_key, app_skey, fcnt)
+    }
+
     /// Verifies the mic and decrypts the EncryptedDataPayload payload if mic matches.
     ///
     /// This is helper method that combines validate_mic and decrypt. In case the mic is fine, it
@@ -713,8 +1469,73 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>, F: CryptoFactory> EncryptedDataPayload<T, F>
     }
 }
 
-fn compute_fcnt(old_fcnt: u32, fcnt: u16) -> u32 {
-    ((old_fcnt >> 16) << 16) ^ u32::from(fcnt)
+/// SessionMatch identifies the session of a data payload among candidate sessions.
+///
+/// It is returned by [EncryptedDataPayload.find_session](struct.EncryptedDataPayload.html#method.find_session).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionMatch {
+    /// The index of the matching candidate.
+    pub index: usize,
+    /// The full 32 bit FCnt of the payload.
+    pub fcnt: u32,
+}
+
+/// The largest increase of the frame counter between consecutive frames of a session accepted by
+/// LoRaWAN 1.0.
+pub const MAX_FCNT_GAP: u32 = 16384;
+
+/// FullFCnt represents a 32 bit frame counter reconstructed from the 16 bits sent in FHDR.
+///
+/// It is returned by [reconstruct_fcnt](fn.reconstruct_fcnt.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FullFCnt {
+    /// The full 32 bit frame counter.
+    pub fcnt: u32,
+    /// Whether the 16 bit frame counter rolled over since the last frame counter.
+    pub rolled_over: bool,
+}
+
+/// Reconstructs the full 32 bit frame counter of a frame from the last one seen in the session.
+///
+/// The frame counter is the smallest one that is not lower than `last_fcnt` and ends with the 16
+/// bits sent in FHDR, so the 16 bit counter rolled over when they are lower than the ones of
+/// `last_fcnt`. A frame repeating the last frame counter gets `last_fcnt`.
+///
+/// # Argument
+///
+/// * last_fcnt - the full frame counter of the last frame of the session.
+/// * fcnt - the 16 bit frame counter from FHDR.
+/// * max_gap - the largest admissible increase from `last_fcnt`, e.g. MAX_FCNT_GAP, or u32::MAX
+///   to accept any frame counter.
+///
+/// # Examples
+///
+/// ```
+/// use lorawan_encoding::parser::{reconstruct_fcnt, FullFCnt, MAX_FCNT_GAP};
+///
+/// let res = reconstruct_fcnt(0x1_fffe, 0x0001, MAX_FCNT_GAP);
+/// assert_eq!(res, Ok(FullFCnt { fcnt: 0x2_0001, rolled_over: true }));
+/// assert!(reconstruct_fcnt(0x1_0010, 0x8000, MAX_FCNT_GAP).is_err());
+/// ```
+pub fn reconstruct_fcnt(last_fcnt: u32, fcnt: u16, max_gap: u32) -> Result<FullFCnt, Error> {
+    let same_epoch = (last_fcnt & 0xffff_0000) | u32::from(fcnt);
+    let res = if same_epoch < last_fcnt {
+        FullFCnt {
+            fcnt: same_epoch
+                .checked_add(0x1_0000)
+                .ok_or(Error::FCntGapTooLarge)?,
+            rolled_over: true,
+        }
+    } else {
+        FullFCnt {
+            fcnt: same_epoch,
+            rolled_over: false,
+        }
+    };
+    if res.fcnt - last_fcnt > max_gap {
+        return Err(Error::FCntGapTooLarge);
+    }
+    Ok(res)
 }
 
 /// DecryptedDataPayload represents a decrypted DataPayload.
@@ -724,6 +1545,31 @@ fn compute_fcnt(old_fcnt: u32, fcnt: u16) -> u32 {
 #[derive(Debug, PartialEq)]
 pub struct DecryptedDataPayload<T>(T);
 
+impl<T: AsRef<[u8]>> DecryptedDataPayload<T> {
+    /// Converts the payload into one that owns a copy of its bytes.
+    ///
+    /// See [OwnedBuffer](trait.OwnedBuffer.html) for the possible buffer types.
+    pub fn into_owned<B: OwnedBuffer>(self) -> Result<DecryptedDataPayload<B>, Error> {
+        self.to_owned()
+    }
+
+    /// Gives a copy of the payload that owns its bytes.
+    ///
+    /// See [OwnedBuffer](trait.OwnedBuffer.html) for the possible buffer types.
+    pub fn to_owned<B: OwnedBuffer>(&self) -> Result<DecryptedDataPayload<B>, Error> {
+        Ok(DecryptedDataPayload(B::from_bytes(self.0.as_ref())?))
+    }
+}
+
+impl<T: AsRef<[u8]>> DecryptedDataPayload<T> {
+    /// Creates a DecryptedDataPayload from bytes that are already known to be a valid and
+    /// decrypted data payload.
+    #[cfg(feature = "serde")]
+    pub(crate) fn new_from_raw(data: T) -> Self {
+        DecryptedDataPayload(data)
+    }
+}
+
 impl<T: AsRef<[u8]>> DataHeader for DecryptedDataPayload<T> {
     fn as_data_bytes(&self) -> &[u8] {
         self.0.as_ref()
@@ -733,7 +1579,7 @@ impl<T: AsRef<[u8]>> DataHeader for DecryptedDataPayload<T> {
 impl<T: AsRef<[u8]>> DecryptedDataPayload<T> {
     /// Returns FRMPayload that can represent either application payload or mac commands if fport
     /// is 0.
-    pub fn frm_payload(&self) -> Result<FRMPayload, &str> {
+    pub fn frm_payload(&self) -> Result<FRMPayload, Error> {
         let data = self.as_data_bytes();
         let len = data.len();
         let fhdr_length = self.fhdr_length();
@@ -770,9 +1616,9 @@ impl<T: AsRef<[u8]>> DecryptedDataPayload<T> {
 /// }
 /// ```
 #[cfg(feature = "default-crypto")]
-pub fn parse<'a, T: AsRef<[u8]> + AsMut<[u8]>>(
+pub fn parse<T: AsRef<[u8]> + AsMut<[u8]>>(
     data: T,
-) -> Result<PhyPayload<T, DefaultFactory>, &'a str> {
+) -> Result<PhyPayload<T, DefaultFactory>, Error> {
     parse_with_factory(data, DefaultFactory)
 }
 
@@ -784,19 +1630,31 @@ pub fn parse<'a, T: AsRef<[u8]> + AsMut<[u8]>>(
 ///
 /// * bytes - the data from which the PhyPayload is to be built.
 /// * factory - the factory that shall be used to create object for crypto functions.
-pub fn parse_with_factory<'a, T, F>(data: T, factory: F) -> Result<PhyPayload<T, F>, &'a str>
+pub fn parse_with_factory<T, F>(data: T, factory: F) -> Result<PhyPayload<T, F>, Error>
 where
     T: AsRef<[u8]> + AsMut<[u8]>,
     F: CryptoFactory,
 {
     let bytes = data.as_ref();
     let len = bytes.len();
+    // proprietary payloads have no defined format, so the only requirement for them is to have
+    // MHDR.
+    if len > 0 && MHDR(bytes[0]).mtype() == MType::Proprietary {
+        if MHDR(bytes[0]).major() != Major::LoRaWANR1 {
+            return Err(Error::UnsupportedMajor);
+        }
+        return Ok(PhyPayload::Proprietary(ProprietaryPayload::new(data)?));
+    }
     // the smallest payload is a data payload without fport and FRMPayload
     // which is 12 bytes long.
     if len < 12 {
-        return Err("insufficient number of bytes");
+        return Err(Error::TooShort);
+    }
+    let mhdr = MHDR(bytes[0]);
+    if mhdr.major() != Major::LoRaWANR1 {
+        return Err(Error::UnsupportedMajor);
     }
-    match MHDR(bytes[0]).mtype() {
+    match mhdr.mtype() {
         MType::JoinRequest => Ok(PhyPayload::JoinRequest(
             JoinRequestPayload::new_with_factory(data, factory)?,
         )),
@@ -809,7 +1667,10 @@ where
         | MType::ConfirmedDataDown => Ok(PhyPayload::Data(DataPayload::Encrypted(
             EncryptedDataPayload::new_with_factory(data, factory)?,
         ))),
-        _ => Err("unsupported message type"),
+        MType::RejoinRequest => Ok(PhyPayload::RejoinRequest(
+            RejoinRequestPayload::new_with_factory(data, factory)?,
+        )),
+        _ => Err(Error::InvalidMType),
     }
 }
 
@@ -831,7 +1692,7 @@ impl MHDR {
             3 => MType::UnconfirmedDataDown,
             4 => MType::ConfirmedDataUp,
             5 => MType::ConfirmedDataDown,
-            6 => MType::RFU,
+            6 => MType::RejoinRequest,
             _ => MType::Proprietary,
         }
     }
@@ -844,6 +1705,11 @@ impl MHDR {
             Major::RFU
         }
     }
+
+    /// Gives the binary representation of the MHDR.
+    pub fn raw_value(&self) -> u8 {
+        self.0
+    }
 }
 
 impl From<u8> for MHDR {
@@ -854,6 +1720,7 @@ impl From<u8> for MHDR {
 
 /// MType gives the possible message types of the PhyPayload.
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MType {
     JoinRequest,
     JoinAccept,
@@ -861,12 +1728,62 @@ pub enum MType {
     UnconfirmedDataDown,
     ConfirmedDataUp,
     ConfirmedDataDown,
-    RFU,
+    RejoinRequest,
     Proprietary,
 }
 
+/// JoinReqType gives the type of the request answered by a LoRaWAN 1.1 JoinAccept.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum JoinReqType {
+    JoinRequest,
+    RejoinRequestType0,
+    RejoinRequestType1,
+    RejoinRequestType2,
+}
+
+/// RejoinType gives the type of a LoRaWAN 1.1 RejoinRequest.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RejoinType {
+    Type0,
+    Type1,
+    Type2,
+}
+
+impl From<RejoinType> for u8 {
+    fn from(v: RejoinType) -> Self {
+        match v {
+            RejoinType::Type0 => 0x00,
+            RejoinType::Type1 => 0x01,
+            RejoinType::Type2 => 0x02,
+        }
+    }
+}
+
+impl From<RejoinType> for JoinReqType {
+    fn from(v: RejoinType) -> Self {
+        match v {
+            RejoinType::Type0 => JoinReqType::RejoinRequestType0,
+            RejoinType::Type1 => JoinReqType::RejoinRequestType1,
+            RejoinType::Type2 => JoinReqType::RejoinRequestType2,
+        }
+    }
+}
+
+impl From<JoinReqType> for u8 {
+    fn from(v: JoinReqType) -> Self {
+        match v {
+            JoinReqType::JoinRequest => 0xff,
+            JoinReqType::RejoinRequestType0 => 0x00,
+            JoinReqType::RejoinRequestType1 => 0x01,
+            JoinReqType::RejoinRequestType2 => 0x02,
+        }
+    }
+}
+
 /// Major gives the supported LoRaWAN payload formats.
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Major {
     LoRaWANR1,
     RFU,
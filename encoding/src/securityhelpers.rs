@@ -21,6 +21,76 @@ pub fn calculate_data_mic<M: keys::Mac>(data: &[u8], key: M, fcnt: u32) -> keys:
     calculate_mic_with_header(&header[..], data, key)
 }
 
+/// find_data_mic_fcnt gives the first of the frame counters for which the data packet has the
+/// given MIC, deriving the CMAC state of the key only once.
+pub fn find_data_mic_fcnt<M: keys::Mac + Clone>(
+    data: &[u8],
+    mic: &keys::MIC,
+    key: M,
+    fcnts: &[u32],
+) -> Option<u32> {
+    let mut header = [0; 16];
+    generate_helper_block(data, 0x49, 0, &mut header[..16]);
+    header[15] = data.len() as u8;
+
+    fcnts.iter().copied().find(|fcnt| {
+        header[10..14].copy_from_slice(&fcnt.to_le_bytes());
+        calculate_mic_with_header(&header[..], data, key.clone()) == *mic
+    })
+}
+
+/// calculate_uplink_data_mic_1_1 computes the MIC of a correct LoRaWAN 1.1 uplink data packet.
+///
+/// The MIC is made of the first two bytes of cmacS, computed with SNwkSIntKey over B1, followed
+/// by the first two bytes of cmacF, computed with FNwkSIntKey over B0.
+pub fn calculate_uplink_data_mic_1_1<M: keys::Mac>(
+    data: &[u8],
+    s_nwk_s_int_key: M,
+    f_nwk_s_int_key: M,
+    fcnt: u32,
+    conf_fcnt: u16,
+    tx_dr: u8,
+    tx_ch: u8,
+) -> keys::MIC {
+    let mut b0 = [0; 16];
+    generate_helper_block(data, 0x49, fcnt, &mut b0[..]);
+    b0[15] = data.len() as u8;
+
+    let mut b1 = b0;
+    set_conf_fcnt(data, conf_fcnt, &mut b1[..]);
+    b1[3] = tx_dr;
+    b1[4] = tx_ch;
+
+    let cmac_f = calculate_mic_with_header(&b0[..], data, f_nwk_s_int_key);
+    let cmac_s = calculate_mic_with_header(&b1[..], data, s_nwk_s_int_key);
+
+    keys::MIC([cmac_s.0[0], cmac_s.0[1], cmac_f.0[0], cmac_f.0[1]])
+}
+
+/// calculate_downlink_data_mic_1_1 computes the MIC of a correct LoRaWAN 1.1 downlink data
+/// packet.
+pub fn calculate_downlink_data_mic_1_1<M: keys::Mac>(
+    data: &[u8],
+    s_nwk_s_int_key: M,
+    fcnt: u32,
+    conf_fcnt: u16,
+) -> keys::MIC {
+    let mut b0 = [0; 16];
+    generate_helper_block(data, 0x49, fcnt, &mut b0[..]);
+    set_conf_fcnt(data, conf_fcnt, &mut b0[..]);
+    b0[15] = data.len() as u8;
+
+    calculate_mic_with_header(&b0[..], data, s_nwk_s_int_key)
+}
+
+// ConfFCnt is only used when the packet acknowledges a confirmed packet, otherwise it is 0.
+fn set_conf_fcnt(data: &[u8], conf_fcnt: u16, res: &mut [u8]) {
+    if data[5] & 0x20 != 0 {
+        res[1] = (conf_fcnt & 0xff) as u8;
+        res[2] = (conf_fcnt >> 8) as u8;
+    }
+}
+
 fn generate_helper_block(data: &[u8], first: u8, fcnt: u32, res: &mut [u8]) {
     res[0] = first;
     // res[1..5] are 0
@@ -52,6 +122,35 @@ pub fn calculate_mic<M: keys::Mac>(data: &[u8], key: M) -> keys::MIC {
     calculate_mic_with_header(&[], data, key)
 }
 
+/// calculate_join_accept_mic_1_1 computes the MIC of a LoRaWAN 1.1 JoinAccept with OptNeg set.
+pub fn calculate_join_accept_mic_1_1<M: keys::Mac>(
+    data: &[u8],
+    key: M,
+    join_req_type: u8,
+    join_eui: &[u8],
+    dev_nonce: &[u8],
+) -> keys::MIC {
+    let mut header = [0u8; 11];
+    header[0] = join_req_type;
+    header[1..9].copy_from_slice(join_eui);
+    header[9..11].copy_from_slice(dev_nonce);
+
+    calculate_mic_with_header(&header[..], data, key)
+}
+
+/// derive_key computes a key by encrypting the provided bytes padded with zeros.
+pub fn derive_key(aes_enc: &dyn keys::Encrypter, bytes: &[u8]) -> keys::AES128 {
+    let mut block = [0u8; 16];
+    block[..bytes.len()].copy_from_slice(bytes);
+
+    let mut input = GenericArray::clone_from_slice(&block);
+    aes_enc.encrypt_block(&mut input);
+
+    let mut output_key = [0u8; 16];
+    output_key.copy_from_slice(&input[0..16]);
+    keys::AES128(output_key)
+}
+
 /// encrypt_frm_data_payload encrypts bytes
 pub fn encrypt_frm_data_payload(
     phy_payload: &mut [u8],
@@ -76,3 +175,33 @@ pub fn encrypt_frm_data_payload(
         phy_payload[start + i] ^= tmp[j]
     }
 }
+
+/// encrypt_fopts encrypts the FOpts of a LoRaWAN 1.1 data packet with NwkSEncKey.
+///
+/// The FCnt counter used on downlink is given by the presence of a non-zero FPort, as described
+/// in the LoRaWAN 1.1 errata.
+pub fn encrypt_fopts(
+    phy_payload: &mut [u8],
+    f_port: Option<u8>,
+    fcnt: u32,
+    aes_enc: &dyn keys::Encrypter,
+) {
+    let f_opts_len = (phy_payload[5] & 0x0f) as usize;
+
+    let mut a = [0u8; 16];
+    generate_helper_block(phy_payload, 0x01, fcnt, &mut a[..]);
+    if phy_payload[0] & 0x20 != 0 {
+        // 0x01 for NFCntDown and 0x02 for AFCntDown
+        a[4] = match f_port {
+            Some(f_port) if f_port > 0 => 0x02,
+            _ => 0x01,
+        };
+    }
+    a[15] = 0x01;
+
+    let tmp = GenericArray::from_mut_slice(&mut a[..]);
+    aes_enc.encrypt_block(tmp);
+    for i in 0..f_opts_len {
+        phy_payload[8 + i] ^= tmp[i];
+    }
+}
@@ -0,0 +1,245 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+use lorawan_encoding::adr::*;
+use lorawan_encoding::error::Error;
+
+fn full_history(snr: f32, rssi: i16) -> UplinkHistory {
+    let mut res = UplinkHistory::new();
+    for fcnt in 100..120 {
+        res.push(UplinkMetrics { fcnt, snr, rssi });
+    }
+    res
+}
+
+fn settings(data_rate: u8, tx_power: u8, nb_trans: u8) -> LinkSettings {
+    LinkSettings {
+        data_rate,
+        tx_power,
+        nb_trans,
+    }
+}
+
+#[test]
+fn test_required_snr_and_sensitivity() {
+    assert_eq!(required_snr(7), -7.5);
+    assert_eq!(required_snr(12), -20.0);
+    assert_eq!(sensitivity(7), -123.0);
+    assert_eq!(sensitivity(11), -134.5);
+    assert_eq!(Region::EU868.spreading_factor(0), Some(12));
+    assert_eq!(Region::EU868.spreading_factor(6), None);
+    assert_eq!(Region::US915.spreading_factor(3), Some(7));
+    assert_eq!(Region::US915.spreading_factor(4), None);
+}
+
+#[test]
+fn test_history() {
+    let mut history = UplinkHistory::new();
+    assert!(history.is_empty());
+    assert_eq!(history.max_snr(), None);
+    assert_eq!(history.packet_loss(), 0.0);
+
+    history.push(UplinkMetrics {
+        fcnt: 1,
+        snr: -3.0,
+        rssi: -110,
+    });
+    // the same uplink received by a second gateway
+    history.push(UplinkMetrics {
+        fcnt: 1,
+        snr: 2.5,
+        rssi: -115,
+    });
+    assert_eq!(history.len(), 1);
+    assert_eq!(history.max_snr(), Some(2.5));
+    assert_eq!(history.max_rssi(), Some(-110));
+
+    for fcnt in 2..30 {
+        history.push(UplinkMetrics {
+            fcnt,
+            snr: -f32::from(fcnt as u8),
+            rssi: -100,
+        });
+    }
+    assert_eq!(history.len(), HISTORY_LEN);
+    assert_eq!(history.uplinks()[0].fcnt, 10);
+    assert_eq!(history.max_snr(), Some(-10.0));
+
+    // uplinks 31 to 34 were lost
+    history.push(UplinkMetrics {
+        fcnt: 35,
+        snr: 0.0,
+        rssi: -100,
+    });
+    assert_eq!(history.uplinks()[0].fcnt, 11);
+    assert_eq!(history.packet_loss(), 20.0);
+
+    // a new session
+    history.push(UplinkMetrics {
+        fcnt: 0,
+        snr: 0.0,
+        rssi: -100,
+    });
+    assert_eq!(history.len(), 1);
+}
+
+#[test]
+fn test_increase_data_rate_and_lower_tx_power() {
+    let engine = AdrEngine::new(Region::EU868);
+    // 10 dB + 20 dB for SF12 - 10 dB of installation margin gives 6 steps
+    let res = engine
+        .optimal_settings(&full_history(10.0, -80), &settings(0, 0, 1))
+        .unwrap();
+    assert_eq!(res, settings(5, 1, 1));
+
+    let res = engine
+        .optimal_settings(&full_history(10.0, -80), &settings(5, 6, 1))
+        .unwrap();
+    assert_eq!(res, settings(5, 7, 1));
+
+    let mut engine = AdrEngine::new(Region::US915);
+    engine.set_installation_margin(15.0);
+    // 10 dB + 15 dB for SF10 - 15 dB of installation margin gives 3 steps
+    let res = engine
+        .optimal_settings(&full_history(10.0, -80), &settings(0, 0, 1))
+        .unwrap();
+    assert_eq!(res, settings(3, 0, 1));
+}
+
+#[test]
+fn test_raise_tx_power() {
+    let engine = AdrEngine::new(Region::EU868);
+    // -10 dB + 7.5 dB for SF7 - 10 dB of installation margin gives -4 steps
+    let res = engine
+        .optimal_settings(&full_history(-10.0, -100), &settings(5, 5, 1))
+        .unwrap();
+    assert_eq!(res, settings(5, 1, 1));
+
+    // the data rate is never lowered
+    let res = engine
+        .optimal_settings(&full_history(-20.0, -100), &settings(5, 2, 1))
+        .unwrap();
+    assert_eq!(res, settings(5, 0, 1));
+}
+
+#[test]
+fn test_rssi_margin() {
+    let engine = AdrEngine::new(Region::EU868);
+    // the SNR margin is 30 dB but the RSSI is only 12 dB above the sensitivity of SF12
+    let res = engine
+        .optimal_settings(&full_history(10.0, -125), &settings(0, 0, 1))
+        .unwrap();
+    assert_eq!(res, settings(0, 0, 1));
+}
+
+#[test]
+fn test_min_history() {
+    let mut engine = AdrEngine::new(Region::EU868);
+    let mut history = UplinkHistory::new();
+    for fcnt in 0..5 {
+        history.push(UplinkMetrics {
+            fcnt,
+            snr: 10.0,
+            rssi: -80,
+        });
+    }
+    let current = settings(0, 0, 1);
+    assert_eq!(engine.optimal_settings(&history, &current), Ok(current));
+
+    engine.set_min_history(5).unwrap();
+    assert_eq!(
+        engine.optimal_settings(&history, &current),
+        Ok(settings(5, 1, 1))
+    );
+    assert!(engine.set_min_history(0).is_err());
+    assert!(engine.set_min_history(HISTORY_LEN + 1).is_err());
+}
+
+#[test]
+fn test_nb_trans() {
+    let engine = AdrEngine::new(Region::EU868);
+    let mut history = UplinkHistory::new();
+    // every fourth uplink is lost
+    for fcnt in (0..27).filter(|f| f & 3 != 3) {
+        history.push(UplinkMetrics {
+            fcnt,
+            snr: -12.0,
+            rssi: -100,
+        });
+    }
+    assert_eq!(history.len(), HISTORY_LEN);
+    assert!(history.packet_loss() > 10.0);
+    let res = engine
+        .optimal_settings(&history, &settings(2, 0, 1))
+        .unwrap();
+    assert_eq!(res, settings(2, 0, 2));
+    let res = engine
+        .optimal_settings(&history, &settings(2, 0, 2))
+        .unwrap();
+    assert_eq!(res, settings(2, 0, 3));
+
+    let res = engine
+        .optimal_settings(&full_history(-12.0, -100), &settings(2, 0, 3))
+        .unwrap();
+    assert_eq!(res, settings(2, 0, 2));
+}
+
+#[test]
+fn test_invalid_settings() {
+    let engine = AdrEngine::new(Region::US915);
+    let history = full_history(0.0, -100);
+    for current in &[settings(4, 0, 1), settings(0, 11, 1), settings(0, 0, 0)] {
+        assert_eq!(
+            engine.optimal_settings(&history, current),
+            Err(Error::ValueOutOfRange)
+        );
+        assert!(engine.link_adr_reqs(current).is_err());
+    }
+    assert!(AdrEngine::new(Region::EU868).set_sub_band(1).is_err());
+    assert!(AdrEngine::new(Region::US915).set_sub_band(9).is_err());
+}
+
+#[test]
+fn test_link_adr_reqs_eu868() {
+    let mut engine = AdrEngine::new(Region::EU868);
+    engine.set_channels([0xff, 0x01, 0, 0, 0, 0, 0, 0, 0]);
+    let cmds = engine.link_adr_reqs(&settings(3, 2, 2)).unwrap();
+    assert_eq!(cmds.len(), 1);
+    assert_eq!(cmds[0].build(), [0x03, 0x32, 0xff, 0x01, 0x02]);
+}
+
+#[test]
+fn test_link_adr_reqs_us915() {
+    let mut engine = AdrEngine::new(Region::US915);
+    let cmds = engine.link_adr_reqs(&settings(3, 5, 1)).unwrap();
+    assert_eq!(cmds.len(), 1);
+    assert_eq!(cmds[0].build(), [0x03, 0x35, 0xff, 0x00, 0x61]);
+
+    engine.set_sub_band(2).unwrap();
+    let cmds = engine.link_adr_reqs(&settings(2, 0, 1)).unwrap();
+    assert_eq!(cmds.len(), 2);
+    assert_eq!(cmds[0].build(), [0x03, 0x20, 0x02, 0x00, 0x71]);
+    assert_eq!(cmds[1].build(), [0x03, 0x20, 0x00, 0xff, 0x01]);
+
+    let mut channels = [0; 9];
+    channels[0] = 0x0f;
+    channels[5] = 0x80;
+    channels[6] = 0x01;
+    engine.set_channels(channels);
+    let cmds = engine.link_adr_reqs(&settings(1, 0, 3)).unwrap();
+    let cmds: Vec<&[u8]> = cmds.iter().map(|c| c.build()).collect();
+    assert_eq!(
+        cmds,
+        vec![
+            &[0x03, 0x10, 0x00, 0x00, 0x73][..],
+            &[0x03, 0x10, 0x0f, 0x00, 0x03][..],
+            &[0x03, 0x10, 0x00, 0x80, 0x23][..],
+            &[0x03, 0x10, 0x01, 0x00, 0x33][..],
+        ]
+    );
+}
@@ -8,6 +8,7 @@
 
 use lorawan_encoding::creator::*;
 use lorawan_encoding::default_crypto::DefaultFactory;
+use lorawan_encoding::error::Error;
 use lorawan_encoding::keys::*;
 use lorawan_encoding::maccommandcreator::*;
 use lorawan_encoding::maccommands::*;
@@ -42,6 +43,17 @@ fn phy_join_accept_payload_with_c_f_list() -> Vec<u8> {
     //867100000, 867300000, 867500000, 867700000, 867900000
 }
 
+fn phy_join_accept_payload_with_channel_mask_c_f_list() -> Vec<u8> {
+    let mut res = Vec::new();
+    res.extend_from_slice(&[
+        0x20, 0x92, 0xd1, 0xed, 0x25, 0xcc, 0xb1, 0x39, 0x10, 0x74, 0x97, 0x2a, 0xe1, 0x46, 0xb0,
+        0x08, 0x77, 0x2a, 0x6b, 0xe0, 0xa7, 0x15, 0x0a, 0x6f, 0x24, 0x74, 0xfb, 0x6b, 0xd3, 0x49,
+        0x4d, 0xb0, 0x2b,
+    ]);
+    res
+    // CFListType 1 enabling the channels 0 to 7
+}
+
 fn phy_dataup_payload() -> Vec<u8> {
     let mut res = Vec::new();
     res.extend_from_slice(&[
@@ -77,6 +89,51 @@ fn data_payload_with_f_opts() -> Vec<u8> {
     res
 }
 
+fn phy_dataup_payload_1_1() -> Vec<u8> {
+    let mut res = Vec::new();
+    res.extend_from_slice(&[
+        0x40, 0x04, 0x03, 0x02, 0x01, 0x81, 0x01, 0x00, 0xdb, 0x01, 0xe1, 0x0b, 0xc9, 0x98, 0xd7,
+        0xf6, 0xce, 0x36, 0x62,
+    ]);
+    res
+}
+
+fn phy_datadown_payload_1_1() -> Vec<u8> {
+    let mut res = Vec::new();
+    res.extend_from_slice(&[
+        0x60, 0x04, 0x03, 0x02, 0x01, 0x21, 0x02, 0x00, 0x40, 0x01, 0x43, 0x78, 0x0a, 0x59, 0xc8,
+        0x44,
+    ]);
+    res
+}
+
+fn phy_join_accept_payload_1_1() -> Vec<u8> {
+    let mut res = Vec::new();
+    res.extend_from_slice(&[
+        0x20, 0x8f, 0x07, 0xc1, 0x2e, 0xaf, 0x0e, 0x8a, 0x2d, 0xef, 0x8b, 0xf3, 0x87, 0x87, 0x52,
+        0x57, 0x2a,
+    ]);
+    res
+}
+
+fn phy_rejoin_request_type0_payload() -> Vec<u8> {
+    let mut res = Vec::new();
+    res.extend_from_slice(&[
+        0xc0, 0x00, 0x01, 0x02, 0x03, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01, 0x05, 0x00,
+        0x8b, 0x3c, 0x5c, 0x54,
+    ]);
+    res
+}
+
+fn phy_rejoin_request_type1_payload() -> Vec<u8> {
+    let mut res = Vec::new();
+    res.extend_from_slice(&[
+        0xc0, 0x01, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x08, 0x07, 0x06, 0x05, 0x04,
+        0x03, 0x02, 0x01, 0x34, 0x12, 0xa7, 0x41, 0x8e, 0x10,
+    ]);
+    res
+}
+
 fn app_key() -> [u8; 16] {
     [
         0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee,
@@ -93,7 +150,7 @@ fn test_mhdr_mtype() {
         (0x60, MType::UnconfirmedDataDown),
         (0x80, MType::ConfirmedDataUp),
         (0xa0, MType::ConfirmedDataDown),
-        (0xc0, MType::RFU),
+        (0xc0, MType::RejoinRequest),
         (0xe0, MType::Proprietary),
     ];
     for &(ref v, ref expected) in &examples {
@@ -117,7 +174,15 @@ fn test_parse_phy_payload_with_too_few_bytes_is_err() {
         0x80, 0x04, 0x03, 0x02, 0x01, 0x00, 0xff, 0x01, 0x02, 0x03, 0x04,
     ];
     let phy = parse(bytes);
-    assert!(phy.is_err());
+    assert_eq!(phy.err(), Some(Error::TooShort));
+}
+
+#[test]
+fn test_parse_phy_payload_with_unsupported_major_is_err() {
+    let mut bytes = phy_dataup_payload();
+    bytes[0] |= 0x01;
+    let phy = parse(bytes);
+    assert_eq!(phy.err(), Some(Error::UnsupportedMajor));
 }
 
 #[test]
@@ -153,6 +218,45 @@ fn test_parse_data_payload() {
     );
 }
 
+#[test]
+fn test_phy_payload_into_owned() {
+    let mut data = phy_dataup_payload();
+    let phy = parse(&mut data[..]).unwrap();
+    let owned: PhyPayload<heapless::Vec<u8, heapless::consts::U256>, _> = phy.into_owned().unwrap();
+    assert_eq!(owned.as_ref(), &phy_dataup_payload()[..]);
+    if let PhyPayload::Data(DataPayload::Encrypted(phy)) = owned {
+        let decrypted = phy.decrypt(None, Some(&AES128([1; 16])), 1).unwrap();
+        assert_eq!(decrypted.frm_payload(), Ok(FRMPayload::Data(b"hello")));
+    } else {
+        panic!("failed to convert data payload");
+    }
+}
+
+#[test]
+fn test_phy_payload_to_owned_vec() {
+    let data = phy_join_accept_payload();
+    let phy = parse(data.clone()).unwrap();
+    let owned: PhyPayload<Vec<u8>, _> = phy.to_owned().unwrap();
+    assert_eq!(owned, phy);
+}
+
+#[test]
+fn test_decrypted_data_payload_to_owned() {
+    let phy = EncryptedDataPayload::new(phy_dataup_payload()).unwrap();
+    let decrypted = DataPayload::Decrypted::<_, DefaultFactory>(
+        phy.decrypt(None, Some(&AES128([1; 16])), 1).unwrap(),
+    );
+    let owned: DataPayload<Vec<u8>, _> = decrypted.to_owned().unwrap();
+    assert_eq!(owned, decrypted);
+}
+
+#[test]
+fn test_phy_payload_to_owned_too_small_buffer_is_err() {
+    let phy = parse(phy_dataup_payload()).unwrap();
+    let owned: Result<PhyPayload<heapless::Vec<u8, heapless::consts::U16>, _>, _> = phy.to_owned();
+    assert_eq!(owned.err(), Some(Error::BufferTooShort));
+}
+
 #[test]
 fn test_new_join_accept_payload_too_short() {
     let mut bytes = phy_join_accept_payload();
@@ -208,6 +312,8 @@ fn test_dl_settings() {
     let dl_settings = DLSettings::new(0xcb);
     assert_eq!(dl_settings.rx1_dr_offset(), 4);
     assert_eq!(dl_settings.rx2_data_rate(), 11);
+    assert_eq!(dl_settings.opt_neg(), true);
+    assert_eq!(DLSettings::new(0x4b).opt_neg(), false);
 }
 
 #[test]
@@ -252,6 +358,163 @@ fn test_validate_data_mic_when_not_ok() {
     assert_eq!(phy.validate_mic(&key, 1), false);
 }
 
+#[test]
+fn test_validate_data_mic_of_repeated_frame() {
+    let phy = EncryptedDataPayload::new(phy_dataup_payload()).unwrap();
+    let nwk_skey = AES128([2; 16]);
+    let app_skey = AES128([1; 16]);
+
+    // an NbTrans repetition carries the FCnt of the last frame seen
+    assert!(phy.validate_mic(&nwk_skey, 1));
+    // the next expected FCnt makes the repetition look like a rollover
+    assert!(!phy.validate_mic(&nwk_skey, 2));
+    assert!(phy.decrypt_if_mic_ok(&nwk_skey, &app_skey, 2).is_err());
+}
+
+#[test]
+fn test_reconstruct_fcnt() {
+    let full = |fcnt, rolled_over| Ok(FullFCnt { fcnt, rolled_over });
+    assert_eq!(reconstruct_fcnt(0, 0, MAX_FCNT_GAP), full(0, false));
+    assert_eq!(reconstruct_fcnt(5, 5, MAX_FCNT_GAP), full(5, false));
+    assert_eq!(reconstruct_fcnt(5, 6, MAX_FCNT_GAP), full(6, false));
+    assert_eq!(
+        reconstruct_fcnt(0x3_1234, 0x2000, MAX_FCNT_GAP),
+        full(0x3_2000, false)
+    );
+    assert_eq!(
+        reconstruct_fcnt(0xffff, 0, MAX_FCNT_GAP),
+        full(0x1_0000, true)
+    );
+    assert_eq!(
+        reconstruct_fcnt(0x1_fff0, 0x0010, MAX_FCNT_GAP),
+        full(0x2_0010, true)
+    );
+    assert_eq!(
+        reconstruct_fcnt(0, 0x4000, MAX_FCNT_GAP),
+        full(0x4000, false)
+    );
+    assert_eq!(
+        reconstruct_fcnt(0, 0x4001, MAX_FCNT_GAP),
+        Err(Error::FCntGapTooLarge)
+    );
+    // an old frame looks like a rollover that is too far ahead
+    assert_eq!(
+        reconstruct_fcnt(0x1_0005, 0x0004, MAX_FCNT_GAP),
+        Err(Error::FCntGapTooLarge)
+    );
+    assert_eq!(
+        reconstruct_fcnt(0x1_0005, 0x0004, u32::MAX),
+        full(0x2_0004, true)
+    );
+    assert_eq!(
+        reconstruct_fcnt(0xffff_fff0, 0x0001, u32::MAX),
+        Err(Error::FCntGapTooLarge)
+    );
+}
+
+#[test]
+fn test_decrypt_data_payload_after_fcnt_rollover() {
+    let nwk_skey = AES128([2; 16]);
+    let app_skey = AES128([1; 16]);
+    let mut phy = DataPayloadCreator::new();
+    phy.set_dev_addr(&[1, 2, 3, 4])
+        .set_f_port(1)
+        .set_fcnt(0x1_0002);
+    let bytes = phy
+        .build(b"hello", &[], &nwk_skey, &app_skey)
+        .unwrap()
+        .to_vec();
+    let phy = EncryptedDataPayload::new(bytes).unwrap();
+
+    assert!(phy.validate_mic(&nwk_skey, 0xfffe));
+    assert!(phy.validate_mic(&nwk_skey, 0x1_0002));
+    assert!(!phy.validate_mic(&nwk_skey, 0x1_0003));
+    let decrypted = phy.decrypt_if_mic_ok(&nwk_skey, &app_skey, 0xfffe).unwrap();
+    assert_eq!(decrypted.frm_payload(), Ok(FRMPayload::Data(b"hello")));
+}
+
+#[test]
+fn test_find_session() {
+    let phy = EncryptedDataPayload::new(phy_dataup_payload()).unwrap();
+    let key = AES128([2; 16]);
+    let other_key = AES128([1; 16]);
+
+    let found = phy.find_session(vec![(&other_key, 0), (&key, 0), (&key, 0)]);
+    assert_eq!(found, Some(SessionMatch { index: 1, fcnt: 1 }));
+    assert_eq!(phy.find_session(vec![(&other_key, 0)]), None);
+    assert_eq!(phy.find_session(vec![]), None);
+}
+
+#[test]
+fn test_find_session_with_fcnt_rollover() {
+    let key = AES128([2; 16]);
+    let mut phy = DataPayloadCreator::new();
+    phy.set_dev_addr(&[1, 2, 3, 4]).set_fcnt(0x1_0001);
+    let bytes = phy.build(&[], &[], &key, &key).unwrap().to_vec();
+    let phy = EncryptedDataPayload::new(bytes).unwrap();
+
+    // the 16 bit counter rolled over since the last uplink
+    let found = phy.find_session(vec![(&key, 0xfff0)]);
+    assert_eq!(found.map(|m| m.fcnt), Some(0x1_0001));
+    let found = phy.find_session(vec![(&key, 0x1_0000)]);
+    assert_eq!(found.map(|m| m.fcnt), Some(0x1_0001));
+    // replays are found with the FCnt they were sent with
+    let found = phy.find_session(vec![(&key, 0x1_0005)]);
+    assert_eq!(found.map(|m| m.fcnt), Some(0x1_0001));
+    assert_eq!(phy.find_session(vec![(&key, 0x2_0000)]), None);
+}
+
+#[test]
+fn test_validate_uplink_data_mic_1_1() {
+    let phy = EncryptedDataPayload::new(phy_dataup_payload_1_1()).unwrap();
+    let f_nwk_s_int_key = AES128([1; 16]);
+    let s_nwk_s_int_key = AES128([2; 16]);
+
+    assert!(phy.validate_uplink_mic_1_1(&s_nwk_s_int_key, &f_nwk_s_int_key, 1, 0, 5, 2));
+    // the ACK bit is not set, so ConfFCnt is ignored
+    assert!(phy.validate_uplink_mic_1_1(&s_nwk_s_int_key, &f_nwk_s_int_key, 1, 7, 5, 2));
+    assert!(!phy.validate_uplink_mic_1_1(&s_nwk_s_int_key, &f_nwk_s_int_key, 1, 0, 5, 3));
+    assert!(!phy.validate_uplink_mic_1_1(&f_nwk_s_int_key, &s_nwk_s_int_key, 1, 0, 5, 2));
+}
+
+#[test]
+fn test_validate_downlink_data_mic_1_1() {
+    let phy = EncryptedDataPayload::new(phy_datadown_payload_1_1()).unwrap();
+    let s_nwk_s_int_key = AES128([2; 16]);
+
+    assert!(phy.validate_downlink_mic_1_1(&s_nwk_s_int_key, 0x10002, 0x1234));
+    assert!(!phy.validate_downlink_mic_1_1(&s_nwk_s_int_key, 0x10002, 0x1235));
+}
+
+#[test]
+fn test_decrypt_data_payload_1_1() {
+    let phy = EncryptedDataPayload::new(phy_dataup_payload_1_1()).unwrap();
+    let nwk_s_enc_key = AES128([3; 16]);
+    let app_skey = AES128([4; 16]);
+    let decrypted = phy
+        .decrypt_1_1(Some(&nwk_s_enc_key), Some(&app_skey), 1)
+        .unwrap();
+
+    let fhdr = decrypted.fhdr();
+    let mac_cmds: Vec<MacCommand> = fhdr.fopts().collect();
+    assert_eq!(
+        mac_cmds,
+        vec![MacCommand::LinkCheckReq(LinkCheckReqPayload())]
+    );
+    assert_eq!(decrypted.frm_payload(), Ok(FRMPayload::Data(&b"hello"[..])));
+}
+
+#[test]
+fn test_decrypt_data_payload_1_1_without_nwk_s_enc_key() {
+    let phy = EncryptedDataPayload::new(phy_dataup_payload_1_1()).unwrap();
+    let app_skey = AES128([4; 16]);
+
+    assert_eq!(
+        phy.decrypt_1_1(None, Some(&app_skey), 1).err(),
+        Some(Error::MissingKey)
+    );
+}
+
 #[test]
 fn test_new_data_payload_is_none_if_bytes_too_short() {
     let bytes = &[
@@ -430,7 +693,10 @@ fn test_data_payload_creator_when_payload_and_fport_0() {
     let nwk_skey = AES128([2; 16]);
     let app_skey = AES128([1; 16]);
     phy.set_f_port(0);
-    assert!(phy.build(b"hello", &[], &nwk_skey, &app_skey).is_err());
+    assert_eq!(
+        phy.build(b"hello", &[], &nwk_skey, &app_skey),
+        Err(Error::MacCommandsWithPayload)
+    );
 }
 
 #[test]
@@ -443,7 +709,10 @@ fn test_data_payload_creator_when_encrypt_but_not_fport_0() {
     let mut cmds: Vec<&dyn SerializableMacCommand> = Vec::new();
     cmds.extend_from_slice(&[&new_channel_req, &new_channel_req, &new_channel_req]);
     phy.set_f_port(1);
-    assert!(phy.build(b"", &cmds[..], &nwk_skey, &app_skey).is_err());
+    assert_eq!(
+        phy.build(b"", &cmds[..], &nwk_skey, &app_skey),
+        Err(Error::FOptsTooLarge)
+    );
 }
 
 #[test]
@@ -451,7 +720,36 @@ fn test_data_payload_creator_when_payload_no_fport() {
     let mut phy = DataPayloadCreator::new();
     let nwk_skey = AES128([2; 16]);
     let app_skey = AES128([1; 16]);
-    assert!(phy.build(b"hello", &[], &nwk_skey, &app_skey).is_err());
+    assert_eq!(
+        phy.build(b"hello", &[], &nwk_skey, &app_skey),
+        Err(Error::MissingFPort)
+    );
+}
+
+#[test]
+fn test_data_payload_creator_when_payload_too_large() {
+    let mut phy = DataPayloadCreator::new();
+    let nwk_skey = AES128([2; 16]);
+    let app_skey = AES128([1; 16]);
+    phy.set_f_port(1);
+    assert_eq!(
+        phy.build(&[0; 256], &[], &nwk_skey, &app_skey),
+        Err(Error::PayloadTooLarge)
+    );
+}
+
+#[test]
+fn test_data_payload_creator_with_max_length() {
+    let mut phy = DataPayloadCreator::new();
+    let nwk_skey = AES128([2; 16]);
+    let app_skey = AES128([1; 16]);
+    phy.set_f_port(1);
+    // MHDR + FHDR + FPort + FRMPayload + MIC fill the 256 bytes of the buffer
+    assert_eq!(
+        phy.build(&[0; 243], &[], &nwk_skey, &app_skey)
+            .map(|p| p.len()),
+        Ok(256)
+    );
 }
 
 #[test]
@@ -499,6 +797,67 @@ fn test_data_payload_creator_when_mac_commands_in_f_opts() {
         &data_payload_with_f_opts()[..]
     );
 }
+
+#[test]
+fn test_data_payload_uplink_creator_1_1() {
+    let mut phy = DataPayloadCreator::new();
+    let f_nwk_s_int_key = AES128([1; 16]);
+    let s_nwk_s_int_key = AES128([2; 16]);
+    let nwk_s_enc_key = AES128([3; 16]);
+    let app_skey = AES128([4; 16]);
+    let fctrl = FCtrl::new(0x80, true);
+    let mac_cmd = MacCommand::LinkCheckReq(LinkCheckReqPayload());
+    phy.set_confirmed(false)
+        .set_uplink(true)
+        .set_f_port(1)
+        .set_dev_addr(&[4, 3, 2, 1])
+        .set_fctrl(&fctrl) // ADR: true, all others: false
+        .set_fcnt(1)
+        .set_tx_dr_and_ch(5, 2);
+
+    assert_eq!(
+        phy.build_1_1(
+            b"hello",
+            &[&mac_cmd],
+            &f_nwk_s_int_key,
+            &s_nwk_s_int_key,
+            &nwk_s_enc_key,
+            &app_skey
+        )
+        .unwrap(),
+        &phy_dataup_payload_1_1()[..]
+    );
+}
+
+#[test]
+fn test_data_payload_downlink_creator_1_1() {
+    let mut phy = DataPayloadCreator::new();
+    let s_nwk_s_int_key = AES128([2; 16]);
+    let nwk_s_enc_key = AES128([3; 16]);
+    let app_skey = AES128([4; 16]);
+    let fctrl = FCtrl::new(0x20, false);
+    let mac_cmd = MacCommand::DevStatusReq(DevStatusReqPayload());
+    phy.set_confirmed(false)
+        .set_uplink(false)
+        .set_f_port(1)
+        .set_dev_addr(&[4, 3, 2, 1])
+        .set_fctrl(&fctrl) // ACK: true, all others: false
+        .set_fcnt(0x10002)
+        .set_conf_fcnt(0x1234);
+
+    assert_eq!(
+        phy.build_1_1(
+            b"hi",
+            &[&mac_cmd],
+            &s_nwk_s_int_key,
+            &s_nwk_s_int_key,
+            &nwk_s_enc_key,
+            &app_skey
+        )
+        .unwrap(),
+        &phy_datadown_payload_1_1()[..]
+    );
+}
 // TODO: test data payload create with piggy_backed mac commands
 
 #[test]
@@ -542,7 +901,6 @@ fn test_validate_join_request_mic_when_not_ok() {
 }
 
 #[test]
-#[cfg(feature = "default-crypto,with-downlink")]
 fn test_join_accept_creator() {
     let mut phy = JoinAcceptCreator::new();
     let key = AES128(app_key());
@@ -613,6 +971,346 @@ fn test_derive_appskey() {
     assert_eq!(appskey.0, expect);
 }
 
+fn join_eui() -> EUI64<[u8; 8]> {
+    EUI64::new([0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]).unwrap()
+}
+
+fn dev_eui() -> EUI64<[u8; 8]> {
+    EUI64::new([0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01]).unwrap()
+}
+
+fn join_accept_1_1_dev_nonce() -> DevNonce<[u8; 2]> {
+    DevNonce::new([0x0a, 0x0b]).unwrap()
+}
+
+fn new_decrypted_join_accept_1_1() -> DecryptedJoinAcceptPayload<Vec<u8>, DefaultFactory> {
+    EncryptedJoinAcceptPayload::new(phy_join_accept_payload_1_1())
+        .unwrap()
+        .decrypt(&AES128([1; 16]))
+}
+
+#[test]
+fn test_derive_jsintkey() {
+    let jsintkey = derive_jsintkey(&AES128([1; 16]), &dev_eui(), &DefaultFactory);
+    let expect = [
+        0xd9, 0x07, 0x5d, 0x62, 0x54, 0xf4, 0xe6, 0xbb, 0xf7, 0xb0, 0x41, 0x9f, 0x10, 0xa2, 0x44,
+        0x71,
+    ];
+    assert_eq!(jsintkey.0, expect);
+}
+
+#[test]
+fn test_derive_jsenckey() {
+    let jsenckey = derive_jsenckey(&AES128([1; 16]), &dev_eui(), &DefaultFactory);
+    let expect = [
+        0x57, 0x02, 0x68, 0xcc, 0xaf, 0xf4, 0x3b, 0x42, 0xd4, 0x85, 0x45, 0x2e, 0x20, 0x68, 0x5e,
+        0xcf,
+    ];
+    assert_eq!(jsenckey.0, expect);
+}
+
+#[test]
+fn test_validate_join_accept_mic_1_1() {
+    let join_accept = new_decrypted_join_accept_1_1();
+    let jsintkey = derive_jsintkey(&AES128([1; 16]), &dev_eui(), &DefaultFactory);
+
+    assert!(join_accept.dl_settings().opt_neg());
+    assert!(join_accept.validate_mic_1_1(
+        &jsintkey,
+        JoinReqType::JoinRequest,
+        &join_eui(),
+        &join_accept_1_1_dev_nonce()
+    ));
+    assert!(!join_accept.validate_mic_1_1(
+        &jsintkey,
+        JoinReqType::RejoinRequestType0,
+        &join_eui(),
+        &join_accept_1_1_dev_nonce()
+    ));
+    assert!(!join_accept.validate_mic(&AES128([1; 16])));
+}
+
+#[test]
+fn test_derive_session_keys_1_1() {
+    let join_accept = new_decrypted_join_accept_1_1();
+    let nwk_key = AES128([1; 16]);
+    let app_key = AES128([2; 16]);
+    let dev_nonce = join_accept_1_1_dev_nonce();
+
+    let expect = [
+        0x4e, 0xba, 0x6f, 0xfd, 0x14, 0x57, 0x77, 0x60, 0x70, 0xa5, 0x8d, 0x8f, 0x7c, 0x95, 0x71,
+        0x6f,
+    ];
+    assert_eq!(
+        join_accept
+            .derive_fnwksintkey(&join_eui(), &dev_nonce, &nwk_key)
+            .0,
+        expect
+    );
+    let expect = [
+        0xc5, 0x0c, 0xbe, 0x64, 0xda, 0x00, 0x78, 0x44, 0x75, 0x22, 0x66, 0xf9, 0xfc, 0x13, 0x2f,
+        0x7c,
+    ];
+    assert_eq!(
+        join_accept
+            .derive_snwksintkey(&join_eui(), &dev_nonce, &nwk_key)
+            .0,
+        expect
+    );
+    let expect = [
+        0x09, 0xb7, 0xb3, 0x3d, 0x5d, 0x77, 0x20, 0x07, 0x54, 0x62, 0x89, 0x86, 0x9f, 0x72, 0x47,
+        0x09,
+    ];
+    assert_eq!(
+        join_accept
+            .derive_nwksenckey(&join_eui(), &dev_nonce, &nwk_key)
+            .0,
+        expect
+    );
+    let expect = [
+        0x40, 0x9e, 0x84, 0x6f, 0xe5, 0xdf, 0xf1, 0xd3, 0x05, 0x76, 0x92, 0xee, 0xef, 0xc6, 0x4d,
+        0xf7,
+    ];
+    assert_eq!(
+        join_accept
+            .derive_appskey_1_1(&join_eui(), &dev_nonce, &app_key)
+            .0,
+        expect
+    );
+}
+
+#[test]
+fn test_join_accept_creator_1_1() {
+    let mut phy = JoinAcceptCreator::new();
+    let nwk_key = AES128([1; 16]);
+    let jsintkey = derive_jsintkey(&nwk_key, &dev_eui(), &DefaultFactory);
+    phy.set_app_nonce(&[0x01, 0x02, 0x03])
+        .set_net_id(&[0x01, 0x11, 0x22])
+        .set_dev_addr(&[0x80, 0x19, 0x03, 0x02])
+        .set_dl_settings(0x80)
+        .set_rx_delay(1);
+
+    assert_eq!(
+        phy.build_1_1(
+            &nwk_key,
+            &jsintkey,
+            JoinReqType::JoinRequest,
+            &join_eui(),
+            &join_accept_1_1_dev_nonce()
+        )
+        .unwrap(),
+        &phy_join_accept_payload_1_1()[..]
+    );
+}
+
+#[test]
+fn test_parse_rejoin_request_type0_payload() {
+    let phy = parse(phy_rejoin_request_type0_payload());
+    assert_eq!(
+        phy,
+        Ok(PhyPayload::RejoinRequest(RejoinRequestPayload::Type02(
+            RejoinRequestType02Payload::new_with_factory(
+                phy_rejoin_request_type0_payload(),
+                DefaultFactory
+            )
+            .unwrap()
+        )))
+    );
+}
+
+#[test]
+fn test_parse_rejoin_request_with_unknown_type_is_err() {
+    let mut data = phy_rejoin_request_type0_payload();
+    data[1] = 0x03;
+    assert_eq!(parse(data), Err(Error::InvalidRejoinType));
+}
+
+#[test]
+fn test_parse_rejoin_request_with_wrong_length_is_err() {
+    let mut data = phy_rejoin_request_type1_payload();
+    data[1] = 0x02;
+    assert_eq!(parse(data), Err(Error::InvalidLength));
+}
+
+#[test]
+fn test_rejoin_request_type0_fields() {
+    let data = phy_rejoin_request_type0_payload();
+    let rejoin_request = RejoinRequestPayload::new(&data[..]).unwrap();
+    assert_eq!(rejoin_request.rejoin_type(), RejoinType::Type0);
+    assert_eq!(rejoin_request.dev_eui().as_ref(), dev_eui().as_ref());
+    assert_eq!(rejoin_request.rj_count(), 5);
+    if let RejoinRequestPayload::Type02(payload) = rejoin_request {
+        assert_eq!(payload.net_id(), NwkAddr::new(&[0x01, 0x02, 0x03]).unwrap());
+        assert!(payload.validate_mic(&AES128([3; 16])));
+        assert!(!payload.validate_mic(&AES128([4; 16])));
+    } else {
+        panic!("failed to parse RejoinRequest of type 0");
+    }
+}
+
+#[test]
+fn test_rejoin_request_type1_fields() {
+    let data = phy_rejoin_request_type1_payload();
+    let rejoin_request = RejoinRequestPayload::new(&data[..]).unwrap();
+    assert_eq!(rejoin_request.rejoin_type(), RejoinType::Type1);
+    assert_eq!(rejoin_request.rj_count(), 0x1234);
+    if let RejoinRequestPayload::Type1(payload) = rejoin_request {
+        assert_eq!(payload.join_eui().as_ref(), join_eui().as_ref());
+        assert_eq!(payload.dev_eui().as_ref(), dev_eui().as_ref());
+        assert!(payload.validate_mic(&AES128([5; 16])));
+        assert!(!payload.validate_mic(&AES128([3; 16])));
+    } else {
+        panic!("failed to parse RejoinRequest of type 1");
+    }
+}
+
+#[test]
+fn test_rejoin_request_type0_creator() {
+    let mut phy = RejoinRequestCreator::new();
+    phy.set_rejoin_type(RejoinType::Type0)
+        .set_net_id(&[0x01, 0x02, 0x03])
+        .set_dev_eui(&[0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01])
+        .set_rj_count(5);
+
+    assert_eq!(
+        phy.build(&AES128([3; 16])).unwrap(),
+        &phy_rejoin_request_type0_payload()[..]
+    );
+}
+
+#[test]
+fn test_rejoin_request_type1_creator() {
+    let mut phy = RejoinRequestCreator::new();
+    phy.set_rejoin_type(RejoinType::Type1)
+        .set_join_eui(&[0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08])
+        .set_dev_eui(&[0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01])
+        .set_rj_count(0x1234);
+
+    assert_eq!(
+        phy.build(&AES128([5; 16])).unwrap(),
+        &phy_rejoin_request_type1_payload()[..]
+    );
+}
+
+#[test]
+fn test_parse_proprietary_payload() {
+    let data = vec![0xe0, 0x01, 0x02, 0x03];
+    let phy = parse(data.clone());
+    assert_eq!(
+        phy,
+        Ok(PhyPayload::Proprietary(
+            ProprietaryPayload::new(data).unwrap()
+        ))
+    );
+    if let Ok(PhyPayload::Proprietary(proprietary)) = phy {
+        assert_eq!(proprietary.mhdr().mtype(), MType::Proprietary);
+        assert_eq!(proprietary.payload(), &[0x01, 0x02, 0x03]);
+        assert_eq!(proprietary.as_ref(), &[0xe0, 0x01, 0x02, 0x03]);
+    }
+}
+
+#[test]
+fn test_parse_proprietary_payload_with_only_mhdr() {
+    let phy = parse(vec![0xe0]);
+    assert_eq!(
+        phy,
+        Ok(PhyPayload::Proprietary(
+            ProprietaryPayload::new(vec![0xe0]).unwrap()
+        ))
+    );
+}
+
+#[test]
+fn test_parse_proprietary_payload_with_unsupported_major_is_err() {
+    assert_eq!(parse(vec![0xe1, 0x01]), Err(Error::UnsupportedMajor));
+}
+
+#[test]
+fn test_proprietary_payload_with_wrong_mtype_is_err() {
+    assert_eq!(
+        ProprietaryPayload::new(phy_dataup_payload()),
+        Err(Error::InvalidMType)
+    );
+    assert_eq!(ProprietaryPayload::new(&[][..]), Err(Error::TooShort));
+}
+
+#[test]
+fn test_proprietary_payload_creator() {
+    let mut phy = ProprietaryPayloadCreator::new();
+    phy.set_payload(&[0x01, 0x02, 0x03]).unwrap();
+    assert_eq!(phy.build(), &[0xe0, 0x01, 0x02, 0x03]);
+}
+
+#[test]
+fn test_proprietary_payload_creator_with_too_long_payload_is_err() {
+    let mut phy = ProprietaryPayloadCreator::with_options([0; 4]).unwrap();
+    assert!(phy.set_payload(&[0x01, 0x02, 0x03, 0x04]).is_err());
+    phy.set_payload(&[0x01, 0x02, 0x03]).unwrap();
+    assert_eq!(phy.build(), &[0xe0, 0x01, 0x02, 0x03]);
+    assert!(ProprietaryPayloadCreator::with_options([0u8; 0]).is_err());
+}
+
+#[test]
+fn test_display_data_payload() {
+    let phy = parse(phy_dataup_payload()).unwrap();
+    assert_eq!(
+        phy.to_string(),
+        "UnconfirmedDataUp DevAddr=01020304 FCnt=1 ADR FPort=1 len=5"
+    );
+
+    let phy = parse(data_payload_with_f_opts()).unwrap();
+    assert_eq!(
+        phy.to_string(),
+        "UnconfirmedDataUp DevAddr=01020304 FCnt=0 FOpts=[LinkCheckReq, \
+         LinkADRAns(ChMaskACK=true, DataRateACK=false, PowerACK=true)]"
+    );
+}
+
+#[test]
+fn test_display_join_payloads() {
+    let phy = parse(phy_join_request_payload()).unwrap();
+    assert_eq!(
+        phy.to_string(),
+        "JoinRequest AppEUI=0102030401020304 DevEUI=0203040502030405 DevNonce=102d"
+    );
+
+    let phy = parse(phy_join_accept_payload()).unwrap();
+    assert_eq!(phy.to_string(), "JoinAccept (encrypted) len=16");
+
+    let decrypted =
+        DecryptedJoinAcceptPayload::new(phy_join_accept_payload_with_c_f_list(), &AES128([1; 16]))
+            .unwrap();
+    assert!(decrypted
+        .to_string()
+        .ends_with("CFList=[867100000, 867300000, 867500000, 867700000, 867900000]"));
+
+    let decrypted = DecryptedJoinAcceptPayload::new(
+        phy_join_accept_payload_with_channel_mask_c_f_list(),
+        &AES128([1; 16]),
+    )
+    .unwrap();
+    assert_eq!(decrypted.c_f_list_type(), Some(1));
+    assert!(decrypted
+        .to_string()
+        .ends_with(" CFList(type=1)=ff0000000000000000000000000000"));
+}
+
+#[test]
+fn test_display_other_payloads() {
+    let phy = parse(phy_rejoin_request_type0_payload()).unwrap();
+    assert_eq!(
+        phy.to_string(),
+        "RejoinRequest Type0 NetID=030201 DevEUI=0102030405060708 RJcount0=5"
+    );
+
+    let phy = parse(vec![0xe0, 0x01, 0x02]).unwrap();
+    assert_eq!(phy.to_string(), "Proprietary len=2");
+
+    assert_eq!(MHDR::new(0x41).to_string(), "UnconfirmedDataUp Major=RFU");
+    assert_eq!(FCtrl::new(0xb0, false).to_string(), "ADR ACK FPending");
+    assert_eq!(FCtrl::new(0xd0, true).to_string(), "ADR ADRACKReq ClassB");
+}
+
 #[test]
 #[cfg(feature = "with-to-string")]
 fn test_eui64_to_string() {
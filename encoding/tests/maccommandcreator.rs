@@ -6,6 +6,7 @@
 //
 // author: Ivaylo Petrov <ivajloip@gmail.com>
 
+use lorawan_encoding::error::Error;
 use lorawan_encoding::maccommandcreator::*;
 use lorawan_encoding::maccommands::*;
 
@@ -166,6 +167,241 @@ fn test_rx_timing_setup_ans_creator() {
     assert_eq!(res, [RXTimingSetupAnsPayload::cid()]);
 }
 
+#[test]
+fn test_tx_param_setup_req_creator() {
+    let mut creator = TxParamSetupReqCreator::new();
+    let res = creator
+        .set_downlink_dwell_time(true)
+        .set_uplink_dwell_time(false)
+        .set_max_eirp(0x05)
+        .unwrap()
+        .build();
+    assert_eq!(res, [TxParamSetupReqPayload::cid(), 0x25]);
+}
+
+#[test]
+fn test_tx_param_setup_req_creator_bad_max_eirp() {
+    let mut creator = TxParamSetupReqCreator::new();
+    assert!(creator.set_max_eirp(0x10).is_err());
+}
+
+#[test]
+fn test_tx_param_setup_ans_creator() {
+    let creator = TxParamSetupAnsCreator::new();
+    let res = creator.build();
+    assert_eq!(res, [TxParamSetupAnsPayload::cid()]);
+}
+
+#[test]
+fn test_dl_channel_req_creator() {
+    let mut creator = DlChannelReqCreator::new();
+    let res = creator
+        .set_channel_index(0x03)
+        .set_frequency(&[0x12, 0x34, 0x56])
+        .build();
+    assert_eq!(res, [DlChannelReqPayload::cid(), 0x03, 0x12, 0x34, 0x56]);
+}
+
+#[test]
+fn test_dl_channel_ans_creator() {
+    let mut creator = DlChannelAnsCreator::new();
+    let res = creator
+        .set_channel_frequency_ack(true)
+        .set_uplink_frequency_exists_ack(true)
+        .build();
+    assert_eq!(res, [DlChannelAnsPayload::cid(), 0x03]);
+}
+
+#[test]
+fn test_reset_ind_creator() {
+    let mut creator = ResetIndCreator::new();
+    let res = creator.set_minor(1).unwrap().build();
+    assert_eq!(res, [ResetIndPayload::cid(), 0x01]);
+}
+
+#[test]
+fn test_reset_conf_creator() {
+    let mut creator = ResetConfCreator::new();
+    let res = creator.set_minor(1).unwrap().build();
+    assert_eq!(res, [ResetConfPayload::cid(), 0x01]);
+}
+
+#[test]
+fn test_rekey_ind_creator() {
+    let mut creator = RekeyIndCreator::new();
+    let res = creator.set_minor(1).unwrap().build();
+    assert_eq!(res, [RekeyIndPayload::cid(), 0x01]);
+}
+
+#[test]
+fn test_rekey_conf_creator_bad_minor() {
+    let mut creator = RekeyConfCreator::new();
+    assert!(creator.set_minor(0x10).is_err());
+}
+
+#[test]
+fn test_adr_param_setup_req_creator() {
+    let mut creator = ADRParamSetupReqCreator::new();
+    let res = creator
+        .set_limit_exp(0x06)
+        .unwrap()
+        .set_delay_exp(0x05)
+        .unwrap()
+        .build();
+    assert_eq!(res, [ADRParamSetupReqPayload::cid(), 0x65]);
+}
+
+#[test]
+fn test_adr_param_setup_ans_creator() {
+    let creator = ADRParamSetupAnsCreator::new();
+    let res = creator.build();
+    assert_eq!(res, [ADRParamSetupAnsPayload::cid()]);
+}
+
+#[test]
+fn test_device_time_req_creator() {
+    let creator = DeviceTimeReqCreator::new();
+    let res = creator.build();
+    assert_eq!(res, [DeviceTimeReqPayload::cid()]);
+}
+
+#[test]
+fn test_device_time_ans_creator() {
+    let mut creator = DeviceTimeAnsCreator::new();
+    let res = creator
+        .set_gps_time(GPSTime::new(1_167_265_554, 0x80))
+        .build();
+    assert_eq!(
+        res,
+        [DeviceTimeAnsPayload::cid(), 0x12, 0x0f, 0x93, 0x45, 0x80]
+    );
+}
+
+#[test]
+fn test_force_rejoin_req_creator() {
+    let mut creator = ForceRejoinReqCreator::new();
+    let res = creator
+        .set_period(0x03)
+        .unwrap()
+        .set_max_retries(0x02)
+        .unwrap()
+        .set_rejoin_type(0x02)
+        .unwrap()
+        .set_data_rate(0x05)
+        .unwrap()
+        .build();
+    assert_eq!(res, [ForceRejoinReqPayload::cid(), 0x25, 0x1a]);
+}
+
+#[test]
+fn test_force_rejoin_req_creator_bad_period() {
+    let mut creator = ForceRejoinReqCreator::new();
+    assert!(creator.set_period(0x08).is_err());
+}
+
+#[test]
+fn test_force_rejoin_req_creator_bad_rejoin_type() {
+    let mut creator = ForceRejoinReqCreator::new();
+    assert!(creator.set_rejoin_type(0x00).is_ok());
+    for rejoin_type in [0x01, 0x03, 0x07, 0x08].iter() {
+        assert_eq!(
+            creator.set_rejoin_type(*rejoin_type).err(),
+            Some(Error::ValueOutOfRange)
+        );
+    }
+}
+
+#[test]
+fn test_rejoin_param_setup_req_creator() {
+    let mut creator = RejoinParamSetupReqCreator::new();
+    let res = creator
+        .set_max_time_n(0x0a)
+        .unwrap()
+        .set_max_count_n(0x04)
+        .unwrap()
+        .build();
+    assert_eq!(res, [RejoinParamSetupReqPayload::cid(), 0xa4]);
+}
+
+#[test]
+fn test_rejoin_param_setup_ans_creator() {
+    let mut creator = RejoinParamSetupAnsCreator::new();
+    let res = creator.set_time_ack(true).build();
+    assert_eq!(res, [RejoinParamSetupAnsPayload::cid(), 0x01]);
+}
+
+#[test]
+fn test_ping_slot_info_req_creator() {
+    let mut creator = PingSlotInfoReqCreator::new();
+    let res = creator.set_periodicity(0x05).unwrap().build();
+    assert_eq!(res, [PingSlotInfoReqPayload::cid(), 0x05]);
+}
+
+#[test]
+fn test_ping_slot_info_req_creator_bad_periodicity() {
+    let mut creator = PingSlotInfoReqCreator::new();
+    assert!(creator.set_periodicity(0x08).is_err());
+}
+
+#[test]
+fn test_ping_slot_info_ans_creator() {
+    let creator = PingSlotInfoAnsCreator::new();
+    let res = creator.build();
+    assert_eq!(res, [PingSlotInfoAnsPayload::cid()]);
+}
+
+#[test]
+fn test_ping_slot_channel_req_creator() {
+    let mut creator = PingSlotChannelReqCreator::new();
+    let res = creator
+        .set_frequency(&[0x12, 0x34, 0x56])
+        .set_data_rate(0x03)
+        .unwrap()
+        .build();
+    assert_eq!(
+        res,
+        [PingSlotChannelReqPayload::cid(), 0x12, 0x34, 0x56, 0x03]
+    );
+}
+
+#[test]
+fn test_ping_slot_channel_ans_creator() {
+    let mut creator = PingSlotChannelAnsCreator::new();
+    let res = creator
+        .set_channel_frequency_ack(true)
+        .set_data_rate_ack(true)
+        .build();
+    assert_eq!(res, [PingSlotChannelAnsPayload::cid(), 0x03]);
+}
+
+#[test]
+fn test_beacon_timing_req_creator() {
+    let creator = BeaconTimingReqCreator::new();
+    let res = creator.build();
+    assert_eq!(res, [BeaconTimingReqPayload::cid()]);
+}
+
+#[test]
+fn test_beacon_timing_ans_creator() {
+    let mut creator = BeaconTimingAnsCreator::new();
+    let res = creator.set_delay(0x1234).set_channel(0x02).build();
+    assert_eq!(res, [BeaconTimingAnsPayload::cid(), 0x34, 0x12, 0x02]);
+}
+
+#[test]
+fn test_beacon_freq_req_creator() {
+    let mut creator = BeaconFreqReqCreator::new();
+    let res = creator.set_frequency(&[0x12, 0x34, 0x56]).build();
+    assert_eq!(res, [BeaconFreqReqPayload::cid(), 0x12, 0x34, 0x56]);
+}
+
+#[test]
+fn test_beacon_freq_ans_creator() {
+    let mut creator = BeaconFreqAnsCreator::new();
+    let res = creator.set_beacon_frequency_ack(true).build();
+    assert_eq!(res, [BeaconFreqAnsPayload::cid(), 0x01]);
+}
+
 #[test]
 fn test_build_mac_commands() {
     let rx_timing_setup_req = RXTimingSetupReqPayload::new_as_mac_cmd(&[0x02]).unwrap().0;
@@ -181,3 +417,13 @@ fn test_build_mac_commands() {
     );
     assert_eq!(res, &vec![0x08, 0x02, 0x06, 0xfe, 0x3f][..]);
 }
+
+#[test]
+fn test_build_mac_commands_with_proprietary() {
+    let link_check_req = LinkCheckReqCreator::new();
+    let proprietary = ProprietaryMacCommand::new(0x80, &[0x01, 0x02]).unwrap();
+    let cmds: Vec<&dyn SerializableMacCommand> = vec![&link_check_req, &proprietary];
+    let mut res = vec![0; mac_commands_len(&cmds[..])];
+    assert_eq!(build_mac_commands(&cmds[..], &mut res[..]), Ok(4));
+    assert_eq!(res, &vec![0x02, 0x80, 0x01, 0x02][..]);
+}
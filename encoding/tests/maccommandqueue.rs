@@ -0,0 +1,121 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+use core::convert::TryFrom;
+
+use heapless::consts::{U16, U2};
+
+use lorawan_encoding::error::Error;
+use lorawan_encoding::maccommandcreator::*;
+use lorawan_encoding::maccommandqueue::*;
+use lorawan_encoding::maccommands::*;
+
+#[test]
+fn test_uplink_mac_command_from_parsed() {
+    let data = [0x02, 0x03, 0x07];
+    let cmds: Vec<UplinkMacCommand> = parse_mac_commands(&data[..], true)
+        .map(|c| UplinkMacCommand::try_from(c).unwrap())
+        .collect();
+    let mut link_adr_ans = LinkADRAnsCreator::new();
+    link_adr_ans
+        .set_channel_mask_ack(true)
+        .set_data_rate_ack(true)
+        .set_tx_power_ack(true);
+    assert_eq!(
+        cmds,
+        vec![
+            UplinkMacCommand::from(LinkCheckReqCreator::new()),
+            UplinkMacCommand::from(link_adr_ans),
+        ]
+    );
+}
+
+#[test]
+fn test_downlink_mac_command_from_over_long_payload() {
+    let payload = [0x50, 0x07, 0x00, 0x01, 0xaa, 0xbb];
+    let cmd = MacCommand::LinkADRReq(LinkADRReqPayload::new(&payload[..]).unwrap());
+    let owned = DownlinkMacCommand::try_from(cmd).unwrap();
+    assert_eq!(owned.payload_bytes(), &payload[..4]);
+}
+
+#[test]
+fn test_uplink_mac_command_from_downlink_cmd_is_err() {
+    let cmd = LinkCheckAnsPayload::new_as_mac_cmd(&[0x01, 0x02])
+        .unwrap()
+        .0;
+    assert_eq!(
+        UplinkMacCommand::try_from(cmd),
+        Err(Error::UnknownMacCommand(0x02))
+    );
+}
+
+#[test]
+fn test_downlink_mac_command_to_mac_command() {
+    let mut creator = LinkADRReqCreator::new();
+    creator
+        .set_data_rate(0x05)
+        .unwrap()
+        .set_tx_power(0x03)
+        .unwrap()
+        .set_channel_mask([0xc7, 0x0b])
+        .set_redundancy(0x37);
+    let cmd = DownlinkMacCommand::from(creator);
+    assert_eq!(cmd.cid(), 0x03);
+    assert_eq!(cmd.payload_bytes(), &[0x53, 0xc7, 0x0b, 0x37]);
+    if let MacCommand::LinkADRReq(payload) = cmd.to_mac_command() {
+        assert_eq!(payload.data_rate(), 5);
+        assert_eq!(payload.tx_power(), 3);
+    } else {
+        panic!("failed to convert to LinkADRReq");
+    }
+    assert_eq!(cmd.clone(), cmd);
+}
+
+#[test]
+fn test_mac_command_queue_push_when_full() {
+    let mut queue: MacCommandQueue<DownlinkMacCommand, U2> = MacCommandQueue::new();
+    assert!(queue.push(DevStatusReqCreator::new().into()).is_ok());
+    assert!(queue.push(DevStatusReqCreator::new().into()).is_ok());
+    assert_eq!(
+        queue.push(LinkCheckAnsCreator::new().into()),
+        Err(DownlinkMacCommand::from(LinkCheckAnsCreator::new()))
+    );
+    assert_eq!(queue.len(), 2);
+}
+
+#[test]
+fn test_mac_command_queue_fopts_commands() {
+    let mut queue: MacCommandQueue<DownlinkMacCommand, U16> = MacCommandQueue::new();
+    for _ in 0..3 {
+        queue.push(LinkADRReqCreator::new().into()).unwrap();
+    }
+    queue.push(DevStatusReqCreator::new().into()).unwrap();
+    assert_eq!(queue.bytes_len(), 16);
+    assert!(!queue.fits_in_fopts());
+    assert_eq!(queue.fopts_commands().len(), 3);
+    assert_eq!(queue.frm_payload_commands(16).len(), 4);
+    assert_eq!(mac_commands_len(&queue.fopts_commands()[..]), 15);
+}
+
+#[test]
+fn test_mac_command_queue_remove_first() {
+    let mut queue: MacCommandQueue<DownlinkMacCommand, U16> = MacCommandQueue::new();
+    queue.push(LinkADRReqCreator::new().into()).unwrap();
+    queue.push(DevStatusReqCreator::new().into()).unwrap();
+    queue.push(LinkCheckAnsCreator::new().into()).unwrap();
+    queue.remove_first(1);
+    assert_eq!(
+        queue.commands(),
+        &[
+            DevStatusReqCreator::new().into(),
+            LinkCheckAnsCreator::new().into()
+        ]
+    );
+    queue.remove_first(5);
+    assert!(queue.is_empty());
+}
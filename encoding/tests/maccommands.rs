@@ -228,6 +228,385 @@ fn test_rx_timing_setup_ans() {
     test_helper!(RXTimingSetupAns, RXTimingSetupAnsPayload);
 }
 
+#[test]
+fn test_tx_param_setup_req() {
+    let data = vec![0x25];
+    test_helper!(
+        data,
+        TxParamSetupReq,
+        TxParamSetupReqPayload,
+        1,
+        (downlink_dwell_time, true),
+        (uplink_dwell_time, false),
+        (max_eirp_raw, 5),
+        (max_eirp, 16),
+    );
+}
+
+#[test]
+fn test_tx_param_setup_req_max_eirp() {
+    let expected = [
+        8, 10, 12, 13, 14, 16, 18, 20, 21, 24, 26, 27, 29, 30, 33, 36,
+    ];
+    for (i, e) in expected.iter().enumerate() {
+        let data = [0x10 | i as u8];
+        let req = TxParamSetupReqPayload::new(&data[..]).unwrap();
+        assert!(req.uplink_dwell_time());
+        assert_eq!(req.max_eirp(), *e);
+    }
+}
+
+#[test]
+fn test_tx_param_setup_ans() {
+    test_helper!(TxParamSetupAns, TxParamSetupAnsPayload);
+}
+
+#[test]
+fn test_dl_channel_req() {
+    let data = vec![0x03, 0x01, 0x02, 0x04];
+    test_helper!(
+        data,
+        DlChannelReq,
+        DlChannelReqPayload,
+        4,
+        (channel_index, 3),
+        (frequency, Frequency::new_from_raw(&data[1..])),
+    );
+}
+
+#[test]
+fn test_dl_channel_ans() {
+    let examples = [
+        ([0x00], false, false, false),
+        ([0x01], true, false, false),
+        ([0x02], false, true, false),
+        ([0x03], true, true, true),
+    ];
+    for &(ref v, e_freq, e_uplink_freq, e_ack) in &examples {
+        let mc = DlChannelAnsPayload::new_as_mac_cmd(&v[..]);
+        if let (MacCommand::DlChannelAns(dca), size) = mc.unwrap() {
+            assert_eq!(size, 1);
+            assert_eq!(dca.channel_freq_ack(), e_freq);
+            assert_eq!(dca.uplink_freq_exists_ack(), e_uplink_freq);
+            assert_eq!(dca.ack(), e_ack);
+        } else {
+            panic!("failed to parse DlChannelAnsPayload");
+        }
+    }
+}
+
+#[test]
+fn test_reset_ind() {
+    let data = vec![0x01];
+    test_helper!(data, ResetInd, ResetIndPayload, 1, (minor, 1),);
+}
+
+#[test]
+fn test_reset_conf() {
+    let data = vec![0x01];
+    test_helper!(data, ResetConf, ResetConfPayload, 1, (minor, 1),);
+}
+
+#[test]
+fn test_rekey_ind() {
+    let data = vec![0x01];
+    test_helper!(data, RekeyInd, RekeyIndPayload, 1, (minor, 1),);
+}
+
+#[test]
+fn test_rekey_conf() {
+    let data = vec![0x01];
+    test_helper!(data, RekeyConf, RekeyConfPayload, 1, (minor, 1),);
+}
+
+#[test]
+fn test_adr_param_setup_req() {
+    let data = vec![0x65];
+    test_helper!(
+        data,
+        ADRParamSetupReq,
+        ADRParamSetupReqPayload,
+        1,
+        (limit_exp, 6),
+        (delay_exp, 5),
+        (adr_ack_limit, 64),
+        (adr_ack_delay, 32),
+    );
+}
+
+#[test]
+fn test_adr_param_setup_ans() {
+    test_helper!(ADRParamSetupAns, ADRParamSetupAnsPayload);
+}
+
+#[test]
+fn test_device_time_req() {
+    test_helper!(DeviceTimeReq, DeviceTimeReqPayload);
+}
+
+#[test]
+fn test_device_time_ans() {
+    let data = vec![0x12, 0x0f, 0x93, 0x45, 0x80];
+    test_helper!(
+        data,
+        DeviceTimeAns,
+        DeviceTimeAnsPayload,
+        5,
+        (seconds, 1_167_265_554),
+        (fraction, 0x80),
+        (gps_time, GPSTime::new(1_167_265_554, 0x80)),
+    );
+}
+
+#[test]
+fn test_gps_time_from_unix() {
+    let examples = [
+        (315_964_800, 0),
+        (362_793_599, 46_828_799),
+        (362_793_600, 46_828_801),
+        (1_483_228_799, 1_167_264_016),
+        (1_483_228_800, 1_167_264_018),
+        (1_600_000_000, 1_284_035_218),
+    ];
+    for &(unix, gps) in &examples {
+        let time = GPSTime::from_unix(unix, 0x10).unwrap();
+        assert_eq!(time.seconds(), gps);
+        assert_eq!(time.fraction(), 0x10);
+        assert_eq!(time.to_unix(), unix);
+    }
+}
+
+#[test]
+fn test_gps_time_leap_second_to_unix() {
+    assert_eq!(GPSTime::new(1_167_264_017, 0).to_unix(), 1_483_228_800);
+}
+
+#[test]
+fn test_gps_time_from_unix_before_gps_epoch() {
+    assert!(GPSTime::from_unix(315_964_799, 0).is_err());
+}
+
+#[test]
+fn test_force_rejoin_req() {
+    let data = vec![0x25, 0x1a];
+    test_helper!(
+        data,
+        ForceRejoinReq,
+        ForceRejoinReqPayload,
+        2,
+        (period, 3),
+        (max_retries, 2),
+        (rejoin_type, 2),
+        (data_rate, 5),
+    );
+}
+
+#[test]
+fn test_rejoin_param_setup_req() {
+    let data = vec![0xa4];
+    test_helper!(
+        data,
+        RejoinParamSetupReq,
+        RejoinParamSetupReqPayload,
+        1,
+        (max_time_n, 10),
+        (max_count_n, 4),
+    );
+}
+
+#[test]
+fn test_rejoin_param_setup_ans() {
+    let data = vec![0x01];
+    test_helper!(
+        data,
+        RejoinParamSetupAns,
+        RejoinParamSetupAnsPayload,
+        1,
+        (time_ack, true),
+    );
+}
+
+#[test]
+fn test_ping_slot_info_req() {
+    let data = vec![0x05];
+    test_helper!(
+        data,
+        PingSlotInfoReq,
+        PingSlotInfoReqPayload,
+        1,
+        (periodicity, 5),
+        (ping_slot_period, 32),
+        (ping_nb, 4),
+    );
+}
+
+#[test]
+fn test_ping_slot_info_ans() {
+    test_helper!(PingSlotInfoAns, PingSlotInfoAnsPayload);
+}
+
+#[test]
+fn test_ping_slot_channel_req() {
+    let data = vec![0x01, 0x02, 0x04, 0x03];
+    test_helper!(
+        data,
+        PingSlotChannelReq,
+        PingSlotChannelReqPayload,
+        4,
+        (frequency, Frequency::new_from_raw(&data[..3])),
+        (data_rate, 3),
+    );
+}
+
+#[test]
+fn test_ping_slot_channel_ans() {
+    let data = vec![0x02];
+    test_helper!(
+        data,
+        PingSlotChannelAns,
+        PingSlotChannelAnsPayload,
+        1,
+        (channel_freq_ack, false),
+        (data_rate_ack, true),
+        (ack, false),
+    );
+}
+
+#[test]
+fn test_beacon_timing_req() {
+    test_helper!(BeaconTimingReq, BeaconTimingReqPayload);
+}
+
+#[test]
+fn test_beacon_timing_ans() {
+    let data = vec![0x34, 0x12, 0x02];
+    test_helper!(
+        data,
+        BeaconTimingAns,
+        BeaconTimingAnsPayload,
+        3,
+        (delay, 0x1234),
+        (channel, 2),
+    );
+}
+
+#[test]
+fn test_beacon_freq_req() {
+    let data = vec![0x01, 0x02, 0x04];
+    test_helper!(
+        data,
+        BeaconFreqReq,
+        BeaconFreqReqPayload,
+        3,
+        (frequency, Frequency::new_from_raw(&data[..])),
+    );
+}
+
+#[test]
+fn test_beacon_freq_ans() {
+    let data = vec![0x01];
+    test_helper!(
+        data,
+        BeaconFreqAns,
+        BeaconFreqAnsPayload,
+        1,
+        (beacon_freq_ack, true),
+    );
+}
+
+#[test]
+fn test_parse_mac_commands_class_b() {
+    let data = vec![0x10, 0x05, 0x12, 0x11, 0x03, 0x13, 0x01];
+    let mut commands = parse_mac_commands(&data[..], true);
+    assert_eq!(
+        commands.next(),
+        Some(MacCommand::PingSlotInfoReq(
+            PingSlotInfoReqPayload::new(&[0x05]).unwrap()
+        ))
+    );
+    assert_eq!(
+        commands.next(),
+        Some(MacCommand::BeaconTimingReq(BeaconTimingReqPayload()))
+    );
+    assert_eq!(
+        commands.next(),
+        Some(MacCommand::PingSlotChannelAns(
+            PingSlotChannelAnsPayload::new(&[0x03]).unwrap()
+        ))
+    );
+    assert_eq!(
+        commands.next(),
+        Some(MacCommand::BeaconFreqAns(
+            BeaconFreqAnsPayload::new(&[0x01]).unwrap()
+        ))
+    );
+    assert_eq!(commands.next(), None);
+}
+
+#[test]
+fn test_parse_mac_commands_1_1_uplink() {
+    let data = vec![0x01, 0x01, 0x0b, 0x01, 0x0c, 0x0f, 0x00];
+    let mut commands = parse_mac_commands(&data[..], true);
+    assert_eq!(
+        commands.next(),
+        Some(MacCommand::ResetInd(ResetIndPayload::new(&[0x01]).unwrap()))
+    );
+    assert_eq!(
+        commands.next(),
+        Some(MacCommand::RekeyInd(RekeyIndPayload::new(&[0x01]).unwrap()))
+    );
+    assert_eq!(
+        commands.next(),
+        Some(MacCommand::ADRParamSetupAns(ADRParamSetupAnsPayload()))
+    );
+    assert_eq!(
+        commands.next(),
+        Some(MacCommand::RejoinParamSetupAns(
+            RejoinParamSetupAnsPayload::new(&[0x00]).unwrap()
+        ))
+    );
+    assert_eq!(commands.next(), None);
+}
+
+#[test]
+fn test_parse_mac_commands_1_1_downlink() {
+    let data = vec![
+        0x01, 0x01, 0x0b, 0x01, 0x0c, 0x65, 0x0e, 0x25, 0x1a, 0x0f, 0xa4,
+    ];
+    let mut commands = parse_mac_commands(&data[..], false);
+    assert_eq!(
+        commands.next(),
+        Some(MacCommand::ResetConf(
+            ResetConfPayload::new(&[0x01]).unwrap()
+        ))
+    );
+    assert_eq!(
+        commands.next(),
+        Some(MacCommand::RekeyConf(
+            RekeyConfPayload::new(&[0x01]).unwrap()
+        ))
+    );
+    assert_eq!(
+        commands.next(),
+        Some(MacCommand::ADRParamSetupReq(
+            ADRParamSetupReqPayload::new(&[0x65]).unwrap()
+        ))
+    );
+    assert_eq!(
+        commands.next(),
+        Some(MacCommand::ForceRejoinReq(
+            ForceRejoinReqPayload::new(&[0x25, 0x1a]).unwrap()
+        ))
+    );
+    assert_eq!(
+        commands.next(),
+        Some(MacCommand::RejoinParamSetupReq(
+            RejoinParamSetupReqPayload::new(&[0xa4]).unwrap()
+        ))
+    );
+    assert_eq!(commands.next(), None);
+}
+
 #[test]
 fn test_parse_mac_commands_empty_downlink() {
     let data = mac_cmds_payload();
@@ -272,6 +651,86 @@ fn test_parse_mac_commands_with_multiple_cmds_with_payloads() {
     );
 }
 
+#[test]
+fn test_parse_mac_commands_with_unknown_cmd() {
+    let data = vec![0x02, 0x70, 0x01, 0x02, 0x03, 0x00];
+    let mut commands = parse_mac_commands(&data[..], true);
+    assert_eq!(
+        commands.next(),
+        Some(MacCommand::LinkCheckReq(LinkCheckReqPayload()))
+    );
+    let unknown = commands.next().unwrap();
+    assert_eq!(
+        unknown,
+        MacCommand::Unknown {
+            cid: 0x70,
+            rest: &[0x01, 0x02, 0x03, 0x00]
+        }
+    );
+    assert_eq!(unknown.cid(), 0x70);
+    assert_eq!(unknown.len(), 4);
+    assert_eq!(commands.next(), None);
+}
+
+#[test]
+fn test_parse_mac_commands_with_truncated_cmd() {
+    let data = vec![0x03, 0x00];
+    let mut commands = parse_mac_commands(&data[..], false);
+    assert_eq!(
+        commands.next(),
+        Some(MacCommand::Unknown {
+            cid: 0x03,
+            rest: &[0x00]
+        })
+    );
+    assert_eq!(commands.next(), None);
+}
+
+#[test]
+fn test_parse_mac_commands_with_proprietary_cmds() {
+    let specs = [
+        ProprietaryMacCommandSpec::new(0x80, true, 2).unwrap(),
+        ProprietaryMacCommandSpec::new(0xff, true, 0).unwrap(),
+        ProprietaryMacCommandSpec::new(0x81, false, 1).unwrap(),
+    ];
+    let data = vec![0x80, 0x01, 0x02, 0xff, 0x02, 0x81, 0x05];
+    let mut commands = parse_mac_commands(&data[..], true).with_proprietary(&specs);
+    assert_eq!(
+        commands.next(),
+        Some(MacCommand::Proprietary(
+            ProprietaryMacCommand::new(0x80, &[0x01, 0x02]).unwrap()
+        ))
+    );
+    assert_eq!(
+        commands.next(),
+        Some(MacCommand::Proprietary(
+            ProprietaryMacCommand::new(0xff, &[]).unwrap()
+        ))
+    );
+    assert_eq!(
+        commands.next(),
+        Some(MacCommand::LinkCheckReq(LinkCheckReqPayload()))
+    );
+    assert_eq!(
+        commands.next(),
+        Some(MacCommand::Unknown {
+            cid: 0x81,
+            rest: &[0x05]
+        })
+    );
+    assert_eq!(commands.next(), None);
+}
+
+#[test]
+fn test_proprietary_mac_command_spec_with_standard_cid() {
+    assert!(ProprietaryMacCommandSpec::new(0x7f, true, 1).is_err());
+}
+
+#[test]
+fn test_proprietary_mac_command_with_standard_cid() {
+    assert!(ProprietaryMacCommand::new(0x7f, &[]).is_err());
+}
+
 fn mac_cmds_payload() -> Vec<u8> {
     vec![LinkCheckReqPayload::cid(), LinkADRAnsPayload::cid(), 0x00]
 }
@@ -365,3 +824,19 @@ fn test_mac_commands_len_with_mac_cmds() {
 
     assert_eq!(mac_commands_len(&cmds[..]), 5);
 }
+
+#[test]
+fn test_display_mac_commands() {
+    let data = [0x03, 0x53, 0xc7, 0x0b, 0x37, 0x08, 0x01, 0x80, 0x01, 0x02];
+    let cmds: Vec<String> = parse_mac_commands(&data[..], false)
+        .map(|c| c.to_string())
+        .collect();
+    assert_eq!(
+        cmds,
+        vec![
+            "LinkADRReq(DataRate=5, TXPower=3, ChMask=0bc7, ChMaskCntl=3, NbTrans=7)",
+            "RXTimingSetupReq(Delay=1)",
+            "Unknown(CID=0x80, Rest=0102)",
+        ]
+    );
+}
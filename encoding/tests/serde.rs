@@ -0,0 +1,279 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+#![cfg(feature = "serde")]
+
+use lorawan_encoding::default_crypto::DefaultFactory;
+use lorawan_encoding::keys::*;
+use lorawan_encoding::maccommands::*;
+use lorawan_encoding::parser::*;
+
+use serde_json::json;
+
+fn phy_join_request_payload() -> Vec<u8> {
+    vec![
+        0x00, 0x04, 0x03, 0x02, 0x01, 0x04, 0x03, 0x02, 0x01, 0x05, 0x04, 0x03, 0x02, 0x05, 0x04,
+        0x03, 0x02, 0x2d, 0x10, 0x6a, 0x99, 0x0e, 0x12,
+    ]
+}
+
+fn phy_join_accept_payload_with_c_f_list() -> Vec<u8> {
+    vec![
+        0x20, 0xe4, 0x56, 0x73, 0xb6, 0x3c, 0xb4, 0xb9, 0xce, 0xcb, 0x2a, 0xa8, 0x3f, 0x03, 0x33,
+        0xe6, 0x15, 0xd2, 0xac, 0x89, 0xee, 0xa1, 0x65, 0x98, 0x37, 0xc3, 0xaa, 0x6d, 0xf9, 0x68,
+        0x98, 0x89, 0xcf,
+    ]
+}
+
+fn phy_dataup_payload() -> Vec<u8> {
+    vec![
+        0x40, 0x04, 0x03, 0x02, 0x01, 0x80, 0x01, 0x00, 0x01, 0xa6, 0x94, 0x64, 0x26, 0x15, 0xd6,
+        0xc3, 0xb5, 0x82,
+    ]
+}
+
+fn data_payload_with_f_opts() -> Vec<u8> {
+    vec![
+        0x40, 0x04, 0x03, 0x02, 0x01, 0x03, 0x00, 0x00, 0x02, 0x03, 0x05, 0xd7, 0xfa, 0x0c, 0x6c,
+    ]
+}
+
+fn phy_rejoin_request_type0_payload() -> Vec<u8> {
+    vec![
+        0xc0, 0x00, 0x01, 0x02, 0x03, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01, 0x05, 0x00,
+        0x8b, 0x3c, 0x5c, 0x54,
+    ]
+}
+
+fn round_trip<T: AsRef<[u8]>>(
+    phy: &PhyPayload<T, DefaultFactory>,
+) -> PhyPayload<Vec<u8>, DefaultFactory> {
+    let json = serde_json::to_string(phy).unwrap();
+    serde_json::from_str(&json).unwrap()
+}
+
+#[test]
+fn test_serialize_data_payload() {
+    let phy = parse(phy_dataup_payload()).unwrap();
+    assert_eq!(
+        serde_json::to_value(&phy).unwrap(),
+        json!({
+            "data": {
+                "mhdr": {"mtype": "UnconfirmedDataUp", "major": "LoRaWANR1"},
+                "fhdr": {
+                    "dev_addr": "01020304",
+                    "fctrl": {
+                        "adr": true,
+                        "adr_ack_req": false,
+                        "ack": false,
+                        "f_pending": false,
+                        "class_b": false,
+                        "f_opts_len": 0
+                    },
+                    "fcnt": 1,
+                    "fopts": []
+                },
+                "f_port": 1,
+                "frm_payload": {"encrypted": "a694642615"},
+                "mic": "d6c3b582"
+            }
+        })
+    );
+}
+
+#[test]
+fn test_serialize_f_opts() {
+    let phy = parse(data_payload_with_f_opts()).unwrap();
+    let value = serde_json::to_value(&phy).unwrap();
+    assert_eq!(
+        value["data"]["fhdr"]["fopts"],
+        json!([
+            {"cid": 2, "name": "LinkCheckReq", "payload": ""},
+            {"cid": 3, "name": "LinkADRAns", "payload": "05"}
+        ])
+    );
+    assert_eq!(value["data"]["f_port"], json!(null));
+    assert_eq!(round_trip(&phy).as_ref(), &data_payload_with_f_opts()[..]);
+}
+
+#[test]
+fn test_serialize_identifiers_msb_first() {
+    let phy = parse(phy_join_request_payload()).unwrap();
+    let value = serde_json::to_value(&phy).unwrap();
+    let text = phy.to_string();
+    for field in ["app_eui", "dev_eui", "dev_nonce"].iter() {
+        let hex = value["join_request"][field].as_str().unwrap();
+        assert!(text.contains(hex), "{} not in {}", hex, text);
+    }
+}
+
+#[test]
+fn test_data_payload_round_trip() {
+    let phy = parse(phy_dataup_payload()).unwrap();
+    let restored = round_trip(&phy);
+    assert_eq!(restored, phy.to_owned().unwrap());
+}
+
+#[test]
+fn test_decrypted_data_payload_round_trip() {
+    let phy = EncryptedDataPayload::new(phy_dataup_payload()).unwrap();
+    let decrypted = PhyPayload::Data(DataPayload::Decrypted(
+        phy.decrypt(None, Some(&AES128([1; 16])), 1).unwrap(),
+    ));
+    let value = serde_json::to_value(&decrypted).unwrap();
+    assert_eq!(value["data"]["frm_payload"], json!({"data": "68656c6c6f"}));
+    let restored = round_trip(&decrypted);
+    assert_eq!(restored, decrypted.to_owned().unwrap());
+}
+
+#[test]
+fn test_decrypted_mac_commands_round_trip() {
+    let key = AES128([2; 16]);
+    let mut phy = lorawan_encoding::creator::DataPayloadCreator::new();
+    phy.set_f_port(0).set_fcnt(3);
+    let cmd = MacCommand::LinkCheckReq(LinkCheckReqPayload());
+    let bytes = phy.build(&[], &[&cmd], &key, &key).unwrap().to_vec();
+    let decrypted = PhyPayload::Data(DataPayload::Decrypted(
+        EncryptedDataPayload::new(bytes)
+            .unwrap()
+            .decrypt(Some(&key), None, 3)
+            .unwrap(),
+    ));
+    let value = serde_json::to_value(&decrypted).unwrap();
+    assert_eq!(
+        value["data"]["frm_payload"],
+        json!({"mac_commands": [{"cid": 2, "name": "LinkCheckReq", "payload": ""}]})
+    );
+    assert_eq!(round_trip(&decrypted), decrypted.to_owned().unwrap());
+}
+
+#[test]
+fn test_join_request_round_trip() {
+    let phy = parse(phy_join_request_payload()).unwrap();
+    let value = serde_json::to_value(&phy).unwrap();
+    assert_eq!(value["join_request"]["dev_eui"], json!("0203040502030405"));
+    assert_eq!(value["join_request"]["dev_nonce"], json!("102d"));
+    assert_eq!(round_trip(&phy), phy.to_owned().unwrap());
+}
+
+#[test]
+fn test_join_accept_round_trip() {
+    let phy = parse(phy_join_accept_payload_with_c_f_list()).unwrap();
+    assert_eq!(round_trip(&phy), phy.to_owned().unwrap());
+
+    let decrypted = PhyPayload::JoinAccept(JoinAcceptPayload::Decrypted(
+        DecryptedJoinAcceptPayload::new(phy_join_accept_payload_with_c_f_list(), &AES128([1; 16]))
+            .unwrap(),
+    ));
+    let value = serde_json::to_value(&decrypted).unwrap();
+    assert_eq!(
+        value["join_accept"]["decrypted"]["cf_list"],
+        json!({"frequencies": [867100000, 867300000, 867500000, 867700000, 867900000]})
+    );
+    assert_eq!(round_trip(&decrypted), decrypted.to_owned().unwrap());
+}
+
+fn decrypted_join_accept_json(cf_list: serde_json::Value) -> serde_json::Value {
+    json!({
+        "join_accept": {
+            "decrypted": {
+                "mhdr": {"mtype": "JoinAccept", "major": "LoRaWANR1"},
+                "app_nonce": "010203",
+                "net_id": "000013",
+                "dev_addr": "26011234",
+                "dl_settings": {"opt_neg": false, "rx1_dr_offset": 0, "rx2_data_rate": 8},
+                "rx_delay": 1,
+                "cf_list": cf_list,
+                "mic": "01020304"
+            }
+        }
+    })
+}
+
+#[test]
+fn test_join_accept_with_channel_mask_c_f_list_round_trip() {
+    let cf_list = "ff000000000000000000000000000001";
+    let value = decrypted_join_accept_json(json!({ "raw": &cf_list[2..] }));
+    assert!(serde_json::from_value::<PhyPayload<Vec<u8>, DefaultFactory>>(value).is_err());
+
+    let value = decrypted_join_accept_json(json!({ "raw": cf_list }));
+    let phy: PhyPayload<Vec<u8>, DefaultFactory> = serde_json::from_value(value.clone()).unwrap();
+    if let PhyPayload::JoinAccept(JoinAcceptPayload::Decrypted(ja)) = &phy {
+        assert_eq!(ja.c_f_list_type(), Some(1));
+    } else {
+        panic!("unexpected payload: {:?}", phy);
+    }
+    assert_eq!(
+        &phy.as_ref()[13..29],
+        &[0xff, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1][..]
+    );
+    assert_eq!(serde_json::to_value(&phy).unwrap(), value);
+}
+
+#[test]
+fn test_deserialize_invalid_c_f_list_frequency_is_err() {
+    for freq in [867100050u32, 1_677_721_600].iter() {
+        let value = decrypted_join_accept_json(json!({ "frequencies": [*freq] }));
+        assert!(serde_json::from_value::<PhyPayload<Vec<u8>, DefaultFactory>>(value).is_err());
+    }
+    let value = decrypted_join_accept_json(json!({ "frequencies": [867100000] }));
+    assert!(serde_json::from_value::<PhyPayload<Vec<u8>, DefaultFactory>>(value).is_ok());
+}
+
+#[test]
+fn test_rejoin_request_round_trip() {
+    let phy = parse(phy_rejoin_request_type0_payload()).unwrap();
+    let value = serde_json::to_value(&phy).unwrap();
+    assert_eq!(
+        value["rejoin_request"]["type02"]["rejoin_type"],
+        json!("Type0")
+    );
+    assert_eq!(round_trip(&phy), phy.to_owned().unwrap());
+}
+
+#[test]
+fn test_proprietary_round_trip() {
+    let phy = parse(vec![0xe0, 0x01, 0x02]).unwrap();
+    let value = serde_json::to_value(&phy).unwrap();
+    assert_eq!(value["proprietary"]["payload"], json!("0102"));
+    assert_eq!(round_trip(&phy), phy.to_owned().unwrap());
+}
+
+#[test]
+fn test_serialize_components() {
+    assert_eq!(
+        serde_json::to_value(DLSettings::new(0xcb)).unwrap(),
+        json!({"opt_neg": true, "rx1_dr_offset": 4, "rx2_data_rate": 11})
+    );
+    assert_eq!(
+        serde_json::to_value(Frequency::new_from_raw(&[0x18, 0x4f, 0x84])).unwrap(),
+        json!(867100000)
+    );
+    let dev_addr: DevAddr<[u8; 4]> = serde_json::from_str("\"01020304\"").unwrap();
+    assert_eq!(dev_addr, DevAddr::new([0x04, 0x03, 0x02, 0x01]).unwrap());
+    let dl_settings: DLSettings =
+        serde_json::from_value(json!({"opt_neg": true, "rx1_dr_offset": 4, "rx2_data_rate": 11}))
+            .unwrap();
+    assert_eq!(dl_settings, DLSettings::new(0xcb));
+}
+
+#[test]
+fn test_deserialize_invalid_hex_is_err() {
+    assert!(serde_json::from_str::<DevAddr<[u8; 4]>>("\"0102030\"").is_err());
+    assert!(serde_json::from_str::<DevAddr<[u8; 4]>>("\"010203\"").is_err());
+    assert!(serde_json::from_str::<EUI64<[u8; 8]>>("\"010203040506070x\"").is_err());
+}
+
+#[test]
+fn test_deserialize_too_long_f_opts_is_err() {
+    let phy = parse(phy_dataup_payload()).unwrap();
+    let mut value = serde_json::to_value(&phy).unwrap();
+    value["data"]["fhdr"]["fopts"] =
+        json!([{"cid": 2, "payload": "00000000000000000000000000000000"}]);
+    assert!(serde_json::from_value::<PhyPayload<Vec<u8>, DefaultFactory>>(value).is_err());
+}
@@ -0,0 +1,31 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Standard base64 encoding, as used for the frames in the JSON messages of the gateways.
+
+use lorawan_encoding::extra::base64;
+use serde::{de, Deserialize, Deserializer, Serializer};
+
+use super::error::Error;
+
+pub(crate) fn encode(data: &[u8]) -> String {
+    base64::encode(data)
+}
+
+pub(crate) fn decode(input: &str) -> Result<Vec<u8>, Error> {
+    base64::decode(input).map_err(|_| Error::InvalidBase64)
+}
+
+pub(crate) fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&encode(data))
+}
+
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    decode(&s).map_err(de::Error::custom)
+}
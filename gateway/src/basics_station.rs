@@ -0,0 +1,434 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Provides the messages of the LoRa Basics Station LNS protocol.
+//!
+//! Basics Station sends the uplink frames already dissected into their fields. The
+//! [JoinRequest](struct.JoinRequest.html), [UplinkDataFrame](struct.UplinkDataFrame.html) and
+//! [ProprietaryDataFrame](struct.ProprietaryDataFrame.html) messages can be built from the
+//! parsed frames and give back the exact bytes of the frame.
+//!
+//! # Examples
+//!
+//! ```
+//! use lorawan_encoding::parser::{DataHeader, DataPayload, PhyPayload};
+//! use lorawan_gateway::basics_station::Message;
+//!
+//! let msg = Message::parse(r#"{"msgtype":"updf","MHdr":64,"DevAddr":16909060,"FCtrl":128,
+//!     "FCnt":1,"FOpts":"","FPort":1,"FRMPayload":"A694642615","MIC":-2102017066,
+//!     "RefTime":0.0,"DR":5,"Freq":868100000,"upinfo":{"rctx":0,"xtime":68116944405337035,
+//!     "gpstime":0,"rssi":-53,"snr":8.25}}"#).unwrap();
+//! if let Message::UplinkDataFrame(updf) = &msg {
+//!     if let PhyPayload::Data(DataPayload::Encrypted(data)) = updf.phy_payload().unwrap() {
+//!         assert_eq!(data.fhdr().fcnt(), 1);
+//!     }
+//! }
+//! ```
+
+use lorawan_encoding::default_crypto::DefaultFactory;
+use lorawan_encoding::keys::CryptoFactory;
+use lorawan_encoding::parser::{
+    parse, AsPhyPayloadBytes, DataHeader, EncryptedDataPayload, JoinRequestPayload, MICAble,
+    PhyPayload, ProprietaryPayload,
+};
+use serde::{Deserialize, Serialize};
+
+use super::error::Error;
+use super::hex;
+
+/// Message is a single websocket message exchanged between a station and the LNS.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "msgtype")]
+pub enum Message {
+    /// Sent by the station after connecting.
+    #[serde(rename = "version")]
+    Version(Version),
+    /// Sent by the LNS to configure the station.
+    #[serde(rename = "router_config")]
+    RouterConfig(RouterConfig),
+    /// Sent by the station for a received JoinRequest.
+    #[serde(rename = "jreq")]
+    JoinRequest(JoinRequest),
+    /// Sent by the station for a received data frame.
+    #[serde(rename = "updf")]
+    UplinkDataFrame(UplinkDataFrame),
+    /// Sent by the station for a received proprietary frame.
+    #[serde(rename = "propdf")]
+    ProprietaryDataFrame(ProprietaryDataFrame),
+    /// Sent by the LNS with a frame to be transmitted.
+    #[serde(rename = "dnmsg")]
+    DownlinkMessage(DownlinkMessage),
+    /// Sent by the station when a downlink was transmitted.
+    #[serde(rename = "dntxed")]
+    DownlinkTransmitted(DownlinkTransmitted),
+    /// Sent by either side for synchronizing the time.
+    #[serde(rename = "timesync")]
+    TimeSync(TimeSync),
+}
+
+impl Message {
+    /// Parses a JSON message.
+    pub fn parse(json: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Provides the JSON representation of the message.
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Creates the uplink message the station sends for the given frame.
+    ///
+    /// Only JoinRequests, data uplinks and proprietary frames can be sent by a station.
+    pub fn from_uplink<T: AsRef<[u8]>, F: CryptoFactory>(
+        phy: &PhyPayload<T, F>,
+        radio: RadioMetadata,
+    ) -> Result<Self, Error> {
+        use lorawan_encoding::parser::DataPayload;
+
+        match phy {
+            PhyPayload::JoinRequest(jr) => Ok(Message::JoinRequest(JoinRequest::new(jr, radio))),
+            PhyPayload::Data(DataPayload::Encrypted(data)) if data.is_uplink() => {
+                Ok(Message::UplinkDataFrame(UplinkDataFrame::new(data, radio)))
+            }
+            PhyPayload::Proprietary(p) => Ok(Message::ProprietaryDataFrame(
+                ProprietaryDataFrame::new(p, radio),
+            )),
+            _ => Err(Error::UnsupportedFrame),
+        }
+    }
+}
+
+/// Version describes the station software.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Version {
+    pub station: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub firmware: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    pub model: String,
+    pub protocol: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub features: Option<String>,
+}
+
+/// RouterConfig configures the region and the radio of the station.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouterConfig {
+    /// The NetIDs of the data frames to be forwarded, all if empty.
+    #[serde(rename = "NetID", default)]
+    pub net_id: Vec<u32>,
+    /// Ranges of JoinEUIs of the JoinRequests to be forwarded, all if empty.
+    #[serde(rename = "JoinEui", default)]
+    pub join_eui: Vec<[u64; 2]>,
+    pub region: String,
+    pub hwspec: String,
+    /// The minimum and maximum frequency in Hz.
+    pub freq_range: [u32; 2],
+    /// The spreading factor, bandwidth in kHz and downlink only flag of each data rate.
+    #[serde(rename = "DRs")]
+    pub drs: Vec<[i32; 3]>,
+    /// The concentrator configuration, passed as is.
+    #[serde(default)]
+    pub sx1301_conf: Vec<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nocca: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nodc: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nodwell: Option<bool>,
+}
+
+/// UpInfo describes the reception of an uplink.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UpInfo {
+    /// The radio context, to be copied to the downlink.
+    pub rctx: i64,
+    /// The internal time of the reception, to be used for scheduling the downlink.
+    pub xtime: i64,
+    /// The GPS time of the reception in microseconds, 0 if not available.
+    #[serde(default)]
+    pub gpstime: i64,
+    /// The UTC time of the reception in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rxtime: Option<f64>,
+    /// RSSI in dBm.
+    pub rssi: f32,
+    /// SNR in dB.
+    pub snr: f32,
+}
+
+/// RadioMetadata contains the fields common to all uplink messages.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RadioMetadata {
+    /// The reference time used for measuring the round-trip time.
+    #[serde(rename = "RefTime", default)]
+    pub ref_time: f64,
+    /// The data rate of the region on which the frame was received.
+    #[serde(rename = "DR")]
+    pub dr: u8,
+    /// The frequency in Hz.
+    #[serde(rename = "Freq")]
+    pub freq: u32,
+    pub upinfo: UpInfo,
+}
+
+/// JoinRequest is a received JoinRequest frame.
+///
+/// EUIs are kept most significant byte first, as they are written in the JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JoinRequest {
+    #[serde(rename = "MHdr")]
+    pub mhdr: u8,
+    #[serde(rename = "JoinEui", with = "hex::eui")]
+    pub join_eui: [u8; 8],
+    #[serde(rename = "DevEui", with = "hex::eui")]
+    pub dev_eui: [u8; 8],
+    #[serde(rename = "DevNonce")]
+    pub dev_nonce: u16,
+    #[serde(rename = "MIC")]
+    pub mic: i32,
+    #[serde(flatten)]
+    pub radio: RadioMetadata,
+}
+
+fn reversed_eui(wire: &[u8]) -> [u8; 8] {
+    let mut res = [0; 8];
+    for (r, b) in res.iter_mut().zip(wire.iter().rev()) {
+        *r = *b;
+    }
+    res
+}
+
+impl JoinRequest {
+    /// Creates the message for the given JoinRequest.
+    pub fn new<T: AsRef<[u8]>, F: CryptoFactory>(
+        phy: &JoinRequestPayload<T, F>,
+        radio: RadioMetadata,
+    ) -> Self {
+        let dev_nonce = phy.dev_nonce();
+        let dev_nonce = dev_nonce.as_ref();
+        JoinRequest {
+            mhdr: phy.as_bytes()[0],
+            join_eui: reversed_eui(phy.app_eui().as_ref()),
+            dev_eui: reversed_eui(phy.dev_eui().as_ref()),
+            dev_nonce: u16::from_le_bytes([dev_nonce[0], dev_nonce[1]]),
+            mic: i32::from_le_bytes(phy.mic().0),
+            radio,
+        }
+    }
+
+    /// Provides the bytes of the frame.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut res = vec![self.mhdr];
+        res.extend(self.join_eui.iter().rev());
+        res.extend(self.dev_eui.iter().rev());
+        res.extend_from_slice(&self.dev_nonce.to_le_bytes());
+        res.extend_from_slice(&self.mic.to_le_bytes());
+        res
+    }
+
+    /// Parses the frame.
+    pub fn phy_payload(&self) -> Result<PhyPayload<Vec<u8>, DefaultFactory>, Error> {
+        Ok(parse(self.to_bytes())?)
+    }
+}
+
+/// UplinkDataFrame is a received data frame.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UplinkDataFrame {
+    #[serde(rename = "MHdr")]
+    pub mhdr: u8,
+    /// The DevAddr as a signed number.
+    #[serde(rename = "DevAddr")]
+    pub dev_addr: i32,
+    #[serde(rename = "FCtrl")]
+    pub fctrl: u8,
+    #[serde(rename = "FCnt")]
+    pub fcnt: u16,
+    #[serde(rename = "FOpts", with = "hex")]
+    pub fopts: Vec<u8>,
+    /// The FPort or -1 if there is none.
+    #[serde(rename = "FPort")]
+    pub f_port: i16,
+    /// The encrypted FRMPayload.
+    #[serde(rename = "FRMPayload", with = "hex")]
+    pub frm_payload: Vec<u8>,
+    #[serde(rename = "MIC")]
+    pub mic: i32,
+    #[serde(flatten)]
+    pub radio: RadioMetadata,
+}
+
+impl UplinkDataFrame {
+    /// Creates the message for the given data uplink.
+    pub fn new<T: AsRef<[u8]>, F>(phy: &EncryptedDataPayload<T, F>, radio: RadioMetadata) -> Self {
+        let bytes = phy.as_data_bytes();
+        let fhdr = phy.fhdr();
+        let fhdr_length = phy.fhdr_length();
+        let f_port = phy.f_port();
+        let frm_payload = match f_port {
+            Some(_) => bytes[1 + fhdr_length + 1..bytes.len() - 4].to_vec(),
+            None => Vec::new(),
+        };
+        let mut dev_addr = [0; 4];
+        dev_addr.copy_from_slice(fhdr.dev_addr().as_ref());
+        UplinkDataFrame {
+            mhdr: bytes[0],
+            dev_addr: i32::from_le_bytes(dev_addr),
+            fctrl: fhdr.fctrl().raw_value(),
+            fcnt: fhdr.fcnt(),
+            fopts: bytes[8..1 + fhdr_length].to_vec(),
+            f_port: f_port.map_or(-1, i16::from),
+            frm_payload,
+            mic: i32::from_le_bytes(phy.mic().0),
+            radio,
+        }
+    }
+
+    /// Provides the bytes of the frame.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut res = vec![self.mhdr];
+        res.extend_from_slice(&self.dev_addr.to_le_bytes());
+        res.push(self.fctrl);
+        res.extend_from_slice(&self.fcnt.to_le_bytes());
+        res.extend_from_slice(&self.fopts);
+        if self.f_port >= 0 {
+            res.push(self.f_port as u8);
+            res.extend_from_slice(&self.frm_payload);
+        }
+        res.extend_from_slice(&self.mic.to_le_bytes());
+        res
+    }
+
+    /// Parses the frame.
+    pub fn phy_payload(&self) -> Result<PhyPayload<Vec<u8>, DefaultFactory>, Error> {
+        Ok(parse(self.to_bytes())?)
+    }
+}
+
+/// ProprietaryDataFrame is a received proprietary frame.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProprietaryDataFrame {
+    /// The whole frame, including the MHDR.
+    #[serde(rename = "FRMPayload", with = "hex")]
+    pub frm_payload: Vec<u8>,
+    #[serde(flatten)]
+    pub radio: RadioMetadata,
+}
+
+impl ProprietaryDataFrame {
+    /// Creates the message for the given proprietary frame.
+    pub fn new<T: AsRef<[u8]>>(phy: &ProprietaryPayload<T>, radio: RadioMetadata) -> Self {
+        ProprietaryDataFrame {
+            frm_payload: phy.as_ref().to_vec(),
+            radio,
+        }
+    }
+
+    /// Parses the frame.
+    pub fn phy_payload(&self) -> Result<PhyPayload<Vec<u8>, DefaultFactory>, Error> {
+        Ok(parse(self.frm_payload.clone())?)
+    }
+}
+
+/// DownlinkMessage asks the station to transmit a frame.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DownlinkMessage {
+    #[serde(rename = "DevEui", with = "hex::eui")]
+    pub dev_eui: [u8; 8],
+    /// The device class: 0 for A, 1 for B and 2 for C.
+    #[serde(rename = "dC")]
+    pub device_class: u8,
+    /// The identifier of the downlink, reported back in the DownlinkTransmitted message.
+    pub diid: i64,
+    /// The PHYPayload to send.
+    #[serde(with = "hex")]
+    pub pdu: Vec<u8>,
+    #[serde(rename = "RxDelay")]
+    pub rx_delay: u8,
+    #[serde(rename = "RX1DR", default, skip_serializing_if = "Option::is_none")]
+    pub rx1_dr: Option<u8>,
+    #[serde(rename = "RX1Freq", default, skip_serializing_if = "Option::is_none")]
+    pub rx1_freq: Option<u32>,
+    #[serde(rename = "RX2DR", default, skip_serializing_if = "Option::is_none")]
+    pub rx2_dr: Option<u8>,
+    #[serde(rename = "RX2Freq", default, skip_serializing_if = "Option::is_none")]
+    pub rx2_freq: Option<u32>,
+    #[serde(default)]
+    pub priority: u8,
+    /// The internal time of the uplink the downlink answers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub xtime: Option<i64>,
+    /// The radio context of the uplink the downlink answers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rctx: Option<i64>,
+    #[serde(rename = "MuxTime", default, skip_serializing_if = "Option::is_none")]
+    pub mux_time: Option<f64>,
+}
+
+impl DownlinkMessage {
+    /// Creates a class A downlink answering the uplink received with the given UpInfo.
+    ///
+    /// The RX1 and RX2 parameters are not set.
+    pub fn new_class_a<T: AsRef<[u8]>>(
+        dev_eui: [u8; 8],
+        diid: i64,
+        pdu: T,
+        rx_delay: u8,
+        upinfo: &UpInfo,
+    ) -> Self {
+        DownlinkMessage {
+            dev_eui,
+            device_class: 0,
+            diid,
+            pdu: pdu.as_ref().to_vec(),
+            rx_delay,
+            rx1_dr: None,
+            rx1_freq: None,
+            rx2_dr: None,
+            rx2_freq: None,
+            priority: 0,
+            xtime: Some(upinfo.xtime),
+            rctx: Some(upinfo.rctx),
+            mux_time: None,
+        }
+    }
+
+    /// Parses the frame to be sent.
+    pub fn phy_payload(&self) -> Result<PhyPayload<Vec<u8>, DefaultFactory>, Error> {
+        Ok(parse(self.pdu.clone())?)
+    }
+}
+
+/// DownlinkTransmitted reports that a downlink was sent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DownlinkTransmitted {
+    pub diid: i64,
+    #[serde(rename = "DevEui", with = "hex::eui")]
+    pub dev_eui: [u8; 8],
+    pub rctx: i64,
+    pub xtime: i64,
+    /// The UTC time of the transmission in seconds.
+    pub txtime: f64,
+    /// The GPS time of the transmission in microseconds, 0 if not available.
+    #[serde(default)]
+    pub gpstime: i64,
+}
+
+/// TimeSync is either a request from the station or the answer of the LNS.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TimeSync {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub txtime: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpstime: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub xtime: Option<i64>,
+}
@@ -0,0 +1,82 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Provides the error type returned by the fallible operations of the crate.
+
+use std::fmt;
+
+/// Error represents the reasons for which decoding or encoding a gateway message can fail.
+#[derive(Debug)]
+pub enum Error {
+    /// The data is shorter than the smallest acceptable message.
+    TooShort,
+    /// The protocol version of the message is not supported.
+    UnsupportedVersion(u8),
+    /// The message identifier is not known.
+    UnknownIdentifier(u8),
+    /// A field that should be base64 encoded is not.
+    InvalidBase64,
+    /// A field that should be hex encoded is not.
+    InvalidHex,
+    /// The LoRaWAN frame type can not be carried by the message.
+    UnsupportedFrame,
+    /// The capture is not a pcap file with LoRaTap headers.
+    InvalidCapture,
+    /// Reading or writing the capture failed.
+    Io(std::io::Error),
+    /// The JSON part of the message is invalid.
+    Json(serde_json::Error),
+    /// The LoRaWAN frame carried by the message is invalid.
+    Encoding(lorawan_encoding::error::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::TooShort => f.write_str("insufficient number of bytes"),
+            Error::UnsupportedVersion(v) => write!(f, "unsupported protocol version {}", v),
+            Error::UnknownIdentifier(id) => write!(f, "unknown message identifier {}", id),
+            Error::InvalidBase64 => f.write_str("invalid base64 data"),
+            Error::InvalidHex => f.write_str("invalid hex data"),
+            Error::UnsupportedFrame => f.write_str("unsupported frame type for the message"),
+            Error::InvalidCapture => f.write_str("invalid LoRaTap capture"),
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+            Error::Json(e) => write!(f, "invalid JSON: {}", e),
+            Error::Encoding(e) => write!(f, "invalid LoRaWAN frame: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Json(e) => Some(e),
+            Error::Encoding(e) => Some(e),
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<lorawan_encoding::error::Error> for Error {
+    fn from(e: lorawan_encoding::error::Error) -> Self {
+        Error::Encoding(e)
+    }
+}
@@ -0,0 +1,58 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Hex encoding of byte fields and the dashed EUI representation used in the JSON messages.
+
+use lorawan_encoding::extra::hex;
+use serde::{de, Deserialize, Deserializer, Serializer};
+
+use super::error::Error;
+
+pub(crate) fn encode(data: &[u8]) -> String {
+    hex::encode_upper(data)
+}
+
+pub(crate) fn decode(input: &str) -> Result<Vec<u8>, Error> {
+    hex::decode(input).map_err(|_| Error::InvalidHex)
+}
+
+pub(crate) fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&encode(data))
+}
+
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    decode(&s).map_err(de::Error::custom)
+}
+
+/// Serde helpers for EUIs written most significant byte first and separated by dashes, e.g.
+/// "00-00-00-00-00-00-00-01". The plain 16 hex digits form is accepted as well.
+pub(crate) mod eui {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        eui: &[u8; 8],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let parts: Vec<String> = eui.iter().map(|b| encode(&[*b])).collect();
+        serializer.serialize_str(&parts.join("-"))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<[u8; 8], D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let bytes = decode(&s.replace('-', "")).map_err(de::Error::custom)?;
+        if bytes.len() != 8 {
+            return Err(de::Error::custom(format!("invalid EUI {}", s)));
+        }
+        let mut res = [0; 8];
+        res.copy_from_slice(&bytes);
+        Ok(res)
+    }
+}
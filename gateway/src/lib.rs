@@ -0,0 +1,17 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Provides codecs for the protocols spoken between LoRaWAN gateways and network servers,
+//! with conversions of the transported frames to and from `lorawan-encoding` types.
+
+mod base64;
+pub mod basics_station;
+pub mod error;
+mod hex;
+pub mod loratap;
+pub mod semtech_udp;
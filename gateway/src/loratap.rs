@@ -0,0 +1,427 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Provides reading and writing of pcap captures of LoRaWAN frames using the LoRaTap
+//! link-layer header, so that the traffic of a gateway can be inspected with Wireshark.
+//!
+//! Captures are written with version 1 of the LoRaTap header. Version 0 headers are accepted
+//! when reading, the fields they lack being left to their default values.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::time::Duration;
+//! use lorawan_gateway::loratap::{PcapReader, PcapWriter, RadioInfo};
+//!
+//! let radio = RadioInfo {
+//!     frequency: 868_100_000,
+//!     spreading_factor: 7,
+//!     rssi: -53,
+//!     snr: 8.25,
+//!     ..Default::default()
+//! };
+//! let phy = vec![0xe0, 0x01, 0x02, 0x03];
+//!
+//! let mut writer = PcapWriter::new(Vec::new()).unwrap();
+//! writer.write_frame(Duration::from_secs(1_588_000_000), &radio, &phy).unwrap();
+//! let capture = writer.into_inner();
+//!
+//! let mut reader = PcapReader::new(&capture[..]).unwrap();
+//! let record = reader.next().unwrap().unwrap();
+//! assert_eq!(record.radio, radio);
+//! assert_eq!(record.phy_payload().unwrap().as_ref(), &phy[..]);
+//! ```
+
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+use lorawan_encoding::default_crypto::DefaultFactory;
+use lorawan_encoding::parser::{parse, PhyPayload};
+
+use super::error::Error;
+use super::semtech_udp::{DataRate, RxPk, TxPk};
+
+/// The pcap link-layer header type assigned to LoRaTap.
+pub const LINKTYPE_LORATAP: u32 = 270;
+
+/// The sync word used by public LoRaWAN networks.
+pub const LORAWAN_PUBLIC_SYNC_WORD: u8 = 0x34;
+
+const PCAP_MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+const PCAP_MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+const PCAP_SNAPLEN: u32 = 65535;
+
+const LORATAP_V0_LENGTH: usize = 15;
+const LORATAP_V1_LENGTH: usize = 35;
+
+const RSSI_OFFSET: i16 = -139;
+
+const FLAG_IQ_INVERTED: u8 = 0x02;
+const FLAG_CRC_OK: u8 = 0x08;
+const FLAG_CRC_BAD: u8 = 0x10;
+const FLAG_NO_CRC: u8 = 0x20;
+
+/// RadioInfo holds the radio metadata stored in the LoRaTap header of a frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadioInfo {
+    /// Frequency in Hz.
+    pub frequency: u32,
+    /// Bandwidth in kHz, stored in steps of 125 kHz.
+    pub bandwidth: u16,
+    pub spreading_factor: u8,
+    /// Denominator of the LoRa coding rate, e.g. 5 for 4/5.
+    pub coding_rate: u8,
+    /// RSSI of the packet in dBm, between -139 and 116.
+    pub rssi: i16,
+    /// SNR in dB, stored in steps of 0.25 dB.
+    pub snr: f32,
+    pub sync_word: u8,
+    /// EUI of the gateway that received or transmitted the frame.
+    pub gateway_eui: [u8; 8],
+    /// Internal timestamp of the concentrator in microseconds.
+    pub timestamp: u32,
+    /// Whether the frame was sent with inverted IQ, as downlinks are.
+    pub iq_inverted: bool,
+    /// Whether the CRC of the frame was valid. `None` if it had no CRC.
+    pub crc_ok: Option<bool>,
+    /// Concentrator IF channel.
+    pub if_channel: u8,
+    /// Concentrator RF chain.
+    pub rf_chain: u8,
+}
+
+impl Default for RadioInfo {
+    fn default() -> Self {
+        RadioInfo {
+            frequency: 0,
+            bandwidth: 125,
+            spreading_factor: 0,
+            coding_rate: 5,
+            rssi: 0,
+            snr: 0.0,
+            sync_word: LORAWAN_PUBLIC_SYNC_WORD,
+            gateway_eui: [0; 8],
+            timestamp: 0,
+            iq_inverted: false,
+            crc_ok: None,
+            if_channel: 0,
+            rf_chain: 0,
+        }
+    }
+}
+
+impl RadioInfo {
+    /// Creates the metadata of a frame received by the given gateway.
+    pub fn from_rxpk(rxpk: &RxPk, gateway_eui: [u8; 8]) -> Self {
+        let mut res = RadioInfo {
+            frequency: mhz_to_hz(rxpk.freq),
+            coding_rate: coding_rate(rxpk.codr.as_deref()),
+            rssi: rxpk.rssi,
+            snr: rxpk.lsnr.unwrap_or_default(),
+            gateway_eui,
+            timestamp: rxpk.tmst,
+            crc_ok: match rxpk.stat {
+                1 => Some(true),
+                -1 => Some(false),
+                _ => None,
+            },
+            if_channel: rxpk.chan,
+            rf_chain: rxpk.rfch,
+            ..Default::default()
+        };
+        res.set_data_rate(&rxpk.datr);
+        res
+    }
+
+    /// Creates the metadata of a frame transmitted by the given gateway.
+    pub fn from_txpk(txpk: &TxPk, gateway_eui: [u8; 8]) -> Self {
+        let mut res = RadioInfo {
+            frequency: mhz_to_hz(txpk.freq),
+            coding_rate: coding_rate(txpk.codr.as_deref()),
+            gateway_eui,
+            timestamp: txpk.tmst.unwrap_or_default(),
+            iq_inverted: txpk.ipol,
+            crc_ok: match txpk.ncrc {
+                Some(true) => None,
+                _ => Some(true),
+            },
+            rf_chain: txpk.rfch,
+            ..Default::default()
+        };
+        res.set_data_rate(&txpk.datr);
+        res
+    }
+
+    fn set_data_rate(&mut self, datr: &DataRate) {
+        if let DataRate::LoRa {
+            spreading_factor,
+            bandwidth,
+        } = *datr
+        {
+            self.spreading_factor = spreading_factor;
+            self.bandwidth = bandwidth;
+        }
+    }
+
+    fn to_bytes(&self) -> [u8; LORATAP_V1_LENGTH] {
+        let mut res = [0; LORATAP_V1_LENGTH];
+        res[0] = 1;
+        res[2..4].copy_from_slice(&(LORATAP_V1_LENGTH as u16).to_be_bytes());
+        res[4..8].copy_from_slice(&self.frequency.to_be_bytes());
+        res[8] = (self.bandwidth / 125) as u8;
+        res[9] = self.spreading_factor;
+        res[10] = self.rssi.saturating_sub(RSSI_OFFSET).clamp(0, 255) as u8;
+        res[13] = (self.snr * 4.0).round() as i8 as u8;
+        res[14] = self.sync_word;
+        res[15..23].copy_from_slice(&self.gateway_eui);
+        res[23..27].copy_from_slice(&self.timestamp.to_be_bytes());
+        if self.iq_inverted {
+            res[27] |= FLAG_IQ_INVERTED;
+        }
+        match self.crc_ok {
+            Some(true) => res[27] |= FLAG_CRC_OK,
+            Some(false) => res[27] |= FLAG_CRC_BAD,
+            None => res[27] |= FLAG_NO_CRC,
+        }
+        res[28] = self.coding_rate;
+        res[31] = self.if_channel;
+        res[32] = self.rf_chain;
+        res
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let mut res = RadioInfo {
+            frequency: u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+            bandwidth: u16::from(bytes[8]) * 125,
+            spreading_factor: bytes[9],
+            rssi: i16::from(bytes[10]) + RSSI_OFFSET,
+            snr: f32::from(bytes[13] as i8) / 4.0,
+            sync_word: bytes[14],
+            ..Default::default()
+        };
+        if bytes.len() >= LORATAP_V1_LENGTH {
+            res.gateway_eui.copy_from_slice(&bytes[15..23]);
+            res.timestamp = u32::from_be_bytes([bytes[23], bytes[24], bytes[25], bytes[26]]);
+            res.iq_inverted = bytes[27] & FLAG_IQ_INVERTED != 0;
+            res.crc_ok = if bytes[27] & FLAG_CRC_OK != 0 {
+                Some(true)
+            } else if bytes[27] & FLAG_CRC_BAD != 0 {
+                Some(false)
+            } else {
+                // FLAG_NO_CRC, or an unknown CRC status
+                None
+            };
+            res.coding_rate = bytes[28];
+            res.if_channel = bytes[31];
+            res.rf_chain = bytes[32];
+        }
+        res
+    }
+}
+
+fn mhz_to_hz(freq: f64) -> u32 {
+    (freq * 1_000_000.0).round() as u32
+}
+
+fn coding_rate(codr: Option<&str>) -> u8 {
+    codr.and_then(|c| c.strip_prefix("4/"))
+        .and_then(|c| c.parse().ok())
+        .unwrap_or(5)
+}
+
+/// Record is a frame read from a capture together with its metadata.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    /// Time of the capture since the UNIX epoch.
+    pub time: Duration,
+    pub radio: RadioInfo,
+    /// The captured PHYPayload.
+    pub data: Vec<u8>,
+}
+
+impl Record {
+    /// Parses the captured data as a LoRaWAN PHYPayload.
+    pub fn phy_payload(&self) -> Result<PhyPayload<Vec<u8>, DefaultFactory>, Error> {
+        Ok(parse(self.data.clone())?)
+    }
+}
+
+/// PcapWriter writes frames to a pcap capture with LoRaTap headers.
+pub struct PcapWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> PcapWriter<W> {
+    /// Creates a writer, writing the pcap file header to `inner`.
+    pub fn new(mut inner: W) -> io::Result<Self> {
+        let mut header = [0; 24];
+        header[0..4].copy_from_slice(&PCAP_MAGIC_MICROS.to_le_bytes());
+        header[4..6].copy_from_slice(&2u16.to_le_bytes());
+        header[6..8].copy_from_slice(&4u16.to_le_bytes());
+        header[16..20].copy_from_slice(&PCAP_SNAPLEN.to_le_bytes());
+        header[20..24].copy_from_slice(&LINKTYPE_LORATAP.to_le_bytes());
+        inner.write_all(&header)?;
+        Ok(PcapWriter { inner })
+    }
+
+    /// Writes a frame captured at `time`, given since the UNIX epoch.
+    pub fn write_frame<T: AsRef<[u8]>>(
+        &mut self,
+        time: Duration,
+        radio: &RadioInfo,
+        phy: T,
+    ) -> io::Result<()> {
+        let phy = phy.as_ref();
+        let len = (LORATAP_V1_LENGTH + phy.len()) as u32;
+        let mut header = [0; 16];
+        header[0..4].copy_from_slice(&(time.as_secs() as u32).to_le_bytes());
+        header[4..8].copy_from_slice(&time.subsec_micros().to_le_bytes());
+        header[8..12].copy_from_slice(&len.to_le_bytes());
+        header[12..16].copy_from_slice(&len.to_le_bytes());
+        self.inner.write_all(&header)?;
+        self.inner.write_all(&radio.to_bytes())?;
+        self.inner.write_all(phy)
+    }
+
+    /// Writes a previously read record.
+    pub fn write_record(&mut self, record: &Record) -> io::Result<()> {
+        self.write_frame(record.time, &record.radio, &record.data)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// PcapReader reads the frames of a pcap capture with LoRaTap headers.
+///
+/// Both byte orders and both microsecond and nanosecond resolution captures are supported.
+pub struct PcapReader<R: Read> {
+    inner: R,
+    big_endian: bool,
+    nanos: bool,
+    snaplen: usize,
+}
+
+impl<R: Read> PcapReader<R> {
+    /// Creates a reader, reading and checking the pcap file header from `inner`.
+    pub fn new(mut inner: R) -> Result<Self, Error> {
+        let mut header = [0; 24];
+        if read_full(&mut inner, &mut header)? != header.len() {
+            return Err(Error::TooShort);
+        }
+        let magic = [header[0], header[1], header[2], header[3]];
+        let (big_endian, nanos) = match u32::from_le_bytes(magic) {
+            PCAP_MAGIC_MICROS => (false, false),
+            PCAP_MAGIC_NANOS => (false, true),
+            _ => match u32::from_be_bytes(magic) {
+                PCAP_MAGIC_MICROS => (true, false),
+                PCAP_MAGIC_NANOS => (true, true),
+                _ => return Err(Error::InvalidCapture),
+            },
+        };
+        let mut res = PcapReader {
+            inner,
+            big_endian,
+            nanos,
+            snaplen: 0,
+        };
+        if res.u32_at(&header, 20) & 0x0fff_ffff != LINKTYPE_LORATAP {
+            return Err(Error::InvalidCapture);
+        }
+        // the length of the records is checked against it before allocating their data
+        res.snaplen = res.u32_at(&header, 16).min(PCAP_SNAPLEN) as usize;
+        Ok(res)
+    }
+
+    /// Reads the next record, returning `None` at the end of the capture.
+    pub fn read_record(&mut self) -> Result<Option<Record>, Error> {
+        let mut header = [0; 16];
+        match read_full(&mut self.inner, &mut header)? {
+            0 => return Ok(None),
+            16 => (),
+            _ => return Err(Error::TooShort),
+        }
+        let secs = self.u32_at(&header, 0);
+        let fraction = self.u32_at(&header, 4);
+        let len = self.u32_at(&header, 8) as usize;
+        if len > self.snaplen {
+            return Err(Error::InvalidCapture);
+        }
+        let mut data = vec![0; len];
+        if read_full(&mut self.inner, &mut data)? != len {
+            return Err(Error::TooShort);
+        }
+
+        if len < LORATAP_V0_LENGTH || data[0] > 1 {
+            return Err(Error::InvalidCapture);
+        }
+        let header_len = u16::from_be_bytes([data[2], data[3]]) as usize;
+        let min_len = if data[0] == 0 {
+            LORATAP_V0_LENGTH
+        } else {
+            LORATAP_V1_LENGTH
+        };
+        if header_len < min_len || header_len > len {
+            return Err(Error::InvalidCapture);
+        }
+        let radio = RadioInfo::from_bytes(&data[..min_len]);
+        data.drain(..header_len);
+
+        let nanos = match (self.nanos, fraction) {
+            (true, 0..=999_999_999) => fraction,
+            (false, 0..=999_999) => fraction * 1000,
+            _ => return Err(Error::InvalidCapture),
+        };
+        Ok(Some(Record {
+            time: Duration::new(u64::from(secs), nanos),
+            radio,
+            data,
+        }))
+    }
+
+    fn u32_at(&self, bytes: &[u8], offset: usize) -> u32 {
+        let b = [
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ];
+        if self.big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        }
+    }
+}
+
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = Result<Record, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(read)
+}
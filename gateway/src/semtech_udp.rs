@@ -0,0 +1,506 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Provides the messages of the Semtech UDP packet forwarder protocol, also known as the Gateway
+//! Messaging Protocol (GWMP).
+//!
+//! # Examples
+//!
+//! ```
+//! use lorawan_gateway::semtech_udp::Packet;
+//!
+//! let data = b"\x02\x12\x34\x00\x01\x02\x03\x04\x05\x06\x07\x08{\"rxpk\":[{\"tmst\":3512348611,\
+//!     \"chan\":2,\"rfch\":0,\"freq\":866.349812,\"stat\":1,\"modu\":\"LORA\",\"datr\":\"SF7BW125\",\
+//!     \"codr\":\"4/6\",\"rssi\":-35,\"lsnr\":5.1,\"size\":18,\
+//!     \"data\":\"QAQDAgGAAQABppRkJhXWw7WC\"}]}";
+//! let packet = Packet::parse(&data[..]).unwrap();
+//! if let Packet::PushData { data, .. } = &packet {
+//!     let phy = data.rxpk[0].phy_payload().unwrap();
+//!     assert_eq!(phy.as_ref()[0], 0x40);
+//! }
+//! // the gateway expects its PUSH_DATA to be acknowledged
+//! let ack = packet.ack().unwrap();
+//! assert_eq!(ack.to_bytes().unwrap(), vec![0x02, 0x12, 0x34, 0x01]);
+//! ```
+
+use std::fmt;
+
+use lorawan_encoding::default_crypto::DefaultFactory;
+use lorawan_encoding::parser::{parse, PhyPayload};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::base64;
+use super::error::Error;
+
+/// The latest version of the protocol, to be used for new messages unless the peer is known to
+/// use an older one.
+pub const PROTOCOL_VERSION: u8 = 2;
+
+const PUSH_DATA: u8 = 0x00;
+const PUSH_ACK: u8 = 0x01;
+const PULL_DATA: u8 = 0x02;
+const PULL_RESP: u8 = 0x03;
+const PULL_ACK: u8 = 0x04;
+const TX_ACK: u8 = 0x05;
+
+/// Packet is a single UDP datagram exchanged between a packet forwarder and a network server.
+///
+/// Every packet carries the version of the protocol it is written with, so that the answers to a
+/// gateway can use the same version as the gateway itself.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Packet {
+    /// Sent by the gateway with the received frames and its statistics.
+    PushData {
+        version: u8,
+        token: u16,
+        gateway_eui: [u8; 8],
+        data: PushData,
+    },
+    /// Sent by the server to acknowledge a PushData.
+    PushAck { version: u8, token: u16 },
+    /// Sent by the gateway to open the route for downlinks.
+    PullData {
+        version: u8,
+        token: u16,
+        gateway_eui: [u8; 8],
+    },
+    /// Sent by the server with a frame to be transmitted.
+    PullResp {
+        version: u8,
+        token: u16,
+        data: PullResp,
+    },
+    /// Sent by the server to acknowledge a PullData.
+    PullAck { version: u8, token: u16 },
+    /// Sent by the gateway to report the outcome of a PullResp.
+    TxAck {
+        version: u8,
+        token: u16,
+        gateway_eui: [u8; 8],
+        data: Option<TxAck>,
+    },
+}
+
+fn gateway_eui(data: &[u8]) -> Result<[u8; 8], Error> {
+    if data.len() < 12 {
+        return Err(Error::TooShort);
+    }
+    let mut res = [0; 8];
+    res.copy_from_slice(&data[4..12]);
+    Ok(res)
+}
+
+impl Packet {
+    /// Parses a UDP datagram, accepting both version 1 and version 2 of the protocol.
+    pub fn parse(data: &[u8]) -> Result<Self, Error> {
+        if data.len() < 4 {
+            return Err(Error::TooShort);
+        }
+        let version = data[0];
+        if version != 1 && version != 2 {
+            return Err(Error::UnsupportedVersion(version));
+        }
+        let token = u16::from_be_bytes([data[1], data[2]]);
+        let res = match data[3] {
+            PUSH_DATA => Packet::PushData {
+                version,
+                token,
+                gateway_eui: gateway_eui(data)?,
+                data: serde_json::from_slice(&data[12..])?,
+            },
+            PUSH_ACK => Packet::PushAck { version, token },
+            PULL_DATA => Packet::PullData {
+                version,
+                token,
+                gateway_eui: gateway_eui(data)?,
+            },
+            PULL_RESP => Packet::PullResp {
+                version,
+                token,
+                data: serde_json::from_slice(&data[4..])?,
+            },
+            PULL_ACK => Packet::PullAck { version, token },
+            TX_ACK => {
+                let gateway_eui = gateway_eui(data)?;
+                // older packet forwarders send no JSON when there is no error
+                let json = &data[12..];
+                let data = if json.iter().all(|b| b.is_ascii_whitespace() || *b == 0) {
+                    None
+                } else {
+                    Some(serde_json::from_slice(json)?)
+                };
+                Packet::TxAck {
+                    version,
+                    token,
+                    gateway_eui,
+                    data,
+                }
+            }
+            id => return Err(Error::UnknownIdentifier(id)),
+        };
+        Ok(res)
+    }
+
+    /// Provides the binary representation of the packet.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let token = self.token().to_be_bytes();
+        let mut res = vec![self.version(), token[0], token[1], self.identifier()];
+        match self {
+            Packet::PushData {
+                gateway_eui, data, ..
+            } => {
+                res.extend_from_slice(gateway_eui);
+                serde_json::to_writer(&mut res, data)?;
+            }
+            Packet::PullData { gateway_eui, .. } => res.extend_from_slice(gateway_eui),
+            Packet::PullResp { data, .. } => serde_json::to_writer(&mut res, data)?,
+            Packet::TxAck {
+                gateway_eui, data, ..
+            } => {
+                res.extend_from_slice(gateway_eui);
+                if let Some(data) = data {
+                    serde_json::to_writer(&mut res, data)?;
+                }
+            }
+            Packet::PushAck { .. } | Packet::PullAck { .. } => (),
+        }
+        Ok(res)
+    }
+
+    /// Gives the random token used for matching the acknowledgements.
+    pub fn token(&self) -> u16 {
+        match self {
+            Packet::PushData { token, .. }
+            | Packet::PushAck { token, .. }
+            | Packet::PullData { token, .. }
+            | Packet::PullResp { token, .. }
+            | Packet::PullAck { token, .. }
+            | Packet::TxAck { token, .. } => *token,
+        }
+    }
+
+    /// Gives the version of the protocol the packet is written with.
+    pub fn version(&self) -> u8 {
+        match self {
+            Packet::PushData { version, .. }
+            | Packet::PushAck { version, .. }
+            | Packet::PullData { version, .. }
+            | Packet::PullResp { version, .. }
+            | Packet::PullAck { version, .. }
+            | Packet::TxAck { version, .. } => *version,
+        }
+    }
+
+    /// Gives the identifier of the packet type as used on the wire.
+    pub fn identifier(&self) -> u8 {
+        match self {
+            Packet::PushData { .. } => PUSH_DATA,
+            Packet::PushAck { .. } => PUSH_ACK,
+            Packet::PullData { .. } => PULL_DATA,
+            Packet::PullResp { .. } => PULL_RESP,
+            Packet::PullAck { .. } => PULL_ACK,
+            Packet::TxAck { .. } => TX_ACK,
+        }
+    }
+
+    /// Gives the acknowledgement the server should send for the packet, if any.
+    ///
+    /// The acknowledgement uses the same version of the protocol as the packet.
+    pub fn ack(&self) -> Option<Packet> {
+        match *self {
+            Packet::PushData { version, token, .. } => Some(Packet::PushAck { version, token }),
+            Packet::PullData { version, token, .. } => Some(Packet::PullAck { version, token }),
+            _ => None,
+        }
+    }
+}
+
+/// PushData is the JSON content of a PUSH_DATA packet.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PushData {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rxpk: Vec<RxPk>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stat: Option<Stat>,
+}
+
+/// PullResp is the JSON content of a PULL_RESP packet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PullResp {
+    pub txpk: TxPk,
+}
+
+/// TxAck is the JSON content of a TX_ACK packet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TxAck {
+    pub txpk_ack: TxPkAck,
+}
+
+impl TxAck {
+    /// Whether the gateway accepted the downlink for transmission.
+    pub fn is_ok(&self) -> bool {
+        !matches!(self.txpk_ack.error.as_deref(), Some(e) if e != "NONE")
+    }
+}
+
+/// TxPkAck reports the outcome of a downlink.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TxPkAck {
+    /// The error reported by the gateway, e.g. "TOO_LATE", or "NONE" on success.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// A warning reported by the gateway, e.g. "TX_POWER".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warn: Option<String>,
+    /// The value related to the warning, e.g. the actually used TX power.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<i32>,
+}
+
+/// Modulation of a radio packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Modulation {
+    #[serde(rename = "LORA")]
+    LoRa,
+    #[serde(rename = "FSK")]
+    Fsk,
+}
+
+/// DataRate of a radio packet.
+///
+/// It is written as e.g. "SF7BW125" for LoRa and as the bit rate for FSK.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataRate {
+    /// LoRa spreading factor and bandwidth in kHz.
+    LoRa {
+        spreading_factor: u8,
+        bandwidth: u16,
+    },
+    /// FSK bit rate in bits per second.
+    Fsk(u32),
+}
+
+impl fmt::Display for DataRate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DataRate::LoRa {
+                spreading_factor,
+                bandwidth,
+            } => write!(f, "SF{}BW{}", spreading_factor, bandwidth),
+            DataRate::Fsk(bit_rate) => write!(f, "{}", bit_rate),
+        }
+    }
+}
+
+impl std::str::FromStr for DataRate {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let rest = s.strip_prefix("SF").ok_or(())?;
+        let bw_pos = rest.find("BW").ok_or(())?;
+        Ok(DataRate::LoRa {
+            spreading_factor: rest[..bw_pos].parse().map_err(|_| ())?,
+            bandwidth: rest[bw_pos + 2..].parse().map_err(|_| ())?,
+        })
+    }
+}
+
+impl Serialize for DataRate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            DataRate::LoRa { .. } => serializer.collect_str(self),
+            DataRate::Fsk(bit_rate) => serializer.serialize_u32(*bit_rate),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DataRate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            LoRa(String),
+            Fsk(u32),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::LoRa(s) => s
+                .parse()
+                .map_err(|_| de::Error::custom(format!("invalid data rate {}", s))),
+            Raw::Fsk(bit_rate) => Ok(DataRate::Fsk(bit_rate)),
+        }
+    }
+}
+
+/// RxPk describes a frame received by the gateway.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RxPk {
+    /// UTC time of the reception in ISO 8601 format.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<String>,
+    /// GPS time of the reception in milliseconds since the GPS epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tmms: Option<u64>,
+    /// Internal timestamp of the reception in microseconds.
+    pub tmst: u32,
+    /// Frequency in MHz.
+    pub freq: f64,
+    /// Concentrator IF channel.
+    pub chan: u8,
+    /// Concentrator RF chain.
+    pub rfch: u8,
+    /// CRC status: 1 for OK, -1 for fail and 0 for no CRC.
+    pub stat: i8,
+    pub modu: Modulation,
+    pub datr: DataRate,
+    /// LoRa coding rate, e.g. "4/5".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub codr: Option<String>,
+    /// RSSI in dBm.
+    pub rssi: i16,
+    /// LoRa SNR in dB.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lsnr: Option<f32>,
+    /// Size of the frame in bytes.
+    pub size: u16,
+    /// The received PHYPayload.
+    #[serde(with = "base64")]
+    pub data: Vec<u8>,
+}
+
+impl RxPk {
+    /// Parses the received data as a LoRaWAN PHYPayload.
+    pub fn phy_payload(&self) -> Result<PhyPayload<Vec<u8>, DefaultFactory>, Error> {
+        Ok(parse(self.data.clone())?)
+    }
+
+    /// Sets the received data to the given PHYPayload, also updating the size.
+    pub fn set_phy_payload<T: AsRef<[u8]>>(&mut self, phy: T) -> &mut Self {
+        self.data = phy.as_ref().to_vec();
+        self.size = self.data.len() as u16;
+        self
+    }
+}
+
+/// TxPk describes a frame to be transmitted by the gateway.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TxPk {
+    /// Send the frame immediately, ignoring tmst and tmms.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub imme: bool,
+    /// Internal timestamp at which to send the frame, in microseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tmst: Option<u32>,
+    /// GPS time at which to send the frame, in milliseconds since the GPS epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tmms: Option<u64>,
+    /// Frequency in MHz.
+    pub freq: f64,
+    /// Concentrator RF chain.
+    pub rfch: u8,
+    /// TX power in dBm.
+    pub powe: u8,
+    pub modu: Modulation,
+    pub datr: DataRate,
+    /// LoRa coding rate, e.g. "4/5".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub codr: Option<String>,
+    /// FSK frequency deviation in Hz.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fdev: Option<u32>,
+    /// Invert the LoRa polarization, which is the case for downlinks to end devices.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub ipol: bool,
+    /// Preamble size.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prea: Option<u16>,
+    /// Size of the frame in bytes.
+    pub size: u16,
+    /// The PHYPayload to send.
+    #[serde(with = "base64")]
+    pub data: Vec<u8>,
+    /// Do not add a CRC to the frame.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ncrc: Option<bool>,
+}
+
+fn is_false(v: &bool) -> bool {
+    !*v
+}
+
+impl TxPk {
+    /// Creates a LoRa downlink to an end device with the given PHYPayload.
+    ///
+    /// # Argument
+    ///
+    /// * freq - the frequency in MHz.
+    /// * datr - the data rate.
+    /// * powe - the TX power in dBm.
+    /// * phy - the PHYPayload to send.
+    pub fn new_lora_downlink<T: AsRef<[u8]>>(freq: f64, datr: DataRate, powe: u8, phy: T) -> Self {
+        let mut res = TxPk {
+            imme: false,
+            tmst: None,
+            tmms: None,
+            freq,
+            rfch: 0,
+            powe,
+            modu: Modulation::LoRa,
+            datr,
+            codr: Some("4/5".into()),
+            fdev: None,
+            ipol: true,
+            prea: None,
+            size: 0,
+            data: Vec::new(),
+            ncrc: None,
+        };
+        res.set_phy_payload(phy);
+        res
+    }
+
+    /// Parses the data to be sent as a LoRaWAN PHYPayload.
+    pub fn phy_payload(&self) -> Result<PhyPayload<Vec<u8>, DefaultFactory>, Error> {
+        Ok(parse(self.data.clone())?)
+    }
+
+    /// Sets the data to be sent to the given PHYPayload, also updating the size.
+    pub fn set_phy_payload<T: AsRef<[u8]>>(&mut self, phy: T) -> &mut Self {
+        self.data = phy.as_ref().to_vec();
+        self.size = self.data.len() as u16;
+        self
+    }
+}
+
+/// Stat carries the statistics of the gateway.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stat {
+    /// UTC time of the statistics, e.g. "2014-01-12 08:59:28 GMT".
+    pub time: String,
+    /// GPS latitude in degrees.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lati: Option<f64>,
+    /// GPS longitude in degrees.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long: Option<f64>,
+    /// GPS altitude in meters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alti: Option<i32>,
+    /// Number of received radio packets.
+    pub rxnb: u32,
+    /// Number of received radio packets with a valid CRC.
+    pub rxok: u32,
+    /// Number of forwarded radio packets.
+    pub rwfw: u32,
+    /// Percentage of acknowledged upstream datagrams.
+    pub ackr: f32,
+    /// Number of downlinks received.
+    pub dwnb: u32,
+    /// Number of packets emitted.
+    pub txnb: u32,
+}
@@ -0,0 +1,226 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+use lorawan_encoding::creator::DataPayloadCreator;
+use lorawan_encoding::keys::AES128;
+use lorawan_encoding::maccommandcreator::LinkCheckReqCreator;
+use lorawan_encoding::maccommands::SerializableMacCommand;
+use lorawan_encoding::parser::{parse, PhyPayload};
+use lorawan_gateway::basics_station::*;
+use lorawan_gateway::error::Error;
+
+fn phy_join_request_payload() -> Vec<u8> {
+    vec![
+        0x00, 0x04, 0x03, 0x02, 0x01, 0x04, 0x03, 0x02, 0x01, 0x05, 0x04, 0x03, 0x02, 0x05, 0x04,
+        0x03, 0x02, 0x2d, 0x10, 0x6a, 0x99, 0x0e, 0x12,
+    ]
+}
+
+fn phy_dataup_payload() -> Vec<u8> {
+    vec![
+        0x40, 0x04, 0x03, 0x02, 0x01, 0x80, 0x01, 0x00, 0x01, 0xa6, 0x94, 0x64, 0x26, 0x15, 0xd6,
+        0xc3, 0xb5, 0x82,
+    ]
+}
+
+fn radio() -> RadioMetadata {
+    RadioMetadata {
+        ref_time: 0.0,
+        dr: 5,
+        freq: 868_100_000,
+        upinfo: UpInfo {
+            rctx: 1,
+            xtime: 68116944405337035,
+            gpstime: 0,
+            rxtime: Some(1_588_000_000.25),
+            rssi: -53.0,
+            snr: 8.25,
+        },
+    }
+}
+
+fn round_trip(phy: Vec<u8>) -> Message {
+    let parsed = parse(phy.clone()).unwrap();
+    let msg = Message::from_uplink(&parsed, radio()).unwrap();
+    let json = msg.to_json().unwrap();
+    let msg = Message::parse(&json).unwrap();
+    let bytes = match &msg {
+        Message::JoinRequest(jr) => jr.to_bytes(),
+        Message::UplinkDataFrame(updf) => updf.to_bytes(),
+        Message::ProprietaryDataFrame(propdf) => propdf.frm_payload.clone(),
+        m => panic!("unexpected message {:?}", m),
+    };
+    assert_eq!(bytes, phy);
+    msg
+}
+
+#[test]
+fn test_join_request() {
+    let msg = round_trip(phy_join_request_payload());
+    let json: serde_json::Value = serde_json::from_str(&msg.to_json().unwrap()).unwrap();
+    assert_eq!(json["msgtype"], "jreq");
+    assert_eq!(json["JoinEui"], "01-02-03-04-01-02-03-04");
+    assert_eq!(json["DevEui"], "02-03-04-05-02-03-04-05");
+    assert_eq!(json["DevNonce"], 0x102d);
+    assert_eq!(json["DR"], 5);
+    assert_eq!(json["upinfo"]["xtime"], 68116944405337035i64);
+    if let Message::JoinRequest(jr) = msg {
+        assert!(matches!(jr.phy_payload(), Ok(PhyPayload::JoinRequest(_))));
+    }
+}
+
+#[test]
+fn test_uplink_data_frame() {
+    let msg = round_trip(phy_dataup_payload());
+    if let Message::UplinkDataFrame(updf) = &msg {
+        assert_eq!(updf.dev_addr, 0x01020304);
+        assert_eq!(updf.fctrl, 0x80);
+        assert_eq!(updf.fcnt, 1);
+        assert_eq!(updf.f_port, 1);
+        assert_eq!(updf.frm_payload, vec![0xa6, 0x94, 0x64, 0x26, 0x15]);
+    } else {
+        panic!("unexpected message {:?}", msg);
+    }
+    let json: serde_json::Value = serde_json::from_str(&msg.to_json().unwrap()).unwrap();
+    assert_eq!(json["msgtype"], "updf");
+    assert_eq!(json["FRMPayload"], "A694642615");
+    assert_eq!(json["FOpts"], "");
+}
+
+#[test]
+fn test_uplink_data_frame_with_fopts_and_no_port() {
+    let mut phy = DataPayloadCreator::new();
+    phy.set_dev_addr(&[0x04, 0x03, 0x02, 0xfa]).set_fcnt(0x1234);
+    let cmd = LinkCheckReqCreator::new();
+    let cmds: Vec<&dyn SerializableMacCommand> = vec![&cmd];
+    let bytes = phy
+        .build(&[], &cmds, &AES128([2; 16]), &AES128([1; 16]))
+        .unwrap()
+        .to_vec();
+    let msg = round_trip(bytes);
+    if let Message::UplinkDataFrame(updf) = &msg {
+        // DevAddr fa020304 does not fit in an i32
+        assert!(updf.dev_addr < 0);
+        assert_eq!(updf.fopts, vec![0x02]);
+        assert_eq!(updf.f_port, -1);
+        assert!(updf.frm_payload.is_empty());
+    } else {
+        panic!("unexpected message {:?}", msg);
+    }
+}
+
+#[test]
+fn test_proprietary_data_frame() {
+    let msg = round_trip(vec![0xe0, 0x01, 0x02, 0x03]);
+    let json: serde_json::Value = serde_json::from_str(&msg.to_json().unwrap()).unwrap();
+    assert_eq!(json["msgtype"], "propdf");
+    assert_eq!(json["FRMPayload"], "E0010203");
+}
+
+#[test]
+fn test_downlink_is_not_an_uplink() {
+    let phy = parse(vec![
+        0x20, 0x49, 0x3e, 0xeb, 0x51, 0xfb, 0xa2, 0x11, 0x6f, 0x81, 0x0e, 0xdb, 0x37, 0x42, 0x97,
+        0x51, 0x42,
+    ])
+    .unwrap();
+    assert!(matches!(
+        Message::from_uplink(&phy, radio()),
+        Err(Error::UnsupportedFrame)
+    ));
+}
+
+#[test]
+fn test_downlink_message() {
+    let upinfo = radio().upinfo;
+    let mut dnmsg = DownlinkMessage::new_class_a(
+        [0, 0, 0, 0, 0, 0, 0, 1],
+        42,
+        phy_dataup_payload(),
+        1,
+        &upinfo,
+    );
+    dnmsg.rx1_dr = Some(5);
+    dnmsg.rx1_freq = Some(868_100_000);
+    let json = Message::DownlinkMessage(dnmsg.clone()).to_json().unwrap();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["msgtype"], "dnmsg");
+    assert_eq!(value["DevEui"], "00-00-00-00-00-00-00-01");
+    assert_eq!(value["dC"], 0);
+    assert_eq!(value["RX1DR"], 5);
+    assert_eq!(value["xtime"], upinfo.xtime);
+    assert!(value.get("RX2DR").is_none());
+    assert_eq!(
+        Message::parse(&json).unwrap(),
+        Message::DownlinkMessage(dnmsg.clone())
+    );
+    assert_eq!(
+        dnmsg.phy_payload().unwrap().as_ref(),
+        &phy_dataup_payload()[..]
+    );
+}
+
+#[test]
+fn test_station_messages() {
+    let version = Message::parse(
+        r#"{"msgtype":"version","station":"2.0.5(rpi/std)","firmware":null,
+        "package":null,"model":"rpi","protocol":2,"features":"rmtsh"}"#,
+    )
+    .unwrap();
+    match version {
+        Message::Version(v) => {
+            assert_eq!(v.protocol, 2);
+            assert_eq!(v.model, "rpi");
+        }
+        m => panic!("unexpected message {:?}", m),
+    }
+
+    let dntxed = Message::parse(
+        r#"{"msgtype":"dntxed","diid":42,"DevEui":"0000000000000001","rctx":1,
+        "xtime":68116944406337035,"txtime":1588000001.25,"gpstime":0}"#,
+    )
+    .unwrap();
+    match dntxed {
+        Message::DownlinkTransmitted(d) => {
+            assert_eq!(d.diid, 42);
+            assert_eq!(d.dev_eui, [0, 0, 0, 0, 0, 0, 0, 1]);
+        }
+        m => panic!("unexpected message {:?}", m),
+    }
+
+    let timesync = Message::TimeSync(TimeSync {
+        txtime: Some(1.5),
+        gpstime: Some(1_234_567),
+        xtime: None,
+    });
+    assert_eq!(
+        timesync.to_json().unwrap(),
+        r#"{"msgtype":"timesync","txtime":1.5,"gpstime":1234567}"#
+    );
+}
+
+#[test]
+fn test_router_config() {
+    let config = Message::parse(
+        r#"{"msgtype":"router_config","NetID":[1],"JoinEui":[[0,18446744073709551615]],
+        "region":"EU863","hwspec":"sx1301/1","freq_range":[863000000,870000000],
+        "DRs":[[12,125,0],[11,125,0],[10,125,0],[9,125,0],[8,125,0],[7,125,0],[7,250,0]],
+        "sx1301_conf":[{"radio_0":{"enable":true,"freq":867500000}}],"nocca":true}"#,
+    )
+    .unwrap();
+    match &config {
+        Message::RouterConfig(c) => {
+            assert_eq!(c.region, "EU863");
+            assert_eq!(c.drs[6], [7, 250, 0]);
+            assert_eq!(c.join_eui[0][1], u64::MAX);
+            assert_eq!(c.nocca, Some(true));
+            assert_eq!(c.sx1301_conf[0]["radio_0"]["freq"], 867500000);
+        }
+        m => panic!("unexpected message {:?}", m),
+    }
+    assert_eq!(Message::parse(&config.to_json().unwrap()).unwrap(), config);
+}
@@ -0,0 +1,277 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+use std::time::Duration;
+
+use lorawan_encoding::parser::{DataHeader, DataPayload, PhyPayload};
+use lorawan_gateway::error::Error;
+use lorawan_gateway::loratap::*;
+use lorawan_gateway::semtech_udp::{DataRate, RxPk, TxPk};
+
+const GATEWAY_EUI: [u8; 8] = [0xaa, 0x55, 0x5a, 0x00, 0x00, 0x00, 0x01, 0x01];
+
+fn phy_dataup_payload() -> Vec<u8> {
+    vec![
+        0x40, 0x04, 0x03, 0x02, 0x01, 0x80, 0x01, 0x00, 0x01, 0xa6, 0x94, 0x64, 0x26, 0x15, 0xd6,
+        0xc3, 0xb5, 0x82,
+    ]
+}
+
+fn rxpk() -> RxPk {
+    serde_json::from_str(
+        r#"{"tmst":3512348611,"chan":2,"rfch":0,"freq":866.349812,"stat":1,"modu":"LORA",
+        "datr":"SF7BW125","codr":"4/6","rssi":-35,"lsnr":5.1,"size":18,
+        "data":"QAQDAgGAAQABppRkJhXWw7WC"}"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_write_header_and_frame() {
+    let radio = RadioInfo::from_rxpk(&rxpk(), GATEWAY_EUI);
+    let mut writer = PcapWriter::new(Vec::new()).unwrap();
+    writer
+        .write_frame(
+            Duration::new(1_588_000_000, 250_000_000),
+            &radio,
+            phy_dataup_payload(),
+        )
+        .unwrap();
+    let bytes = writer.into_inner();
+
+    assert_eq!(
+        &bytes[..24],
+        &[
+            0xd4, 0xc3, 0xb2, 0xa1, 0x02, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0xff, 0xff, 0x00, 0x00, 0x0e, 0x01, 0x00, 0x00,
+        ][..]
+    );
+    // seconds, microseconds and twice the captured length
+    assert_eq!(
+        &bytes[24..40],
+        &[
+            0x00, 0xf5, 0xa6, 0x5e, 0x90, 0xd0, 0x03, 0x00, 0x35, 0x00, 0x00, 0x00, 0x35, 0x00,
+            0x00, 0x00,
+        ][..]
+    );
+    let loratap = &bytes[40..75];
+    assert_eq!(&loratap[..4], &[0x01, 0x00, 0x00, 0x23]);
+    // 866349812 Hz, 125 kHz, SF7
+    assert_eq!(&loratap[4..10], &[0x33, 0xa3, 0x72, 0xf4, 0x01, 0x07]);
+    // RSSI -35 dBm and SNR 5.1 dB rounded to 5 dB
+    assert_eq!(loratap[10], 104);
+    assert_eq!(loratap[13], 20);
+    assert_eq!(loratap[14], LORAWAN_PUBLIC_SYNC_WORD);
+    assert_eq!(&loratap[15..23], &GATEWAY_EUI[..]);
+    assert_eq!(&loratap[23..27], &3512348611u32.to_be_bytes()[..]);
+    // CRC OK, coding rate 4/6, IF channel 2
+    assert_eq!(loratap[27], 0x08);
+    assert_eq!(loratap[28], 6);
+    assert_eq!(loratap[31], 2);
+    assert_eq!(&bytes[75..], &phy_dataup_payload()[..]);
+}
+
+#[test]
+fn test_round_trip() {
+    let mut rx_radio = RadioInfo::from_rxpk(&rxpk(), GATEWAY_EUI);
+    rx_radio.snr = 5.0;
+    let datr = DataRate::LoRa {
+        spreading_factor: 12,
+        bandwidth: 125,
+    };
+    let mut txpk = TxPk::new_lora_downlink(869.525, datr, 14, phy_dataup_payload());
+    txpk.tmst = Some(3513348611);
+    let tx_radio = RadioInfo::from_txpk(&txpk, GATEWAY_EUI);
+    assert!(tx_radio.iq_inverted);
+    assert_eq!(tx_radio.frequency, 869_525_000);
+
+    let mut writer = PcapWriter::new(Vec::new()).unwrap();
+    let records = vec![
+        Record {
+            time: Duration::new(1_588_000_000, 250_000_000),
+            radio: rx_radio,
+            data: rxpk().data,
+        },
+        Record {
+            time: Duration::new(1_588_000_001, 250_000_000),
+            radio: tx_radio,
+            data: txpk.data.clone(),
+        },
+    ];
+    for record in &records {
+        writer.write_record(record).unwrap();
+    }
+    let bytes = writer.into_inner();
+
+    let read: Vec<Record> = PcapReader::new(&bytes[..])
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(read, records);
+    match read[0].phy_payload().unwrap() {
+        PhyPayload::Data(DataPayload::Encrypted(data)) => {
+            assert_eq!(data.fhdr().fcnt(), 1);
+        }
+        _ => panic!("unexpected payload"),
+    }
+}
+
+#[test]
+fn test_frame_without_crc() {
+    let datr = DataRate::LoRa {
+        spreading_factor: 12,
+        bandwidth: 125,
+    };
+    let mut txpk = TxPk::new_lora_downlink(869.525, datr, 14, phy_dataup_payload());
+    txpk.ncrc = Some(true);
+    let radio = RadioInfo::from_txpk(&txpk, GATEWAY_EUI);
+    assert_eq!(radio.crc_ok, None);
+
+    let mut writer = PcapWriter::new(Vec::new()).unwrap();
+    writer
+        .write_frame(Duration::default(), &radio, &txpk.data)
+        .unwrap();
+    let bytes = writer.into_inner();
+    // IQ inverted and no CRC
+    assert_eq!(bytes[40 + 27], 0x22);
+    let mut reader = PcapReader::new(&bytes[..]).unwrap();
+    assert_eq!(reader.next().unwrap().unwrap().radio, radio);
+}
+
+#[test]
+fn test_read_big_endian_nanos_v0() {
+    let mut bytes = vec![
+        0xa1, 0xb2, 0x3c, 0x4d, 0x00, 0x02, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0xff, 0xff, 0x00, 0x00, 0x01, 0x0e,
+    ];
+    bytes.extend_from_slice(&[
+        0x5e, 0xa6, 0xf5, 0x00, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x13, 0x00, 0x00, 0x00,
+        0x13,
+    ]);
+    // LoRaTap version 0: 868.1 MHz, 250 kHz, SF7, RSSI -53 dBm, SNR -2.5 dB
+    bytes.extend_from_slice(&[
+        0x00, 0x00, 0x00, 0x0f, 0x33, 0xbe, 0x27, 0xa0, 0x02, 0x07, 0x56, 0x00, 0x00, 0xf6, 0x12,
+    ]);
+    bytes.extend_from_slice(&[0xe0, 0x01, 0x02, 0x03]);
+
+    let mut reader = PcapReader::new(&bytes[..]).unwrap();
+    let record = reader.read_record().unwrap().unwrap();
+    assert_eq!(record.time, Duration::new(1_588_000_000, 7));
+    assert_eq!(record.radio.frequency, 868_100_000);
+    assert_eq!(record.radio.bandwidth, 250);
+    assert_eq!(record.radio.spreading_factor, 7);
+    assert_eq!(record.radio.rssi, -53);
+    assert_eq!(record.radio.snr, -2.5);
+    assert_eq!(record.radio.sync_word, 0x12);
+    assert_eq!(record.radio.gateway_eui, [0; 8]);
+    assert_eq!(record.data, vec![0xe0, 0x01, 0x02, 0x03]);
+    assert!(matches!(
+        record.phy_payload(),
+        Ok(PhyPayload::Proprietary(_))
+    ));
+    assert!(reader.read_record().unwrap().is_none());
+}
+
+#[test]
+fn test_read_errors() {
+    assert!(matches!(
+        PcapReader::new(&[0xd4, 0xc3, 0xb2][..]),
+        Err(Error::TooShort)
+    ));
+    let mut header = PcapWriter::new(Vec::new()).unwrap().into_inner();
+    header[20] = 1;
+    assert!(matches!(
+        PcapReader::new(&header[..]),
+        Err(Error::InvalidCapture)
+    ));
+    header[0] = 0;
+    assert!(matches!(
+        PcapReader::new(&header[..]),
+        Err(Error::InvalidCapture)
+    ));
+
+    let mut writer = PcapWriter::new(Vec::new()).unwrap();
+    writer
+        .write_frame(Duration::default(), &RadioInfo::default(), [0xe0])
+        .unwrap();
+    let bytes = writer.into_inner();
+    let mut reader = PcapReader::new(&bytes[..bytes.len() - 1]).unwrap();
+    assert!(matches!(reader.next(), Some(Err(Error::TooShort))));
+
+    let mut bytes = bytes;
+    bytes[40] = 2;
+    let mut reader = PcapReader::new(&bytes[..]).unwrap();
+    assert!(matches!(reader.next(), Some(Err(Error::InvalidCapture))));
+}
+
+#[test]
+fn test_read_invalid_fraction() {
+    let mut writer = PcapWriter::new(Vec::new()).unwrap();
+    writer
+        .write_frame(Duration::default(), &RadioInfo::default(), [0xe0])
+        .unwrap();
+    let mut bytes = writer.into_inner();
+    bytes[28..32].copy_from_slice(&999_999u32.to_le_bytes());
+    let mut reader = PcapReader::new(&bytes[..]).unwrap();
+    let record = reader.next().unwrap().unwrap();
+    assert_eq!(record.time, Duration::new(0, 999_999_000));
+
+    // microseconds that would overflow when converted to nanoseconds
+    bytes[28..32].copy_from_slice(&4_294_968u32.to_le_bytes());
+    let mut reader = PcapReader::new(&bytes[..]).unwrap();
+    assert!(matches!(reader.next(), Some(Err(Error::InvalidCapture))));
+    bytes[28..32].copy_from_slice(&1_000_000u32.to_le_bytes());
+    let mut reader = PcapReader::new(&bytes[..]).unwrap();
+    assert!(matches!(reader.next(), Some(Err(Error::InvalidCapture))));
+
+    // the same capture with nanosecond resolution
+    bytes[0..4].copy_from_slice(&[0x4d, 0x3c, 0xb2, 0xa1]);
+    let mut reader = PcapReader::new(&bytes[..]).unwrap();
+    let record = reader.next().unwrap().unwrap();
+    assert_eq!(record.time, Duration::new(0, 1_000_000));
+    bytes[28..32].copy_from_slice(&1_000_000_000u32.to_le_bytes());
+    let mut reader = PcapReader::new(&bytes[..]).unwrap();
+    assert!(matches!(reader.next(), Some(Err(Error::InvalidCapture))));
+}
+
+#[test]
+fn test_read_record_longer_than_snaplen() {
+    let mut writer = PcapWriter::new(Vec::new()).unwrap();
+    writer
+        .write_frame(Duration::default(), &RadioInfo::default(), [0xe0])
+        .unwrap();
+    let mut bytes = writer.into_inner();
+    bytes[32..36].copy_from_slice(&u32::MAX.to_le_bytes());
+    let mut reader = PcapReader::new(&bytes[..]).unwrap();
+    assert!(matches!(reader.next(), Some(Err(Error::InvalidCapture))));
+
+    // the length is also limited by a smaller snaplen of the capture
+    let mut bytes = PcapWriter::new(Vec::new()).unwrap().into_inner();
+    bytes[16..20].copy_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0, 36, 0, 0, 0, 36, 0, 0, 0]);
+    bytes.extend_from_slice(&[0; 36]);
+    let mut reader = PcapReader::new(&bytes[..]).unwrap();
+    assert!(matches!(reader.next(), Some(Err(Error::InvalidCapture))));
+}
+
+#[test]
+fn test_extreme_rssi_is_clamped() {
+    let mut writer = PcapWriter::new(Vec::new()).unwrap();
+    for rssi in &[i16::MIN, i16::MAX] {
+        let radio = RadioInfo {
+            rssi: *rssi,
+            ..Default::default()
+        };
+        writer
+            .write_frame(Duration::default(), &radio, [0xe0])
+            .unwrap();
+    }
+    let bytes = writer.into_inner();
+    let mut reader = PcapReader::new(&bytes[..]).unwrap();
+    assert_eq!(reader.next().unwrap().unwrap().radio.rssi, -139);
+    assert_eq!(reader.next().unwrap().unwrap().radio.rssi, 116);
+}
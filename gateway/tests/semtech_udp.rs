@@ -0,0 +1,206 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+use lorawan_encoding::parser::{DataHeader, DataPayload, PhyPayload};
+use lorawan_gateway::error::Error;
+use lorawan_gateway::semtech_udp::*;
+
+const GATEWAY_EUI: [u8; 8] = [0xaa, 0x55, 0x5a, 0x00, 0x00, 0x00, 0x01, 0x01];
+
+fn phy_dataup_payload() -> Vec<u8> {
+    vec![
+        0x40, 0x04, 0x03, 0x02, 0x01, 0x80, 0x01, 0x00, 0x01, 0xa6, 0x94, 0x64, 0x26, 0x15, 0xd6,
+        0xc3, 0xb5, 0x82,
+    ]
+}
+
+fn push_data_packet() -> Vec<u8> {
+    let mut res = vec![0x02, 0xab, 0xcd, 0x00];
+    res.extend_from_slice(&GATEWAY_EUI);
+    res.extend_from_slice(
+        br#"{"rxpk":[{"time":"2013-03-31T16:21:17.528002Z","tmst":3512348611,"chan":2,
+        "rfch":0,"freq":866.349812,"stat":1,"modu":"LORA","datr":"SF7BW125","codr":"4/6",
+        "rssi":-35,"lsnr":5.1,"size":18,"data":"QAQDAgGAAQABppRkJhXWw7WC"}],
+        "stat":{"time":"2014-01-12 08:59:28 GMT","lati":46.24000,"long":3.25230,"alti":145,
+        "rxnb":2,"rxok":2,"rwfw":2,"ackr":100.0,"dwnb":2,"txnb":2}}"#,
+    );
+    res
+}
+
+#[test]
+fn test_parse_push_data() {
+    let packet = Packet::parse(&push_data_packet()).unwrap();
+    assert_eq!(packet.token(), 0xabcd);
+    assert_eq!(packet.identifier(), 0x00);
+    if let Packet::PushData {
+        gateway_eui, data, ..
+    } = &packet
+    {
+        assert_eq!(*gateway_eui, GATEWAY_EUI);
+        assert_eq!(data.rxpk.len(), 1);
+        let rxpk = &data.rxpk[0];
+        assert_eq!(rxpk.tmst, 3512348611);
+        assert_eq!(rxpk.modu, Modulation::LoRa);
+        assert_eq!(
+            rxpk.datr,
+            DataRate::LoRa {
+                spreading_factor: 7,
+                bandwidth: 125
+            }
+        );
+        assert_eq!(rxpk.rssi, -35);
+        assert_eq!(rxpk.lsnr, Some(5.1));
+        assert_eq!(rxpk.data, phy_dataup_payload());
+        match rxpk.phy_payload().unwrap() {
+            PhyPayload::Data(DataPayload::Encrypted(data)) => {
+                assert_eq!(data.fhdr().fcnt(), 1);
+            }
+            _ => panic!("unexpected payload"),
+        }
+        let stat = data.stat.as_ref().unwrap();
+        assert_eq!(stat.alti, Some(145));
+        assert_eq!(stat.rxok, 2);
+    } else {
+        panic!("unexpected packet {:?}", packet);
+    }
+    assert_eq!(
+        packet.ack(),
+        Some(Packet::PushAck {
+            version: 2,
+            token: 0xabcd
+        })
+    );
+}
+
+#[test]
+fn test_push_data_round_trip() {
+    let packet = Packet::parse(&push_data_packet()).unwrap();
+    let bytes = packet.to_bytes().unwrap();
+    assert_eq!(&bytes[..12], &push_data_packet()[..12]);
+    assert_eq!(Packet::parse(&bytes).unwrap(), packet);
+}
+
+#[test]
+fn test_push_data_v1_round_trip() {
+    let mut data = push_data_packet();
+    data[0] = 0x01;
+    let packet = Packet::parse(&data).unwrap();
+    assert_eq!(packet.version(), 1);
+    let bytes = packet.to_bytes().unwrap();
+    assert_eq!(&bytes[..12], &data[..12]);
+    assert_eq!(Packet::parse(&bytes).unwrap(), packet);
+
+    let ack = packet.ack().unwrap();
+    assert_eq!(ack.to_bytes().unwrap(), vec![0x01, 0xab, 0xcd, 0x01]);
+}
+
+#[test]
+fn test_pull_data_and_ack() {
+    let mut data = vec![0x01, 0x12, 0x34, 0x02];
+    data.extend_from_slice(&GATEWAY_EUI);
+    let packet = Packet::parse(&data).unwrap();
+    assert_eq!(
+        packet,
+        Packet::PullData {
+            version: 1,
+            token: 0x1234,
+            gateway_eui: GATEWAY_EUI
+        }
+    );
+    let ack = packet.ack().unwrap();
+    // the acknowledgement uses the version of the gateway
+    assert_eq!(ack.to_bytes().unwrap(), vec![0x01, 0x12, 0x34, 0x04]);
+    assert_eq!(Packet::parse(&[0x01, 0x12, 0x34, 0x04]).unwrap(), ack);
+}
+
+#[test]
+fn test_pull_resp_with_phy_payload() {
+    let datr = DataRate::LoRa {
+        spreading_factor: 12,
+        bandwidth: 125,
+    };
+    let mut txpk = TxPk::new_lora_downlink(869.525, datr, 14, phy_dataup_payload());
+    txpk.tmst = Some(1000);
+    let packet = Packet::PullResp {
+        version: PROTOCOL_VERSION,
+        token: 7,
+        data: PullResp { txpk },
+    };
+    let bytes = packet.to_bytes().unwrap();
+    assert_eq!(&bytes[..4], &[0x02, 0x00, 0x07, 0x03]);
+    let json: serde_json::Value = serde_json::from_slice(&bytes[4..]).unwrap();
+    assert_eq!(json["txpk"]["datr"], "SF12BW125");
+    assert_eq!(json["txpk"]["ipol"], true);
+    assert_eq!(json["txpk"]["size"], 18);
+    assert_eq!(json["txpk"]["data"], "QAQDAgGAAQABppRkJhXWw7WC");
+    assert!(json["txpk"].get("imme").is_none());
+
+    let parsed = Packet::parse(&bytes).unwrap();
+    assert_eq!(parsed, packet);
+    if let Packet::PullResp { data, .. } = parsed {
+        assert_eq!(
+            data.txpk.phy_payload().unwrap().as_ref(),
+            &phy_dataup_payload()[..]
+        );
+    }
+}
+
+#[test]
+fn test_tx_ack() {
+    let mut data = vec![0x02, 0x00, 0x07, 0x05];
+    data.extend_from_slice(&GATEWAY_EUI);
+    let packet = Packet::parse(&data).unwrap();
+    assert_eq!(
+        packet,
+        Packet::TxAck {
+            version: 2,
+            token: 7,
+            gateway_eui: GATEWAY_EUI,
+            data: None
+        }
+    );
+
+    data.extend_from_slice(br#"{"txpk_ack":{"error":"TOO_LATE"}}"#);
+    match Packet::parse(&data).unwrap() {
+        Packet::TxAck {
+            data: Some(ack), ..
+        } => {
+            assert!(!ack.is_ok());
+            assert_eq!(ack.txpk_ack.error.as_deref(), Some("TOO_LATE"));
+        }
+        p => panic!("unexpected packet {:?}", p),
+    }
+}
+
+#[test]
+fn test_fsk_data_rate() {
+    let datr: DataRate = serde_json::from_str("50000").unwrap();
+    assert_eq!(datr, DataRate::Fsk(50000));
+    assert_eq!(serde_json::to_string(&datr).unwrap(), "50000");
+    assert!(serde_json::from_str::<DataRate>("\"SF7\"").is_err());
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(matches!(Packet::parse(&[0x02, 0x00]), Err(Error::TooShort)));
+    assert!(matches!(
+        Packet::parse(&[0x03, 0x00, 0x00, 0x01]),
+        Err(Error::UnsupportedVersion(3))
+    ));
+    assert!(matches!(
+        Packet::parse(&[0x02, 0x00, 0x00, 0x09]),
+        Err(Error::UnknownIdentifier(9))
+    ));
+    assert!(matches!(
+        Packet::parse(&[0x02, 0x00, 0x00, 0x02, 0x01]),
+        Err(Error::TooShort)
+    ));
+    let mut data = vec![0x02, 0x00, 0x00, 0x00];
+    data.extend_from_slice(&GATEWAY_EUI);
+    data.extend_from_slice(br#"{"rxpk":[{"data":"!!"}]}"#);
+    assert!(matches!(Packet::parse(&data), Err(Error::Json(_))));
+}
@@ -0,0 +1,88 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Provides the errors reported by the servers.
+
+use std::fmt;
+
+/// Error represents the reasons for which a frame can be rejected by the servers.
+#[derive(Debug)]
+pub enum Error {
+    /// The device is not known to the server.
+    UnknownDevice,
+    /// The JoinEUI of the JoinRequest is not the one registered for the device.
+    JoinEuiMismatch,
+    /// The MIC of the frame is not valid.
+    InvalidMic,
+    /// The DevNonce of the JoinRequest was already used by the device.
+    DevNonceReplay(u16),
+    /// The FCnt of the frame is not greater than the one of the last frame of the session.
+    FCntReplay(u32),
+    /// The frame type is not handled by the server.
+    UnsupportedFrame,
+    /// The queue of mac commands of the device is full.
+    MacCommandQueueFull,
+    /// The gateway has not pulled data from the server yet.
+    UnknownGateway,
+    /// The server settings can not be encoded in a frame.
+    InvalidSettings,
+    /// Sending or receiving packets failed.
+    Io(std::io::Error),
+    /// The packet received from a gateway is invalid.
+    Gateway(lorawan_gateway::error::Error),
+    /// The LoRaWAN frame is invalid.
+    Encoding(lorawan_encoding::error::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnknownDevice => f.write_str("unknown device"),
+            Error::JoinEuiMismatch => f.write_str("JoinEUI does not match the registered one"),
+            Error::InvalidMic => f.write_str("invalid MIC"),
+            Error::DevNonceReplay(n) => write!(f, "DevNonce {:04x} already used", n),
+            Error::FCntReplay(fcnt) => write!(f, "FCnt {} already used", fcnt),
+            Error::UnsupportedFrame => f.write_str("unsupported frame type"),
+            Error::MacCommandQueueFull => f.write_str("mac command queue full"),
+            Error::UnknownGateway => f.write_str("unknown gateway"),
+            Error::InvalidSettings => f.write_str("invalid server settings"),
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+            Error::Gateway(e) => write!(f, "invalid gateway packet: {}", e),
+            Error::Encoding(e) => write!(f, "invalid LoRaWAN frame: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Gateway(e) => Some(e),
+            Error::Encoding(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<lorawan_gateway::error::Error> for Error {
+    fn from(e: lorawan_gateway::error::Error) -> Self {
+        Error::Gateway(e)
+    }
+}
+
+impl From<lorawan_encoding::error::Error> for Error {
+    fn from(e: lorawan_encoding::error::Error) -> Self {
+        Error::Encoding(e)
+    }
+}
@@ -0,0 +1,217 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Provides a front-end feeding a [network server](../network_server/index.html) with the uplinks
+//! of gateways running the Semtech UDP packet forwarder and sending them its downlinks.
+//!
+//! Downlinks are sent in the first receive window of the device. When the gateway reports that
+//! it could not send it there, with a TX_ACK carrying an error, the downlink is sent in the second
+//! receive window instead.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::collections::HashMap;
+//! use lorawan_server::gwmp::GwmpFrontend;
+//! use lorawan_server::join_server::{JoinServer, NetId};
+//! use lorawan_server::network_server::{Event, NetworkServer, RxSettings};
+//!
+//! let join_server = JoinServer::new(HashMap::new(), NetId::new(0x000013).unwrap());
+//! let mut server = NetworkServer::new(join_server, RxSettings::default());
+//! let mut frontend = GwmpFrontend::bind("127.0.0.1:1700").unwrap();
+//! frontend
+//!     .run(&mut server, |event| {
+//!         if let Event::Uplink(uplink) = event {
+//!             println!("{:?}", uplink.frm_payload);
+//!         }
+//!     })
+//!     .unwrap();
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use lorawan_gateway::semtech_udp::{Packet, PullResp, TxPk};
+
+use super::error::Error;
+use super::join_server::DeviceRegistry;
+use super::network_server::{Event, NetworkServer, Reception};
+
+/// The number of downlinks whose TX_ACK is awaited to fall back to the second receive window.
+const MAX_PENDING_TX: usize = 64;
+
+/// How long to wait for packets when the network server has no frames to process.
+const IDLE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// GwmpFrontend exchanges the packets of the Gateway Messaging Protocol with the gateways.
+pub struct GwmpFrontend {
+    socket: UdpSocket,
+    // the address of each gateway and the protocol version it uses
+    gateways: HashMap<[u8; 8], (SocketAddr, u8)>,
+    pending_tx: VecDeque<(u16, [u8; 8], TxPk)>,
+    next_token: u16,
+}
+
+impl GwmpFrontend {
+    /// Creates a front-end listening on the given address, usually port 1700.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        Ok(Self::new(UdpSocket::bind(addr)?))
+    }
+
+    /// Creates a front-end using an already bound socket.
+    pub fn new(socket: UdpSocket) -> Self {
+        GwmpFrontend {
+            socket,
+            gateways: HashMap::new(),
+            pending_tx: VecDeque::new(),
+            next_token: 0,
+        }
+    }
+
+    /// Gives the address the front-end listens on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Handles the packets of the gateways and the events of the network server forever, giving
+    /// the events to `handler`.
+    ///
+    /// It only returns if receiving from the socket fails.
+    pub fn run<R: DeviceRegistry, H: FnMut(Event)>(
+        &mut self,
+        server: &mut NetworkServer<R>,
+        mut handler: H,
+    ) -> Result<(), Error> {
+        loop {
+            let timeout = match server.next_deadline() {
+                Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+                None => IDLE_TIMEOUT,
+            };
+            for event in self.step(server, timeout)? {
+                handler(event);
+            }
+        }
+    }
+
+    /// Waits up to `timeout` for a packet from a gateway, handles it and processes the frames
+    /// of the network server whose deduplication window is over.
+    ///
+    /// The downlinks of the network server are sent to the gateways before the events are
+    /// returned. Invalid packets and downlinks that can not be sent are reported as
+    /// [Dropped](../network_server/enum.Event.html#variant.Dropped) events.
+    pub fn step<R: DeviceRegistry>(
+        &mut self,
+        server: &mut NetworkServer<R>,
+        timeout: Duration,
+    ) -> Result<Vec<Event>, Error> {
+        let mut events = Vec::new();
+        // a zero timeout is rejected by the socket
+        self.socket
+            .set_read_timeout(Some(timeout.max(Duration::from_millis(1))))?;
+        let mut buf = [0; 65535];
+        match self.socket.recv_from(&mut buf) {
+            Ok((len, addr)) => {
+                if let Err(e) = self.handle_packet(&buf[..len], addr, server) {
+                    events.push(Event::Dropped(e));
+                }
+            }
+            Err(e) if is_timeout(&e) => (),
+            Err(e) => return Err(e.into()),
+        }
+
+        for event in server.poll(Instant::now()) {
+            let sent = match &event {
+                Event::Downlink(downlink) => self.send_txpk(
+                    downlink.gateway_eui,
+                    downlink.rx1.clone(),
+                    Some(downlink.rx2.clone()),
+                ),
+                _ => Ok(()),
+            };
+            events.push(event);
+            if let Err(e) = sent {
+                events.push(Event::Dropped(e));
+            }
+        }
+        Ok(events)
+    }
+
+    fn handle_packet<R: DeviceRegistry>(
+        &mut self,
+        data: &[u8],
+        addr: SocketAddr,
+        server: &mut NetworkServer<R>,
+    ) -> Result<(), Error> {
+        let packet = Packet::parse(data)?;
+        if let Some(ack) = packet.ack() {
+            self.socket.send_to(&ack.to_bytes()?, addr)?;
+        }
+        match packet {
+            Packet::PushData {
+                gateway_eui, data, ..
+            } => {
+                let now = Instant::now();
+                // frames with a bad CRC can not be LoRaWAN frames of our devices
+                for rxpk in data.rxpk.into_iter().filter(|rxpk| rxpk.stat == 1) {
+                    server.handle_reception(Reception { gateway_eui, rxpk }, now);
+                }
+            }
+            Packet::PullData {
+                version,
+                gateway_eui,
+                ..
+            } => {
+                self.gateways.insert(gateway_eui, (addr, version));
+            }
+            Packet::TxAck { token, data, .. } => {
+                let pos = self.pending_tx.iter().position(|(t, _, _)| *t == token);
+                if let Some((_, gateway_eui, rx2)) = pos.and_then(|i| self.pending_tx.remove(i)) {
+                    if matches!(data, Some(ack) if !ack.is_ok()) {
+                        self.send_txpk(gateway_eui, rx2, None)?;
+                    }
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn send_txpk(
+        &mut self,
+        gateway_eui: [u8; 8],
+        txpk: TxPk,
+        fallback: Option<TxPk>,
+    ) -> Result<(), Error> {
+        let (addr, version) = *self
+            .gateways
+            .get(&gateway_eui)
+            .ok_or(Error::UnknownGateway)?;
+        let token = self.next_token;
+        self.next_token = self.next_token.wrapping_add(1);
+        let packet = Packet::PullResp {
+            version,
+            token,
+            data: PullResp { txpk },
+        };
+        self.socket.send_to(&packet.to_bytes()?, addr)?;
+        if let Some(rx2) = fallback {
+            if self.pending_tx.len() == MAX_PENDING_TX {
+                self.pending_tx.pop_front();
+            }
+            self.pending_tx.push_back((token, gateway_eui, rx2));
+        }
+        Ok(())
+    }
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+    // the kind of the error depends on the platform
+    e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut
+}
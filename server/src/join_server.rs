@@ -0,0 +1,370 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Provides a join server answering the JoinRequests of LoRaWAN 1.0 devices.
+//!
+//! The join server validates the JoinRequest with the AppKey of the device found in a
+//! [DeviceRegistry](trait.DeviceRegistry.html), rejects replayed DevNonces, allocates a DevAddr
+//! from its NetID and builds the encrypted JoinAccept. The session keys derived for the new
+//! session are returned together with the JoinAccept.
+//!
+//! EUIs are given in the byte order used in the frames, i.e. least significant byte first, while
+//! NetIDs and DevAddrs are given as numbers.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::collections::HashMap;
+//! use lorawan_encoding::creator::JoinRequestCreator;
+//! use lorawan_encoding::keys::AES128;
+//! use lorawan_encoding::parser::{parse, PhyPayload};
+//! use lorawan_server::join_server::{DeviceKeys, JoinServer, NetId};
+//!
+//! let app_key = AES128([1; 16]);
+//! let mut registry = HashMap::new();
+//! registry.insert([2; 8], DeviceKeys { join_eui: [1; 8], app_key });
+//! let mut server = JoinServer::new(registry, NetId::new(0x000013).unwrap());
+//!
+//! let mut creator = JoinRequestCreator::new();
+//! creator.set_app_eui(&[1; 8]).set_dev_eui(&[2; 8]).set_dev_nonce(&[0x2d, 0x10]);
+//! let join_request = match parse(creator.build(&app_key).unwrap().to_vec()).unwrap() {
+//!     PhyPayload::JoinRequest(jr) => jr,
+//!     _ => unreachable!(),
+//! };
+//!
+//! let activation = server.handle_join_request(&join_request).unwrap();
+//! assert_eq!(activation.dev_addr, 0x2600_0000);
+//! // the same DevNonce can not be used again
+//! assert!(server.handle_join_request(&join_request).is_err());
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+
+use lorawan_encoding::creator::JoinAcceptCreator;
+use lorawan_encoding::keys::{CryptoFactory, AES128};
+use lorawan_encoding::maccommands::Frequency;
+use lorawan_encoding::parser::{EncryptedJoinAcceptPayload, JoinRequestPayload};
+
+use super::error::Error;
+
+/// The number of bits of the NwkID for each of the NetID types.
+const NWK_ID_BITS: [u32; 8] = [6, 6, 9, 11, 12, 13, 15, 17];
+
+/// NetId represents the 24 bit identifier of a network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NetId(u32);
+
+impl NetId {
+    /// Creates a NetId, returning `None` if the value does not fit in 24 bits.
+    pub fn new(net_id: u32) -> Option<Self> {
+        if net_id > 0xff_ffff {
+            None
+        } else {
+            Some(NetId(net_id))
+        }
+    }
+
+    /// Gives the type of the NetID, encoded in its 3 most significant bits.
+    pub fn net_type(&self) -> u8 {
+        (self.0 >> 21) as u8
+    }
+
+    /// Gives the NwkID, the part of the NetID that is present in the DevAddrs of the network.
+    pub fn nwk_id(&self) -> u32 {
+        self.0 & ((1 << self.nwk_id_bits()) - 1)
+    }
+
+    /// Gives the number of bits of the DevAddrs that are left for the NwkAddr.
+    pub fn nwk_addr_bits(&self) -> u32 {
+        32 - self.prefix_bits() - self.nwk_id_bits()
+    }
+
+    /// Gives the DevAddr with the given NwkAddr, or `None` if the NwkAddr does not fit.
+    pub fn dev_addr(&self, nwk_addr: u32) -> Option<u32> {
+        let addr_bits = self.nwk_addr_bits();
+        if nwk_addr >> addr_bits != 0 {
+            return None;
+        }
+        Some(self.dev_addr_prefix() | (self.nwk_id() << addr_bits) | nwk_addr)
+    }
+
+    /// Whether the DevAddr belongs to the network.
+    pub fn contains(&self, dev_addr: u32) -> bool {
+        let addr_bits = self.nwk_addr_bits();
+        dev_addr >> addr_bits == self.dev_addr(0).unwrap() >> addr_bits
+    }
+
+    /// Gives the bytes of the NetID in the order used in the frames.
+    pub fn to_bytes(&self) -> [u8; 3] {
+        let b = self.0.to_le_bytes();
+        [b[0], b[1], b[2]]
+    }
+
+    fn prefix_bits(&self) -> u32 {
+        u32::from(self.net_type()) + 1
+    }
+
+    fn nwk_id_bits(&self) -> u32 {
+        NWK_ID_BITS[self.net_type() as usize]
+    }
+
+    fn dev_addr_prefix(&self) -> u32 {
+        // net_type ones followed by a zero
+        let prefix = ((1 << self.net_type()) - 1) << 1;
+        prefix << (32 - self.prefix_bits())
+    }
+}
+
+impl From<NetId> for u32 {
+    fn from(net_id: NetId) -> u32 {
+        net_id.0
+    }
+}
+
+/// DeviceKeys holds what the join server needs to know about a device.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceKeys {
+    /// The JoinEUI (AppEUI) of the device, least significant byte first.
+    pub join_eui: [u8; 8],
+    pub app_key: AES128,
+}
+
+/// DeviceRegistry gives the keys of the devices allowed to join.
+pub trait DeviceRegistry {
+    /// Gives the keys of the device with the given DevEUI, if it is known.
+    fn device_keys(&self, dev_eui: &[u8; 8]) -> Option<DeviceKeys>;
+}
+
+impl DeviceRegistry for HashMap<[u8; 8], DeviceKeys> {
+    fn device_keys(&self, dev_eui: &[u8; 8]) -> Option<DeviceKeys> {
+        self.get(dev_eui).cloned()
+    }
+}
+
+/// DevNoncePolicy describes how DevNonces are checked for replays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DevNoncePolicy {
+    /// DevNonces are random, as in LoRaWAN 1.0.3 and earlier, and the given number of the last
+    /// DevNonces of each device can not be used again.
+    History(usize),
+    /// DevNonces are counters, as in LoRaWAN 1.0.4 and 1.1, and have to increase with every
+    /// JoinRequest.
+    Increasing,
+}
+
+/// JoinSettings holds the configuration sent to the devices in the JoinAccept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JoinSettings {
+    /// The DLSettings byte with the RX1 data rate offset and the RX2 data rate.
+    pub dl_settings: u8,
+    /// The delay of the first receive window in seconds.
+    pub rx_delay: u8,
+    /// Up to 5 additional channel frequencies in Hz, sent as CFList when not empty.
+    pub c_f_list: Vec<u32>,
+}
+
+impl Default for JoinSettings {
+    fn default() -> Self {
+        JoinSettings {
+            dl_settings: 0,
+            rx_delay: 1,
+            c_f_list: Vec::new(),
+        }
+    }
+}
+
+/// Activation is the outcome of an accepted JoinRequest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Activation {
+    /// The DevEUI of the device, least significant byte first.
+    pub dev_eui: [u8; 8],
+    pub dev_nonce: u16,
+    pub dev_addr: u32,
+    pub nwk_skey: AES128,
+    pub app_skey: AES128,
+    /// The PHYPayload of the encrypted JoinAccept to send to the device.
+    pub join_accept: Vec<u8>,
+}
+
+/// JoinServer answers the JoinRequests of the devices in its registry.
+pub struct JoinServer<R> {
+    registry: R,
+    net_id: NetId,
+    settings: JoinSettings,
+    dev_nonce_policy: DevNoncePolicy,
+    dev_nonces: HashMap<[u8; 8], VecDeque<u16>>,
+    next_nwk_addr: u32,
+    next_app_nonce: u32,
+}
+
+impl<R: DeviceRegistry> JoinServer<R> {
+    /// Creates a join server allocating DevAddrs from the given network.
+    ///
+    /// By default the JoinAccepts use the [default settings](struct.JoinSettings.html) and the
+    /// last 16 DevNonces of each device are remembered.
+    pub fn new(registry: R, net_id: NetId) -> Self {
+        JoinServer {
+            registry,
+            net_id,
+            settings: JoinSettings::default(),
+            dev_nonce_policy: DevNoncePolicy::History(16),
+            dev_nonces: HashMap::new(),
+            next_nwk_addr: 0,
+            next_app_nonce: 0,
+        }
+    }
+
+    /// Sets the configuration sent in the JoinAccepts.
+    pub fn set_settings(&mut self, settings: JoinSettings) -> &mut Self {
+        self.settings = settings;
+
+        self
+    }
+
+    /// Sets how the DevNonces are checked for replays.
+    pub fn set_dev_nonce_policy(&mut self, policy: DevNoncePolicy) -> &mut Self {
+        self.dev_nonce_policy = policy;
+
+        self
+    }
+
+    /// Gives the registry of the devices.
+    pub fn registry(&self) -> &R {
+        &self.registry
+    }
+
+    /// Gives the registry of the devices, e.g. to add new devices.
+    pub fn registry_mut(&mut self) -> &mut R {
+        &mut self.registry
+    }
+
+    /// Gives the network of the join server.
+    pub fn net_id(&self) -> NetId {
+        self.net_id
+    }
+
+    /// Validates the JoinRequest and creates the JoinAccept and the session keys for it.
+    ///
+    /// The DevNonce is only recorded when the JoinRequest is accepted, so that frames with
+    /// invalid MIC can not prevent a device from joining.
+    pub fn handle_join_request<T: AsRef<[u8]>, F: CryptoFactory>(
+        &mut self,
+        join_request: &JoinRequestPayload<T, F>,
+    ) -> Result<Activation, Error> {
+        let mut dev_eui = [0; 8];
+        dev_eui.copy_from_slice(join_request.dev_eui().as_ref());
+        let keys = self
+            .registry
+            .device_keys(&dev_eui)
+            .ok_or(Error::UnknownDevice)?;
+        if join_request.app_eui().as_ref() != keys.join_eui {
+            return Err(Error::JoinEuiMismatch);
+        }
+        if !join_request.validate_mic(&keys.app_key) {
+            return Err(Error::InvalidMic);
+        }
+        let dev_nonce = join_request.dev_nonce();
+        let dev_nonce_value = u16::from_le_bytes([dev_nonce.as_ref()[0], dev_nonce.as_ref()[1]]);
+        self.check_dev_nonce(&dev_eui, dev_nonce_value)?;
+
+        let nwk_addr_mask = (1 << self.net_id.nwk_addr_bits()) - 1;
+        let dev_addr = self
+            .net_id
+            .dev_addr(self.next_nwk_addr & nwk_addr_mask)
+            .unwrap();
+        let app_nonce = self.next_app_nonce.to_le_bytes();
+        let join_accept = self.build_join_accept(
+            &[app_nonce[0], app_nonce[1], app_nonce[2]],
+            dev_addr,
+            &keys.app_key,
+        )?;
+
+        let decrypted =
+            EncryptedJoinAcceptPayload::new(join_accept.clone())?.decrypt(&keys.app_key);
+        let nwk_skey = decrypted.derive_newskey(&dev_nonce, &keys.app_key);
+        let app_skey = decrypted.derive_appskey(&dev_nonce, &keys.app_key);
+
+        self.record_dev_nonce(dev_eui, dev_nonce_value);
+        self.next_nwk_addr = (self.next_nwk_addr + 1) & nwk_addr_mask;
+        self.next_app_nonce = (self.next_app_nonce + 1) & 0xff_ffff;
+        Ok(Activation {
+            dev_eui,
+            dev_nonce: dev_nonce_value,
+            dev_addr,
+            nwk_skey,
+            app_skey,
+            join_accept,
+        })
+    }
+
+    fn check_dev_nonce(&self, dev_eui: &[u8; 8], dev_nonce: u16) -> Result<(), Error> {
+        let used = match self.dev_nonces.get(dev_eui) {
+            Some(used) => used,
+            None => return Ok(()),
+        };
+        let replayed = match self.dev_nonce_policy {
+            DevNoncePolicy::History(_) => used.contains(&dev_nonce),
+            DevNoncePolicy::Increasing => matches!(used.back(), Some(last) if dev_nonce <= *last),
+        };
+        if replayed {
+            Err(Error::DevNonceReplay(dev_nonce))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn record_dev_nonce(&mut self, dev_eui: [u8; 8], dev_nonce: u16) {
+        let limit = match self.dev_nonce_policy {
+            DevNoncePolicy::History(n) => n,
+            DevNoncePolicy::Increasing => 1,
+        };
+        let used = self.dev_nonces.entry(dev_eui).or_default();
+        used.push_back(dev_nonce);
+        while used.len() > limit {
+            used.pop_front();
+        }
+    }
+
+    fn build_join_accept(
+        &self,
+        app_nonce: &[u8; 3],
+        dev_addr: u32,
+        key: &AES128,
+    ) -> Result<Vec<u8>, Error> {
+        let settings = &self.settings;
+        if settings.rx_delay > 0x0f
+            || settings.c_f_list.len() > 5
+            || settings
+                .c_f_list
+                .iter()
+                .any(|f| f / 100 * 100 != *f || f / 100 > 0xff_ffff)
+        {
+            return Err(Error::InvalidSettings);
+        }
+
+        let mut phy = JoinAcceptCreator::new();
+        phy.set_app_nonce(app_nonce)
+            .set_net_id(&self.net_id.to_bytes())
+            .set_dev_addr(&dev_addr.to_le_bytes())
+            .set_dl_settings(settings.dl_settings)
+            .set_rx_delay(settings.rx_delay);
+        if !settings.c_f_list.is_empty() {
+            let bytes: Vec<[u8; 3]> = settings
+                .c_f_list
+                .iter()
+                .map(|f| {
+                    let b = (f / 100).to_le_bytes();
+                    [b[0], b[1], b[2]]
+                })
+                .collect();
+            let freqs: Vec<Frequency> = bytes.iter().map(Frequency::from).collect();
+            phy.set_c_f_list(freqs)?;
+        }
+        Ok(phy.build(key)?.to_vec())
+    }
+}
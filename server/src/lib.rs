@@ -0,0 +1,14 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Provides the building blocks of the network side of LoRaWAN on top of `lorawan-encoding`.
+
+pub mod error;
+pub mod gwmp;
+pub mod join_server;
+pub mod network_server;
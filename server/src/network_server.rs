@@ -0,0 +1,636 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Provides a network server processing the uplinks received by the gateways and scheduling the
+//! downlinks answering them.
+//!
+//! The network server does not do any I/O on its own. The receptions reported by the gateways are
+//! given to [handle_reception](struct.NetworkServer.html#method.handle_reception) and the
+//! copies of the same frame received by several gateways are gathered for a deduplication window.
+//! Once it is over, [poll](struct.NetworkServer.html#method.poll) processes the frame and gives
+//! back the [events](enum.Event.html) resulting from it:
+//!
+//! * JoinRequests are handed to the [join server](../join_server/index.html) and the new session
+//!   replaces any previous session of the device;
+//! * data frames are matched with the sessions using the same DevAddr by checking their MIC,
+//!   FCnts that are not greater than the last one are rejected and the mac commands that need an
+//!   answer from the network are answered;
+//! * a downlink is scheduled through the gateway that received the frame with the best SNR when
+//!   there is something to send: a JoinAccept, the ACK of a confirmed uplink, queued mac commands
+//!   or queued application data.
+//!
+//! The first receive window uses the frequency of the uplink and the data rates are shifted by
+//! increasing the spreading factor, as in EU868.
+//!
+//! See the [gwmp](../gwmp/index.html) module for feeding the network server from the Semtech UDP
+//! packet forwarder.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::collections::HashMap;
+//! use std::time::{Duration, Instant};
+//! use lorawan_encoding::creator::DataPayloadCreator;
+//! use lorawan_encoding::keys::AES128;
+//! use lorawan_gateway::semtech_udp::RxPk;
+//! use lorawan_server::join_server::{JoinServer, NetId};
+//! use lorawan_server::network_server::{Event, NetworkServer, Reception, RxSettings, Session};
+//!
+//! let join_server = JoinServer::new(HashMap::new(), NetId::new(0x000013).unwrap());
+//! let mut server = NetworkServer::new(join_server, RxSettings::default());
+//! let (nwk_skey, app_skey) = (AES128([2; 16]), AES128([1; 16]));
+//! server.add_session(Session::new([1; 8], 0x2600_0001, nwk_skey, app_skey));
+//!
+//! let mut phy = DataPayloadCreator::new();
+//! phy.set_confirmed(true)
+//!     .set_dev_addr(&[0x01, 0x00, 0x00, 0x26])
+//!     .set_f_port(1)
+//!     .set_fcnt(1);
+//! let phy = phy.build(b"hello", &[], &nwk_skey, &app_skey).unwrap();
+//! let rxpk: RxPk = serde_json::from_value(serde_json::json!({
+//!     "tmst": 1000000, "chan": 0, "rfch": 0, "freq": 868.1, "stat": 1, "modu": "LORA",
+//!     "datr": "SF7BW125", "codr": "4/5", "rssi": -50, "lsnr": 7.5, "size": 0, "data": ""
+//! })).unwrap();
+//! let mut reception = Reception { gateway_eui: [0xaa; 8], rxpk };
+//! reception.rxpk.set_phy_payload(phy);
+//!
+//! let now = Instant::now();
+//! server.handle_reception(reception, now);
+//! let events = server.poll(now + Duration::from_secs(1));
+//! match &events[..] {
+//!     [Event::Uplink(uplink), Event::Downlink(downlink)] => {
+//!         assert_eq!(uplink.frm_payload, b"hello");
+//!         // the confirmed uplink is acknowledged in RX1, one second after the uplink
+//!         assert_eq!(downlink.rx1.tmst, Some(2000000));
+//!     }
+//!     _ => panic!("unexpected events {:?}", events),
+//! }
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryFrom;
+use std::time::{Duration, Instant};
+
+use heapless::consts::U16;
+use lorawan_encoding::adr::required_snr;
+use lorawan_encoding::creator::DataPayloadCreator;
+use lorawan_encoding::default_crypto::DefaultFactory;
+use lorawan_encoding::keys::AES128;
+use lorawan_encoding::maccommandcreator::{DeviceTimeAnsCreator, LinkCheckAnsCreator};
+use lorawan_encoding::maccommandqueue::{DownlinkMacCommand, MacCommandQueue, UplinkMacCommand};
+use lorawan_encoding::maccommands::{GPSTime, MacCommand};
+use lorawan_encoding::parser::{
+    parse, DataHeader, DataPayload, DecryptedDataPayload, EncryptedDataPayload, FCtrl, FRMPayload,
+    MHDRAble, MType, PhyPayload,
+};
+use lorawan_gateway::semtech_udp::{DataRate, RxPk, TxPk};
+
+use super::error::Error;
+use super::join_server::{Activation, DeviceRegistry, JoinServer};
+
+/// The delay in seconds of the first receive window of a JoinAccept.
+pub const JOIN_ACCEPT_DELAY1: u32 = 5;
+
+/// The delay in seconds of the second receive window of a JoinAccept.
+pub const JOIN_ACCEPT_DELAY2: u32 = 6;
+
+/// The maximum number of mac commands waiting to be sent to a device.
+pub type MacCommandQueueSize = U16;
+
+/// The largest FRMPayload of a queued downlink, so that the frame still fits in the 256 bytes
+/// buffer of `DataPayloadCreator` with the maximum of 15 bytes of mac commands in FOpts.
+pub const MAX_DOWNLINK_PAYLOAD_LEN: usize = 256 - 8 - 15 - 1 - 4;
+
+/// RxSettings holds the parameters of the receive windows of the devices.
+///
+/// They have to match the settings the devices use: the defaults of the region, which are
+/// also used for JoinAccepts, or the ones sent in the JoinAccept or with mac commands.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RxSettings {
+    /// The delay in seconds of the first receive window.
+    pub rx1_delay: u8,
+    /// The offset between the data rate of the uplink and the one of the first receive window.
+    pub rx1_dr_offset: u8,
+    /// The frequency of the second receive window in MHz.
+    pub rx2_frequency: f64,
+    /// The data rate of the second receive window.
+    pub rx2_data_rate: DataRate,
+    /// The TX power in dBm used for the downlinks.
+    pub tx_power: u8,
+}
+
+impl Default for RxSettings {
+    /// Gives the default settings of EU868.
+    fn default() -> Self {
+        RxSettings {
+            rx1_delay: 1,
+            rx1_dr_offset: 0,
+            rx2_frequency: 869.525,
+            rx2_data_rate: DataRate::LoRa {
+                spreading_factor: 12,
+                bandwidth: 125,
+            },
+            tx_power: 14,
+        }
+    }
+}
+
+/// Session holds the state of an activated device.
+#[derive(Debug)]
+pub struct Session {
+    /// The DevEUI of the device, least significant byte first.
+    pub dev_eui: [u8; 8],
+    pub dev_addr: u32,
+    pub nwk_skey: AES128,
+    pub app_skey: AES128,
+    /// The FCnt of the last accepted uplink, `None` before the first uplink of the session.
+    pub fcnt_up: Option<u32>,
+    /// The FCnt of the next downlink.
+    pub fcnt_down: u32,
+    /// The mac commands waiting to be sent in FOpts.
+    pub mac_commands: MacCommandQueue<DownlinkMacCommand, MacCommandQueueSize>,
+    /// The FPort and FRMPayload of the application downlinks waiting to be sent.
+    pub downlinks: VecDeque<(u8, Vec<u8>)>,
+}
+
+impl Session {
+    /// Creates a new session, e.g. for a device activated by personalization.
+    pub fn new(dev_eui: [u8; 8], dev_addr: u32, nwk_skey: AES128, app_skey: AES128) -> Self {
+        Session {
+            dev_eui,
+            dev_addr,
+            nwk_skey,
+            app_skey,
+            fcnt_up: None,
+            fcnt_down: 0,
+            mac_commands: MacCommandQueue::new(),
+            downlinks: VecDeque::new(),
+        }
+    }
+}
+
+impl From<&Activation> for Session {
+    fn from(activation: &Activation) -> Self {
+        Session::new(
+            activation.dev_eui,
+            activation.dev_addr,
+            activation.nwk_skey,
+            activation.app_skey,
+        )
+    }
+}
+
+/// Reception is a frame received by a gateway.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reception {
+    pub gateway_eui: [u8; 8],
+    pub rxpk: RxPk,
+}
+
+/// UplinkFrame is a data frame accepted by the network server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UplinkFrame {
+    /// The DevEUI of the device, least significant byte first.
+    pub dev_eui: [u8; 8],
+    pub dev_addr: u32,
+    /// The full 32 bit FCnt of the frame.
+    pub fcnt: u32,
+    pub confirmed: bool,
+    pub adr: bool,
+    pub f_port: Option<u8>,
+    /// The decrypted application payload, empty when the frame carries mac commands.
+    pub frm_payload: Vec<u8>,
+    /// The mac commands of the frame, both from FOpts and from FRMPayload.
+    pub mac_commands: Vec<UplinkMacCommand>,
+    /// The receptions of the frame by the gateways.
+    pub receptions: Vec<Reception>,
+}
+
+/// Downlink is a frame to be sent to a device in one of its receive windows.
+///
+/// The frame should be sent in the first receive window and, if the gateway can not send it
+/// there, in the second one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Downlink {
+    /// The gateway that has to send the frame.
+    pub gateway_eui: [u8; 8],
+    /// The DevEUI of the device, least significant byte first.
+    pub dev_eui: [u8; 8],
+    pub rx1: TxPk,
+    pub rx2: TxPk,
+}
+
+/// Event is the outcome of the processing of a frame.
+#[derive(Debug)]
+pub enum Event {
+    /// A device joined the network.
+    Joined(Activation),
+    /// A data frame was accepted.
+    Uplink(UplinkFrame),
+    /// A frame has to be sent to a device.
+    Downlink(Downlink),
+    /// A frame was rejected.
+    Dropped(Error),
+}
+
+struct PendingFrame {
+    phy: Vec<u8>,
+    deadline: Instant,
+    receptions: Vec<Reception>,
+}
+
+/// NetworkServer processes uplinks and schedules downlinks for the sessions it knows about.
+pub struct NetworkServer<R> {
+    join_server: JoinServer<R>,
+    rx_settings: RxSettings,
+    dedup_window: Duration,
+    sessions: HashMap<u32, Vec<Session>>,
+    pending: Vec<PendingFrame>,
+}
+
+impl<R: DeviceRegistry> NetworkServer<R> {
+    /// Creates a network server that uses the given join server for the JoinRequests.
+    ///
+    /// The receptions of the same frame are gathered for 200 ms by default.
+    pub fn new(join_server: JoinServer<R>, rx_settings: RxSettings) -> Self {
+        NetworkServer {
+            join_server,
+            rx_settings,
+            dedup_window: Duration::from_millis(200),
+            sessions: HashMap::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Sets how long the receptions of the same frame by different gateways are gathered.
+    pub fn set_dedup_window(&mut self, dedup_window: Duration) -> &mut Self {
+        self.dedup_window = dedup_window;
+
+        self
+    }
+
+    /// Gives the join server.
+    pub fn join_server(&mut self) -> &mut JoinServer<R> {
+        &mut self.join_server
+    }
+
+    /// Adds a session, replacing any previous session of the same device.
+    pub fn add_session(&mut self, session: Session) {
+        self.remove_session(&session.dev_eui);
+        self.sessions
+            .entry(session.dev_addr)
+            .or_default()
+            .push(session);
+    }
+
+    /// Removes the session of a device, giving it back.
+    pub fn remove_session(&mut self, dev_eui: &[u8; 8]) -> Option<Session> {
+        let mut res = None;
+        for sessions in self.sessions.values_mut() {
+            if let Some(i) = sessions.iter().position(|s| s.dev_eui == *dev_eui) {
+                res = Some(sessions.swap_remove(i));
+                break;
+            }
+        }
+        self.sessions.retain(|_, sessions| !sessions.is_empty());
+        res
+    }
+
+    /// Gives the session of a device.
+    pub fn session(&self, dev_eui: &[u8; 8]) -> Option<&Session> {
+        self.sessions
+            .values()
+            .flat_map(|sessions| sessions.iter())
+            .find(|s| s.dev_eui == *dev_eui)
+    }
+
+    /// Gives the session of a device, e.g. to queue downlinks or mac commands.
+    pub fn session_mut(&mut self, dev_eui: &[u8; 8]) -> Option<&mut Session> {
+        self.sessions
+            .values_mut()
+            .flat_map(|sessions| sessions.iter_mut())
+            .find(|s| s.dev_eui == *dev_eui)
+    }
+
+    /// Queues application data to be sent after the next uplink of the device.
+    ///
+    /// Payloads longer than `MAX_DOWNLINK_PAYLOAD_LEN` are rejected, as they can not be sent.
+    pub fn queue_downlink(
+        &mut self,
+        dev_eui: &[u8; 8],
+        f_port: u8,
+        frm_payload: Vec<u8>,
+    ) -> Result<(), Error> {
+        if f_port == 0 {
+            return Err(Error::UnsupportedFrame);
+        }
+        if frm_payload.len() > MAX_DOWNLINK_PAYLOAD_LEN {
+            return Err(lorawan_encoding::error::Error::PayloadTooLarge.into());
+        }
+        let session = self.session_mut(dev_eui).ok_or(Error::UnknownDevice)?;
+        session.downlinks.push_back((f_port, frm_payload));
+        Ok(())
+    }
+
+    /// Queues a mac command to be sent after the next uplink of the device.
+    pub fn queue_mac_command(
+        &mut self,
+        dev_eui: &[u8; 8],
+        cmd: DownlinkMacCommand,
+    ) -> Result<(), Error> {
+        let session = self.session_mut(dev_eui).ok_or(Error::UnknownDevice)?;
+        session
+            .mac_commands
+            .push(cmd)
+            .map_err(|_| Error::MacCommandQueueFull)
+    }
+
+    /// Adds the reception of a frame by a gateway.
+    ///
+    /// # Argument
+    ///
+    /// * reception - the frame and the gateway that received it.
+    /// * now - the time of the reception, used for the deduplication.
+    pub fn handle_reception(&mut self, reception: Reception, now: Instant) {
+        if let Some(frame) = self
+            .pending
+            .iter_mut()
+            .find(|f| f.phy == reception.rxpk.data)
+        {
+            frame.receptions.push(reception);
+            return;
+        }
+        self.pending.push(PendingFrame {
+            phy: reception.rxpk.data.clone(),
+            deadline: now + self.dedup_window,
+            receptions: vec![reception],
+        });
+    }
+
+    /// Gives the time at which [poll](#method.poll) will have frames to process.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending.iter().map(|f| f.deadline).min()
+    }
+
+    /// Processes the frames whose deduplication window is over.
+    pub fn poll(&mut self, now: Instant) -> Vec<Event> {
+        let mut events = Vec::new();
+        let mut i = 0;
+        while i < self.pending.len() {
+            if self.pending[i].deadline <= now {
+                let frame = self.pending.remove(i);
+                self.process(frame, &mut events);
+            } else {
+                i += 1;
+            }
+        }
+        events
+    }
+
+    fn process(&mut self, frame: PendingFrame, events: &mut Vec<Event>) {
+        let res = match parse(frame.phy) {
+            Ok(PhyPayload::JoinRequest(join_request)) => {
+                match self.join_server.handle_join_request(&join_request) {
+                    Ok(activation) => {
+                        self.add_session(Session::from(&activation));
+                        let downlink = self.downlink(
+                            &frame.receptions,
+                            activation.dev_eui,
+                            &activation.join_accept,
+                            true,
+                        );
+                        events.push(Event::Joined(activation));
+                        downlink
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            Ok(PhyPayload::Data(DataPayload::Encrypted(payload))) if payload.is_uplink() => {
+                self.process_data(payload, frame.receptions, events)
+            }
+            Ok(_) => Err(Error::UnsupportedFrame),
+            Err(e) => Err(Error::Encoding(e)),
+        };
+        match res {
+            Ok(Some(downlink)) => events.push(Event::Downlink(downlink)),
+            Ok(None) => (),
+            Err(e) => events.push(Event::Dropped(e)),
+        }
+    }
+
+    fn process_data(
+        &mut self,
+        payload: EncryptedDataPayload<Vec<u8>, DefaultFactory>,
+        receptions: Vec<Reception>,
+        events: &mut Vec<Event>,
+    ) -> Result<Option<Downlink>, Error> {
+        let dev_addr = u32::from_le_bytes(to_array(payload.fhdr().dev_addr().as_ref()));
+        let candidates = self
+            .sessions
+            .get_mut(&dev_addr)
+            .ok_or(Error::UnknownDevice)?;
+        let found = payload
+            .find_session(
+                candidates
+                    .iter()
+                    .map(|s| (&s.nwk_skey, s.fcnt_up.unwrap_or(0))),
+            )
+            .ok_or(Error::InvalidMic)?;
+        let fcnt = found.fcnt;
+        let session = &mut candidates[found.index];
+        if matches!(session.fcnt_up, Some(last) if fcnt <= last) {
+            return Err(Error::FCntReplay(fcnt));
+        }
+        let decrypted = payload.decrypt(Some(&session.nwk_skey), Some(&session.app_skey), fcnt)?;
+        session.fcnt_up = Some(fcnt);
+
+        let uplink = uplink_frame(session, fcnt, &decrypted, receptions);
+        answer_mac_commands(session, &uplink);
+        let downlink = if uplink.confirmed
+            || !session.mac_commands.is_empty()
+            || !session.downlinks.is_empty()
+        {
+            Some(build_data_downlink(session, uplink.confirmed))
+        } else {
+            None
+        };
+        let dev_eui = session.dev_eui;
+        // the uplink is accepted even if its downlink can not be sent, which is then dropped
+        let res = match downlink {
+            Some(Ok(phy)) => self.downlink(&uplink.receptions, dev_eui, &phy, false),
+            Some(Err(e)) => Err(e),
+            None => Ok(None),
+        };
+        events.push(Event::Uplink(uplink));
+        res
+    }
+
+    fn downlink(
+        &self,
+        receptions: &[Reception],
+        dev_eui: [u8; 8],
+        phy: &[u8],
+        join_accept: bool,
+    ) -> Result<Option<Downlink>, Error> {
+        let best = receptions
+            .iter()
+            .max_by(|a, b| {
+                let snr_a = a.rxpk.lsnr.unwrap_or(f32::MIN);
+                let snr_b = b.rxpk.lsnr.unwrap_or(f32::MIN);
+                snr_a
+                    .partial_cmp(&snr_b)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then(a.rxpk.rssi.cmp(&b.rxpk.rssi))
+            })
+            .ok_or(Error::UnsupportedFrame)?;
+        let settings = &self.rx_settings;
+        let (rx1_delay, rx1_dr_offset) = if join_accept {
+            (JOIN_ACCEPT_DELAY1, 0)
+        } else {
+            (u32::from(settings.rx1_delay.max(1)), settings.rx1_dr_offset)
+        };
+        let rx1_data_rate = match best.rxpk.datr {
+            DataRate::LoRa {
+                spreading_factor,
+                bandwidth,
+            } => DataRate::LoRa {
+                spreading_factor: (spreading_factor + rx1_dr_offset).min(12),
+                bandwidth,
+            },
+            DataRate::Fsk(_) => settings.rx2_data_rate,
+        };
+
+        let rx = &best.rxpk;
+        let mut rx1 = TxPk::new_lora_downlink(rx.freq, rx1_data_rate, settings.tx_power, phy);
+        rx1.tmst = Some(rx.tmst.wrapping_add(rx1_delay * 1_000_000));
+        let mut rx2 = TxPk::new_lora_downlink(
+            settings.rx2_frequency,
+            settings.rx2_data_rate,
+            settings.tx_power,
+            phy,
+        );
+        rx2.tmst = Some(rx.tmst.wrapping_add((rx1_delay + 1) * 1_000_000));
+        Ok(Some(Downlink {
+            gateway_eui: best.gateway_eui,
+            dev_eui,
+            rx1,
+            rx2,
+        }))
+    }
+}
+
+fn to_array(bytes: &[u8]) -> [u8; 4] {
+    [bytes[0], bytes[1], bytes[2], bytes[3]]
+}
+
+fn uplink_frame(
+    session: &Session,
+    fcnt: u32,
+    decrypted: &DecryptedDataPayload<Vec<u8>>,
+    receptions: Vec<Reception>,
+) -> UplinkFrame {
+    let fhdr = decrypted.fhdr();
+    let mut mac_commands: Vec<UplinkMacCommand> = fhdr
+        .fopts()
+        .filter_map(|cmd| UplinkMacCommand::try_from(cmd).ok())
+        .collect();
+    let mut frm_payload = Vec::new();
+    match decrypted.frm_payload() {
+        Ok(FRMPayload::Data(data)) => frm_payload.extend_from_slice(data),
+        Ok(FRMPayload::MACCommands(cmds)) => mac_commands.extend(
+            cmds.mac_commands()
+                .filter_map(|cmd| UplinkMacCommand::try_from(cmd).ok()),
+        ),
+        _ => (),
+    }
+    UplinkFrame {
+        dev_eui: session.dev_eui,
+        dev_addr: session.dev_addr,
+        fcnt,
+        confirmed: decrypted.mhdr().mtype() == MType::ConfirmedDataUp,
+        adr: fhdr.fctrl().adr(),
+        f_port: decrypted.f_port(),
+        frm_payload,
+        mac_commands,
+        receptions,
+    }
+}
+
+fn answer_mac_commands(session: &mut Session, uplink: &UplinkFrame) {
+    for cmd in &uplink.mac_commands {
+        let answer: DownlinkMacCommand = match cmd.to_mac_command() {
+            MacCommand::LinkCheckReq(_) => {
+                let margin = uplink
+                    .receptions
+                    .iter()
+                    .filter_map(|r| match (r.rxpk.datr, r.rxpk.lsnr) {
+                        (
+                            DataRate::LoRa {
+                                spreading_factor, ..
+                            },
+                            Some(snr),
+                        ) => Some(snr - required_snr(spreading_factor)),
+                        _ => None,
+                    })
+                    .fold(0.0f32, f32::max);
+                let mut creator = LinkCheckAnsCreator::new();
+                creator
+                    .set_margin(margin.min(254.0) as u8)
+                    .set_gateway_count(uplink.receptions.len().min(255) as u8);
+                creator.into()
+            }
+            MacCommand::DeviceTimeReq(_) => {
+                // only gateways with a GPS know the time of the reception
+                let gps_millis = match uplink.receptions.iter().find_map(|r| r.rxpk.tmms) {
+                    Some(millis) => millis,
+                    None => continue,
+                };
+                let mut creator = DeviceTimeAnsCreator::new();
+                creator.set_gps_time(GPSTime::new(
+                    (gps_millis / 1000) as u32,
+                    ((gps_millis % 1000) * 256 / 1000) as u8,
+                ));
+                creator.into()
+            }
+            _ => continue,
+        };
+        // answers that do not fit are dropped, the device asks again
+        let _ = session.mac_commands.push(answer);
+    }
+}
+
+fn build_data_downlink(session: &mut Session, ack: bool) -> Result<Vec<u8>, Error> {
+    let downlink = session.downlinks.pop_front();
+    let mut fctrl = 0;
+    if ack {
+        fctrl |= 0x20;
+    }
+    if !session.downlinks.is_empty() {
+        fctrl |= 0x10;
+    }
+
+    let mut phy = DataPayloadCreator::new();
+    phy.set_uplink(false)
+        .set_dev_addr(&session.dev_addr.to_le_bytes())
+        .set_fctrl(&FCtrl::new(fctrl, false))
+        .set_fcnt(session.fcnt_down);
+    if let Some((f_port, _)) = &downlink {
+        phy.set_f_port(*f_port);
+    }
+    let frm_payload = downlink.as_ref().map(|(_, p)| &p[..]).unwrap_or(&[]);
+    let (res, sent) = {
+        let cmds = session.mac_commands.fopts_commands();
+        let res = phy
+            .build(frm_payload, &cmds, &session.nwk_skey, &session.app_skey)?
+            .to_vec();
+        (res, cmds.len())
+    };
+    session.mac_commands.remove_first(sent);
+    session.fcnt_down = session.fcnt_down.wrapping_add(1);
+    Ok(res)
+}
@@ -0,0 +1,163 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+use std::collections::HashMap;
+use std::net::UdpSocket;
+use std::time::Duration;
+
+use lorawan_encoding::creator::DataPayloadCreator;
+use lorawan_encoding::keys::AES128;
+use lorawan_gateway::semtech_udp::{Packet, PushData, RxPk, TxAck, TxPkAck, PROTOCOL_VERSION};
+use lorawan_server::error::Error;
+use lorawan_server::gwmp::GwmpFrontend;
+use lorawan_server::join_server::{DeviceKeys, JoinServer, NetId};
+use lorawan_server::network_server::{Event, NetworkServer, RxSettings, Session};
+
+const GATEWAY_EUI: [u8; 8] = [0xaa; 8];
+
+fn server() -> NetworkServer<HashMap<[u8; 8], DeviceKeys>> {
+    let join_server = JoinServer::new(HashMap::new(), NetId::new(0x000013).unwrap());
+    let mut server = NetworkServer::new(join_server, RxSettings::default());
+    server.set_dedup_window(Duration::from_millis(0));
+    server.add_session(Session::new(
+        [1; 8],
+        0x2600_0001,
+        AES128([2; 16]),
+        AES128([1; 16]),
+    ));
+    server
+}
+
+fn push_data(token: u16) -> Packet {
+    let mut phy = DataPayloadCreator::new();
+    phy.set_confirmed(true)
+        .set_dev_addr(&[0x01, 0x00, 0x00, 0x26])
+        .set_f_port(1)
+        .set_fcnt(u32::from(token));
+    let phy = phy
+        .build(b"hi", &[], &AES128([2; 16]), &AES128([1; 16]))
+        .unwrap();
+    let mut rxpk: RxPk = serde_json::from_value(serde_json::json!({
+        "tmst": 1000000, "chan": 0, "rfch": 0, "freq": 868.1, "stat": 1, "modu": "LORA",
+        "datr": "SF7BW125", "codr": "4/5", "rssi": -50, "lsnr": 7.5, "size": 0, "data": ""
+    }))
+    .unwrap();
+    rxpk.set_phy_payload(phy);
+    Packet::PushData {
+        version: PROTOCOL_VERSION,
+        token,
+        gateway_eui: GATEWAY_EUI,
+        data: PushData {
+            rxpk: vec![rxpk],
+            stat: None,
+        },
+    }
+}
+
+fn receive(socket: &UdpSocket) -> Packet {
+    let mut buf = [0; 4096];
+    let len = socket.recv(&mut buf).unwrap();
+    Packet::parse(&buf[..len]).unwrap()
+}
+
+#[test]
+fn test_uplink_and_downlinks() {
+    let mut server = server();
+    let mut frontend = GwmpFrontend::bind("127.0.0.1:0").unwrap();
+    let gateway = UdpSocket::bind("127.0.0.1:0").unwrap();
+    gateway
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    gateway.connect(frontend.local_addr().unwrap()).unwrap();
+    let timeout = Duration::from_secs(5);
+
+    // the downlink can not be sent before the gateway pulls data
+    gateway.send(&push_data(1).to_bytes().unwrap()).unwrap();
+    let events = frontend.step(&mut server, timeout).unwrap();
+    assert!(matches!(
+        &events[..],
+        [
+            Event::Uplink(_),
+            Event::Downlink(_),
+            Event::Dropped(Error::UnknownGateway)
+        ]
+    ));
+    assert_eq!(
+        receive(&gateway),
+        Packet::PushAck {
+            version: PROTOCOL_VERSION,
+            token: 1
+        }
+    );
+
+    let pull_data = Packet::PullData {
+        version: PROTOCOL_VERSION,
+        token: 7,
+        gateway_eui: GATEWAY_EUI,
+    };
+    gateway.send(&pull_data.to_bytes().unwrap()).unwrap();
+    assert!(frontend.step(&mut server, timeout).unwrap().is_empty());
+    assert_eq!(
+        receive(&gateway),
+        Packet::PullAck {
+            version: PROTOCOL_VERSION,
+            token: 7
+        }
+    );
+
+    gateway.send(&push_data(2).to_bytes().unwrap()).unwrap();
+    let events = frontend.step(&mut server, timeout).unwrap();
+    assert!(matches!(
+        &events[..],
+        [Event::Uplink(_), Event::Downlink(_)]
+    ));
+    assert_eq!(
+        receive(&gateway),
+        Packet::PushAck {
+            version: PROTOCOL_VERSION,
+            token: 2
+        }
+    );
+    let token = match receive(&gateway) {
+        Packet::PullResp { token, data, .. } => {
+            assert_eq!(data.txpk.freq, 868.1);
+            assert_eq!(data.txpk.tmst, Some(2_000_000));
+            token
+        }
+        p => panic!("unexpected packet {:?}", p),
+    };
+
+    // the gateway is too late for RX1, the downlink is sent in RX2
+    let tx_ack = Packet::TxAck {
+        version: PROTOCOL_VERSION,
+        token,
+        gateway_eui: GATEWAY_EUI,
+        data: Some(TxAck {
+            txpk_ack: TxPkAck {
+                error: Some("TOO_LATE".into()),
+                ..Default::default()
+            },
+        }),
+    };
+    gateway.send(&tx_ack.to_bytes().unwrap()).unwrap();
+    assert!(frontend.step(&mut server, timeout).unwrap().is_empty());
+    match receive(&gateway) {
+        Packet::PullResp { data, .. } => {
+            assert_eq!(data.txpk.freq, 869.525);
+            assert_eq!(data.txpk.tmst, Some(3_000_000));
+        }
+        p => panic!("unexpected packet {:?}", p),
+    }
+
+    gateway.send(&[0x02, 0x00]).unwrap();
+    let events = frontend.step(&mut server, timeout).unwrap();
+    assert!(matches!(&events[..], [Event::Dropped(Error::Gateway(_))]));
+    assert!(frontend
+        .step(&mut server, Duration::from_millis(10))
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,209 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+use std::collections::HashMap;
+
+use lorawan_encoding::creator::JoinRequestCreator;
+use lorawan_encoding::default_crypto::DefaultFactory;
+use lorawan_encoding::keys::AES128;
+use lorawan_encoding::parser::{
+    parse, DevNonce, EncryptedJoinAcceptPayload, JoinRequestPayload, PhyPayload,
+};
+use lorawan_server::error::Error;
+use lorawan_server::join_server::*;
+
+const JOIN_EUI: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+const DEV_EUI: [u8; 8] = [8, 7, 6, 5, 4, 3, 2, 1];
+
+fn app_key() -> AES128 {
+    AES128([
+        0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee,
+        0xff,
+    ])
+}
+
+fn server() -> JoinServer<HashMap<[u8; 8], DeviceKeys>> {
+    let mut registry = HashMap::new();
+    registry.insert(
+        DEV_EUI,
+        DeviceKeys {
+            join_eui: JOIN_EUI,
+            app_key: app_key(),
+        },
+    );
+    JoinServer::new(registry, NetId::new(0x000013).unwrap())
+}
+
+fn join_request(
+    join_eui: &[u8; 8],
+    dev_nonce: u16,
+    key: &AES128,
+) -> JoinRequestPayload<Vec<u8>, DefaultFactory> {
+    let mut creator = JoinRequestCreator::new();
+    creator
+        .set_app_eui(join_eui)
+        .set_dev_eui(&DEV_EUI)
+        .set_dev_nonce(&dev_nonce.to_le_bytes());
+    match parse(creator.build(key).unwrap().to_vec()).unwrap() {
+        PhyPayload::JoinRequest(jr) => jr,
+        _ => panic!("failed to parse JoinRequest"),
+    }
+}
+
+#[test]
+fn test_net_id_dev_addrs() {
+    let net_id = NetId::new(0x000013).unwrap();
+    assert_eq!(net_id.net_type(), 0);
+    assert_eq!(net_id.nwk_id(), 0x13);
+    assert_eq!(net_id.nwk_addr_bits(), 25);
+    assert_eq!(net_id.dev_addr(1), Some(0x2600_0001));
+    assert_eq!(net_id.dev_addr(1 << 25), None);
+    assert!(net_id.contains(0x27ff_ffff));
+    assert!(!net_id.contains(0x2800_0000));
+    assert_eq!(net_id.to_bytes(), [0x13, 0x00, 0x00]);
+
+    let net_id = NetId::new(0x60_002f).unwrap();
+    assert_eq!(net_id.net_type(), 3);
+    assert_eq!(net_id.nwk_addr_bits(), 17);
+    assert_eq!(net_id.dev_addr(5), Some(0xe05e_0005));
+
+    let net_id = NetId::new(0xe0_0001).unwrap();
+    assert_eq!(net_id.net_type(), 7);
+    assert_eq!(net_id.nwk_addr_bits(), 7);
+    assert_eq!(net_id.dev_addr(0x7f), Some(0xfe00_00ff));
+
+    assert_eq!(NetId::new(0x100_0000), None);
+}
+
+#[test]
+fn test_join_accept_and_session_keys() {
+    let mut server = server();
+    server.set_settings(JoinSettings {
+        dl_settings: 0x13,
+        rx_delay: 5,
+        c_f_list: vec![867_100_000, 867_300_000],
+    });
+    let activation = server
+        .handle_join_request(&join_request(&JOIN_EUI, 0x102d, &app_key()))
+        .unwrap();
+    assert_eq!(activation.dev_eui, DEV_EUI);
+    assert_eq!(activation.dev_nonce, 0x102d);
+    assert_eq!(activation.dev_addr, 0x2600_0000);
+    assert_eq!(activation.join_accept.len(), 33);
+
+    // the device decrypts the JoinAccept and derives the same keys
+    let join_accept = EncryptedJoinAcceptPayload::new(activation.join_accept.clone())
+        .unwrap()
+        .decrypt(&app_key());
+    assert!(join_accept.validate_mic(&app_key()));
+    assert_eq!(join_accept.dev_addr().as_ref(), &[0x00, 0x00, 0x00, 0x26]);
+    assert_eq!(join_accept.net_id().as_ref(), &[0x13, 0x00, 0x00]);
+    assert_eq!(join_accept.dl_settings().raw_value(), 0x13);
+    assert_eq!(join_accept.rx_delay(), 5);
+    let c_f_list = join_accept.c_f_list().unwrap();
+    assert_eq!(c_f_list[0].value(), 867_100_000);
+    assert_eq!(c_f_list[1].value(), 867_300_000);
+    assert_eq!(c_f_list[2].value(), 0);
+    let dev_nonce = DevNonce::new(&[0x2d, 0x10][..]).unwrap();
+    assert_eq!(
+        join_accept.derive_newskey(&dev_nonce, &app_key()),
+        activation.nwk_skey
+    );
+    assert_eq!(
+        join_accept.derive_appskey(&dev_nonce, &app_key()),
+        activation.app_skey
+    );
+    assert_ne!(activation.nwk_skey, activation.app_skey);
+
+    // the next join gets a new address and AppNonce
+    let next = server
+        .handle_join_request(&join_request(&JOIN_EUI, 0x102e, &app_key()))
+        .unwrap();
+    assert_eq!(next.dev_addr, 0x2600_0001);
+    assert_ne!(next.nwk_skey, activation.nwk_skey);
+}
+
+#[test]
+fn test_rejected_join_requests() {
+    let mut server = server();
+    let other_eui = [9; 8];
+    assert!(matches!(
+        server.handle_join_request(&join_request(&other_eui, 1, &app_key())),
+        Err(Error::JoinEuiMismatch)
+    ));
+    assert!(matches!(
+        server.handle_join_request(&join_request(&JOIN_EUI, 1, &AES128([1; 16]))),
+        Err(Error::InvalidMic)
+    ));
+    // the DevNonce of the frame with invalid MIC is not consumed
+    assert!(server
+        .handle_join_request(&join_request(&JOIN_EUI, 1, &app_key()))
+        .is_ok());
+    assert!(matches!(
+        server.handle_join_request(&join_request(&JOIN_EUI, 1, &app_key())),
+        Err(Error::DevNonceReplay(1))
+    ));
+
+    server.registry_mut().clear();
+    assert!(matches!(
+        server.handle_join_request(&join_request(&JOIN_EUI, 2, &app_key())),
+        Err(Error::UnknownDevice)
+    ));
+}
+
+#[test]
+fn test_dev_nonce_history() {
+    let mut server = server();
+    server.set_dev_nonce_policy(DevNoncePolicy::History(2));
+    for dev_nonce in &[5, 3, 9] {
+        assert!(server
+            .handle_join_request(&join_request(&JOIN_EUI, *dev_nonce, &app_key()))
+            .is_ok());
+    }
+    assert!(server
+        .handle_join_request(&join_request(&JOIN_EUI, 9, &app_key()))
+        .is_err());
+    // only the last 2 DevNonces are remembered
+    assert!(server
+        .handle_join_request(&join_request(&JOIN_EUI, 5, &app_key()))
+        .is_ok());
+}
+
+#[test]
+fn test_increasing_dev_nonce() {
+    let mut server = server();
+    server.set_dev_nonce_policy(DevNoncePolicy::Increasing);
+    assert!(server
+        .handle_join_request(&join_request(&JOIN_EUI, 5, &app_key()))
+        .is_ok());
+    assert!(matches!(
+        server.handle_join_request(&join_request(&JOIN_EUI, 4, &app_key())),
+        Err(Error::DevNonceReplay(4))
+    ));
+    assert!(server
+        .handle_join_request(&join_request(&JOIN_EUI, 6, &app_key()))
+        .is_ok());
+}
+
+#[test]
+fn test_invalid_settings() {
+    let mut server = server();
+    server.set_settings(JoinSettings {
+        c_f_list: vec![867_100_050],
+        ..Default::default()
+    });
+    assert!(matches!(
+        server.handle_join_request(&join_request(&JOIN_EUI, 1, &app_key())),
+        Err(Error::InvalidSettings)
+    ));
+    // the DevNonce can be used once the settings are fixed
+    server.set_settings(JoinSettings::default());
+    let activation = server
+        .handle_join_request(&join_request(&JOIN_EUI, 1, &app_key()))
+        .unwrap();
+    assert_eq!(activation.join_accept.len(), 17);
+}
@@ -0,0 +1,349 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use lorawan_encoding::creator::{DataPayloadCreator, JoinRequestCreator};
+use lorawan_encoding::error::Error as EncodingError;
+use lorawan_encoding::keys::AES128;
+use lorawan_encoding::maccommandcreator::{DutyCycleReqCreator, LinkCheckReqCreator};
+use lorawan_encoding::maccommandqueue::UplinkMacCommand;
+use lorawan_encoding::maccommands::{MacCommand, SerializableMacCommand};
+use lorawan_encoding::parser::{
+    parse, DataHeader, DataPayload, DecryptedDataPayload, EncryptedJoinAcceptPayload, FRMPayload,
+    PhyPayload,
+};
+use lorawan_gateway::semtech_udp::{DataRate, RxPk};
+use lorawan_server::error::Error;
+use lorawan_server::join_server::{DeviceKeys, JoinServer, NetId};
+use lorawan_server::network_server::*;
+
+const DEV_ADDR: u32 = 0x2600_0001;
+const GATEWAY_A: [u8; 8] = [0xaa; 8];
+const GATEWAY_B: [u8; 8] = [0xbb; 8];
+
+fn nwk_skey() -> AES128 {
+    AES128([2; 16])
+}
+
+fn app_skey() -> AES128 {
+    AES128([1; 16])
+}
+
+fn server() -> NetworkServer<HashMap<[u8; 8], DeviceKeys>> {
+    let join_server = JoinServer::new(HashMap::new(), NetId::new(0x000013).unwrap());
+    let mut server = NetworkServer::new(join_server, RxSettings::default());
+    server.add_session(Session::new([1; 8], DEV_ADDR, nwk_skey(), app_skey()));
+    server
+}
+
+fn uplink(
+    fcnt: u32,
+    confirmed: bool,
+    payload: &[u8],
+    cmds: &[&dyn SerializableMacCommand],
+    nwk_skey: &AES128,
+) -> Vec<u8> {
+    let mut phy = DataPayloadCreator::new();
+    phy.set_confirmed(confirmed)
+        .set_dev_addr(&DEV_ADDR.to_le_bytes())
+        .set_fcnt(fcnt);
+    if !payload.is_empty() {
+        phy.set_f_port(1);
+    }
+    phy.build(payload, cmds, nwk_skey, &app_skey())
+        .unwrap()
+        .to_vec()
+}
+
+fn reception(gateway_eui: [u8; 8], phy: &[u8], lsnr: f32) -> Reception {
+    let mut rxpk: RxPk = serde_json::from_value(serde_json::json!({
+        "tmst": 1000000, "tmms": 1_262_304_018_500u64, "chan": 0, "rfch": 0, "freq": 868.3,
+        "stat": 1, "modu": "LORA", "datr": "SF9BW125", "codr": "4/5", "rssi": -80,
+        "lsnr": lsnr, "size": 0, "data": ""
+    }))
+    .unwrap();
+    rxpk.set_phy_payload(phy);
+    Reception { gateway_eui, rxpk }
+}
+
+fn process(server: &mut NetworkServer<HashMap<[u8; 8], DeviceKeys>>, phy: &[u8]) -> Vec<Event> {
+    let now = Instant::now();
+    server.handle_reception(reception(GATEWAY_A, phy, 5.0), now);
+    server.poll(now + Duration::from_secs(1))
+}
+
+fn decrypt_downlink(downlink: &Downlink) -> DecryptedDataPayload<Vec<u8>> {
+    match parse(downlink.rx1.data.clone()).unwrap() {
+        PhyPayload::Data(DataPayload::Encrypted(phy)) => {
+            let fcnt = u32::from(phy.fhdr().fcnt());
+            phy.decrypt_if_mic_ok(&nwk_skey(), &app_skey(), fcnt)
+                .unwrap()
+        }
+        _ => panic!("unexpected downlink"),
+    }
+}
+
+#[test]
+fn test_deduplication_and_best_gateway() {
+    let mut server = server();
+    let phy = uplink(1, true, b"hello", &[], &nwk_skey());
+    let now = Instant::now();
+    server.handle_reception(reception(GATEWAY_A, &phy, -3.0), now);
+    server.handle_reception(
+        reception(GATEWAY_B, &phy, 6.5),
+        now + Duration::from_millis(50),
+    );
+    assert_eq!(
+        server.next_deadline(),
+        Some(now + Duration::from_millis(200))
+    );
+    assert!(server.poll(now + Duration::from_millis(100)).is_empty());
+
+    let events = server.poll(now + Duration::from_millis(200));
+    assert_eq!(events.len(), 2);
+    match &events[0] {
+        Event::Uplink(uplink) => {
+            assert_eq!(uplink.dev_eui, [1; 8]);
+            assert_eq!(uplink.fcnt, 1);
+            assert!(uplink.confirmed);
+            assert_eq!(uplink.f_port, Some(1));
+            assert_eq!(uplink.frm_payload, b"hello");
+            assert_eq!(uplink.receptions.len(), 2);
+        }
+        e => panic!("unexpected event {:?}", e),
+    }
+    match &events[1] {
+        Event::Downlink(downlink) => {
+            assert_eq!(downlink.gateway_eui, GATEWAY_B);
+            assert_eq!(downlink.rx1.freq, 868.3);
+            assert_eq!(downlink.rx1.tmst, Some(2_000_000));
+            assert_eq!(
+                downlink.rx1.datr,
+                DataRate::LoRa {
+                    spreading_factor: 9,
+                    bandwidth: 125
+                }
+            );
+            assert_eq!(downlink.rx2.freq, 869.525);
+            assert_eq!(downlink.rx2.tmst, Some(3_000_000));
+            let decrypted = decrypt_downlink(downlink);
+            assert!(decrypted.fhdr().fctrl().ack());
+            assert_eq!(decrypted.fhdr().fcnt(), 0);
+        }
+        e => panic!("unexpected event {:?}", e),
+    }
+    assert!(server.poll(now + Duration::from_secs(1)).is_empty());
+    assert_eq!(server.session(&[1; 8]).unwrap().fcnt_down, 1);
+}
+
+#[test]
+fn test_sessions_sharing_dev_addr() {
+    let mut server = server();
+    let other_nwk_skey = AES128([7; 16]);
+    server.add_session(Session::new([2; 8], DEV_ADDR, other_nwk_skey, app_skey()));
+
+    let events = process(&mut server, &uplink(1, false, b"a", &[], &other_nwk_skey));
+    match &events[..] {
+        [Event::Uplink(uplink)] => assert_eq!(uplink.dev_eui, [2; 8]),
+        e => panic!("unexpected events {:?}", e),
+    }
+    assert_eq!(server.session(&[2; 8]).unwrap().fcnt_up, Some(1));
+    assert_eq!(server.session(&[1; 8]).unwrap().fcnt_up, None);
+
+    let events = process(&mut server, &uplink(1, false, b"a", &[], &AES128([8; 16])));
+    assert!(matches!(&events[..], [Event::Dropped(Error::InvalidMic)]));
+}
+
+#[test]
+fn test_fcnt_tracking() {
+    let mut server = server();
+    server.session_mut(&[1; 8]).unwrap().fcnt_up = Some(0xfffe);
+    let events = process(&mut server, &uplink(0xffff, false, b"a", &[], &nwk_skey()));
+    assert!(matches!(&events[..], [Event::Uplink(u)] if u.fcnt == 0xffff));
+    // the 16 bit counter rolls over
+    let events = process(
+        &mut server,
+        &uplink(0x1_0000, false, b"a", &[], &nwk_skey()),
+    );
+    assert!(matches!(&events[..], [Event::Uplink(u)] if u.fcnt == 0x1_0000));
+    // replays are rejected
+    let events = process(
+        &mut server,
+        &uplink(0x1_0000, false, b"a", &[], &nwk_skey()),
+    );
+    assert!(matches!(
+        &events[..],
+        [Event::Dropped(Error::FCntReplay(0x1_0000))]
+    ));
+
+    let mut unknown = uplink(1, false, b"a", &[], &nwk_skey());
+    unknown[1] = 0x02;
+    let events = process(&mut server, &unknown);
+    assert!(matches!(
+        &events[..],
+        [Event::Dropped(Error::UnknownDevice)]
+    ));
+}
+
+#[test]
+fn test_mac_commands() {
+    let mut server = server();
+    let mut duty_cycle = DutyCycleReqCreator::new();
+    duty_cycle.set_max_duty_cycle(3).unwrap();
+    server
+        .queue_mac_command(&[1; 8], duty_cycle.into())
+        .unwrap();
+    let link_check = LinkCheckReqCreator::new();
+    let events = process(
+        &mut server,
+        &uplink(1, false, &[], &[&link_check], &nwk_skey()),
+    );
+    match &events[..] {
+        [Event::Uplink(uplink), Event::Downlink(downlink)] => {
+            assert_eq!(uplink.f_port, None);
+            assert_eq!(
+                uplink.mac_commands,
+                vec![UplinkMacCommand::from(LinkCheckReqCreator::new())]
+            );
+            let decrypted = decrypt_downlink(downlink);
+            assert!(!decrypted.fhdr().fctrl().ack());
+            let fhdr = decrypted.fhdr();
+            let cmds: Vec<MacCommand> = fhdr.fopts().collect();
+            assert_eq!(cmds.len(), 2);
+            assert!(
+                matches!(&cmds[0], MacCommand::DutyCycleReq(req) if req.max_duty_cycle_raw() == 3)
+            );
+            match &cmds[1] {
+                // SNR 5 dB with SF9 that needs -12.5 dB
+                MacCommand::LinkCheckAns(ans) => {
+                    assert_eq!(ans.margin(), 17);
+                    assert_eq!(ans.gateway_count(), 1);
+                }
+                c => panic!("unexpected mac command {:?}", c),
+            }
+        }
+        e => panic!("unexpected events {:?}", e),
+    }
+    assert!(server.session(&[1; 8]).unwrap().mac_commands.is_empty());
+}
+
+#[test]
+fn test_queued_downlinks() {
+    let mut server = server();
+    server
+        .queue_downlink(&[1; 8], 2, b"first".to_vec())
+        .unwrap();
+    server
+        .queue_downlink(&[1; 8], 3, b"second".to_vec())
+        .unwrap();
+    assert!(matches!(
+        server.queue_downlink(&[9; 8], 2, vec![]),
+        Err(Error::UnknownDevice)
+    ));
+
+    let events = process(&mut server, &uplink(1, false, b"a", &[], &nwk_skey()));
+    match &events[..] {
+        [Event::Uplink(_), Event::Downlink(downlink)] => {
+            let decrypted = decrypt_downlink(downlink);
+            assert!(decrypted.fhdr().fctrl().f_pending());
+            assert_eq!(decrypted.f_port(), Some(2));
+            assert_eq!(decrypted.frm_payload().unwrap(), FRMPayload::Data(b"first"));
+        }
+        e => panic!("unexpected events {:?}", e),
+    }
+    let events = process(&mut server, &uplink(2, false, b"a", &[], &nwk_skey()));
+    match &events[..] {
+        [Event::Uplink(_), Event::Downlink(downlink)] => {
+            let decrypted = decrypt_downlink(downlink);
+            assert!(!decrypted.fhdr().fctrl().f_pending());
+            assert_eq!(decrypted.fhdr().fcnt(), 1);
+            assert_eq!(
+                decrypted.frm_payload().unwrap(),
+                FRMPayload::Data(b"second")
+            );
+        }
+        e => panic!("unexpected events {:?}", e),
+    }
+    let events = process(&mut server, &uplink(3, false, b"a", &[], &nwk_skey()));
+    assert_eq!(events.len(), 1);
+}
+
+#[test]
+fn test_oversized_downlink_keeps_uplink() {
+    let mut server = server();
+    let oversized = vec![0; MAX_DOWNLINK_PAYLOAD_LEN + 1];
+    assert!(matches!(
+        server.queue_downlink(&[1; 8], 2, oversized.clone()),
+        Err(Error::Encoding(EncodingError::PayloadTooLarge))
+    ));
+    server
+        .queue_downlink(&[1; 8], 2, vec![0; MAX_DOWNLINK_PAYLOAD_LEN])
+        .unwrap();
+    let events = process(&mut server, &uplink(1, false, b"a", &[], &nwk_skey()));
+    assert!(matches!(
+        &events[..],
+        [Event::Uplink(_), Event::Downlink(_)]
+    ));
+
+    // a downlink that can not be built is dropped, but the uplink is still delivered
+    let session = server.session_mut(&[1; 8]).unwrap();
+    session.downlinks.push_back((2, vec![0; 300]));
+    let events = process(&mut server, &uplink(2, true, b"a", &[], &nwk_skey()));
+    match &events[..] {
+        [Event::Uplink(uplink), Event::Dropped(Error::Encoding(EncodingError::PayloadTooLarge))] => {
+            assert_eq!(uplink.fcnt, 2);
+            assert_eq!(uplink.frm_payload, b"a".to_vec());
+        }
+        e => panic!("unexpected events {:?}", e),
+    }
+    assert!(server.session(&[1; 8]).unwrap().downlinks.is_empty());
+}
+
+#[test]
+fn test_join() {
+    let app_key = AES128([3; 16]);
+    let mut server = server();
+    server.join_server().registry_mut().insert(
+        [1; 8],
+        DeviceKeys {
+            join_eui: [4; 8],
+            app_key,
+        },
+    );
+    let mut creator = JoinRequestCreator::new();
+    creator
+        .set_app_eui(&[4; 8])
+        .set_dev_eui(&[1; 8])
+        .set_dev_nonce(&[1, 0]);
+    let events = process(&mut server, creator.build(&app_key).unwrap());
+    let activation = match &events[..] {
+        [Event::Joined(activation), Event::Downlink(downlink)] => {
+            assert_eq!(downlink.dev_eui, [1; 8]);
+            assert_eq!(downlink.rx1.data, activation.join_accept);
+            assert_eq!(downlink.rx1.tmst, Some(6_000_000));
+            assert_eq!(downlink.rx2.tmst, Some(7_000_000));
+            let join_accept = EncryptedJoinAcceptPayload::new(downlink.rx1.data.clone())
+                .unwrap()
+                .decrypt(&app_key);
+            assert!(join_accept.validate_mic(&app_key));
+            activation.clone()
+        }
+        e => panic!("unexpected events {:?}", e),
+    };
+
+    // the new session replaces the one of the personalized device
+    let session = server.session(&[1; 8]).unwrap();
+    assert_eq!(session.dev_addr, activation.dev_addr);
+    assert_eq!(session.nwk_skey, activation.nwk_skey);
+    let events = process(&mut server, &uplink(1, false, b"a", &[], &nwk_skey()));
+    assert!(matches!(
+        &events[..],
+        [Event::Dropped(Error::UnknownDevice)]
+    ));
+}
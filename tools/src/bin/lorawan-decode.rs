@@ -0,0 +1,223 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Decodes a LoRaWAN PHYPayload given as hex or base64 and prints its content.
+//!
+//! When the relevant keys are provided, the MIC is validated and the encrypted parts of the
+//! frame are decrypted before printing. The process exits with 2 if the MIC is invalid.
+
+use clap::{crate_version, App, Arg, ArgMatches};
+use lorawan_encoding::default_crypto::DefaultFactory;
+use lorawan_encoding::keys::AES128;
+use lorawan_encoding::parser::{
+    parse, DataHeader, DataPayload, FRMPayload, JoinAcceptPayload, MICAble, PhyPayload,
+};
+use lorawan_tools::{decode_base64, decode_hex, encode_hex, parse_key};
+use std::process;
+
+type Frame = PhyPayload<Vec<u8>, DefaultFactory>;
+
+struct Options {
+    app_key: Option<AES128>,
+    nwk_skey: Option<AES128>,
+    app_skey: Option<AES128>,
+    fcnt: Option<u32>,
+}
+
+fn optional_key(matches: &ArgMatches, name: &str) -> Result<Option<AES128>, String> {
+    matches
+        .value_of(name)
+        .map(|v| parse_key(v).map_err(|e| format!("{}: {}", name, e)))
+        .transpose()
+}
+
+fn read_options(matches: &ArgMatches) -> Result<Options, String> {
+    let fcnt = matches
+        .value_of("fcnt")
+        .map(|v| v.parse::<u32>().map_err(|e| format!("fcnt: {}", e)))
+        .transpose()?;
+    Ok(Options {
+        app_key: optional_key(matches, "app-key")?,
+        nwk_skey: optional_key(matches, "nwk-skey")?,
+        app_skey: optional_key(matches, "app-skey")?,
+        fcnt,
+    })
+}
+
+/// Validates the MIC and decrypts the frame as far as the provided keys allow it.
+fn check_and_decrypt(frame: Frame, options: &Options) -> Result<(Frame, Option<bool>), String> {
+    let res = match frame {
+        PhyPayload::JoinRequest(jr) => {
+            let mic_valid = options.app_key.as_ref().map(|k| jr.validate_mic(k));
+            (PhyPayload::JoinRequest(jr), mic_valid)
+        }
+        PhyPayload::JoinAccept(JoinAcceptPayload::Encrypted(ja)) => match &options.app_key {
+            Some(key) => {
+                let decrypted = ja.decrypt(key);
+                let mic_valid = decrypted.validate_mic(key);
+                (
+                    PhyPayload::JoinAccept(JoinAcceptPayload::Decrypted(decrypted)),
+                    Some(mic_valid),
+                )
+            }
+            None => (
+                PhyPayload::JoinAccept(JoinAcceptPayload::Encrypted(ja)),
+                None,
+            ),
+        },
+        PhyPayload::Data(DataPayload::Encrypted(data)) => {
+            let fcnt = options
+                .fcnt
+                .unwrap_or_else(|| u32::from(data.fhdr().fcnt()));
+            let mic_valid = options
+                .nwk_skey
+                .as_ref()
+                .map(|k| data.validate_mic(k, fcnt));
+            let key = match data.f_port() {
+                Some(port) if port != 0 => &options.app_skey,
+                _ => &options.nwk_skey,
+            };
+            if key.is_none() {
+                return Ok((PhyPayload::Data(DataPayload::Encrypted(data)), mic_valid));
+            }
+            let decrypted = data
+                .decrypt(options.nwk_skey.as_ref(), options.app_skey.as_ref(), fcnt)
+                .map_err(|e| format!("could not decrypt payload: {:?}", e))?;
+            (
+                PhyPayload::Data(DataPayload::Decrypted(decrypted)),
+                mic_valid,
+            )
+        }
+        other => (other, None),
+    };
+    Ok(res)
+}
+
+fn print_text(frame: &Frame, mic_valid: Option<bool>) {
+    println!("{}", frame);
+    let mic = match frame {
+        PhyPayload::JoinRequest(jr) => Some(jr.mic()),
+        PhyPayload::JoinAccept(JoinAcceptPayload::Encrypted(ja)) => Some(ja.mic()),
+        PhyPayload::JoinAccept(JoinAcceptPayload::Decrypted(ja)) => Some(ja.mic()),
+        PhyPayload::Data(DataPayload::Encrypted(data)) => Some(data.mic()),
+        PhyPayload::Data(DataPayload::Decrypted(data)) => Some(data.mic()),
+        _ => None,
+    };
+    if let Some(mic) = mic {
+        let status = match mic_valid {
+            Some(true) => "valid",
+            Some(false) => "invalid",
+            None => "not checked",
+        };
+        println!("MIC: {} ({})", encode_hex(&mic.0), status);
+    }
+    match frame {
+        PhyPayload::Data(DataPayload::Encrypted(data)) => {
+            let bytes = data.as_data_bytes();
+            let start = 1 + data.fhdr_length() + 1;
+            if data.f_port().is_some() && start < bytes.len() - 4 {
+                let payload = &bytes[start..bytes.len() - 4];
+                println!("FRMPayload (encrypted): {}", encode_hex(payload));
+            }
+        }
+        PhyPayload::Data(DataPayload::Decrypted(data)) => {
+            if let Ok(FRMPayload::Data(payload)) = data.frm_payload() {
+                println!("FRMPayload: {}", encode_hex(payload));
+            }
+        }
+        PhyPayload::Proprietary(p) => println!("Payload: {}", encode_hex(p.payload())),
+        _ => (),
+    }
+}
+
+fn print_json(frame: &Frame, mic_valid: Option<bool>) -> Result<(), String> {
+    let output = serde_json::json!({
+        "frame": frame,
+        "mic_valid": mic_valid,
+    });
+    let text = serde_json::to_string_pretty(&output).map_err(|e| e.to_string())?;
+    println!("{}", text);
+    Ok(())
+}
+
+fn run(matches: &ArgMatches) -> Result<Option<bool>, String> {
+    let options = read_options(matches)?;
+    let input = matches.value_of("payload").unwrap();
+    let bytes = if matches.is_present("base64") {
+        decode_base64(input)?
+    } else {
+        decode_hex(input)?
+    };
+    let frame = parse(bytes).map_err(|e| format!("could not parse payload: {:?}", e))?;
+    let (frame, mic_valid) = check_and_decrypt(frame, &options)?;
+    if matches.is_present("json") {
+        print_json(&frame, mic_valid)?;
+    } else {
+        print_text(&frame, mic_valid);
+    }
+    Ok(mic_valid)
+}
+
+fn main() {
+    let key_arg = |name, help| {
+        Arg::with_name(name)
+            .long(name)
+            .takes_value(true)
+            .value_name("HEX")
+            .help(help)
+    };
+    let matches = App::new("lorawan-decode")
+        .version(crate_version!())
+        .about("Decodes a LoRaWAN PHYPayload and optionally validates and decrypts it")
+        .arg(
+            Arg::with_name("payload")
+                .required(true)
+                .value_name("PAYLOAD")
+                .help("The PHYPayload, hex encoded unless --base64 is given"),
+        )
+        .arg(
+            Arg::with_name("base64")
+                .short("b")
+                .long("base64")
+                .help("Treat PAYLOAD as base64 instead of hex"),
+        )
+        .arg(key_arg(
+            "app-key",
+            "AppKey for validating join requests and decrypting join accepts",
+        ))
+        .arg(key_arg(
+            "nwk-skey",
+            "NwkSKey for validating data frames and decrypting MAC commands",
+        ))
+        .arg(key_arg(
+            "app-skey",
+            "AppSKey for decrypting the application payload",
+        ))
+        .arg(
+            Arg::with_name("fcnt")
+                .long("fcnt")
+                .takes_value(true)
+                .value_name("FCNT")
+                .help("The 32-bit frame counter or the last one seen, defaults to the 16 bits in the frame"),
+        )
+        .arg(
+            Arg::with_name("json")
+                .long("json")
+                .help("Print the result as JSON"),
+        )
+        .get_matches();
+
+    match run(&matches) {
+        Ok(Some(false)) => process::exit(2),
+        Ok(_) => (),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+    }
+}
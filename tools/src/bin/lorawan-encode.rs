@@ -0,0 +1,647 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Builds a LoRaWAN PHYPayload from command-line flags or a JSON description and prints it as
+//! hex or base64.
+//!
+//! EUIs, DevAddr, NetID and the nonces are given most significant byte first, the same way
+//! lorawan-decode prints them, while keys and payloads are given in transmission order.
+
+use clap::{crate_version, App, Arg, ArgMatches};
+use lorawan_encoding::creator::{DataPayloadCreator, JoinAcceptCreator, JoinRequestCreator};
+use lorawan_encoding::keys::AES128;
+use lorawan_encoding::maccommandcreator::*;
+use lorawan_encoding::maccommandqueue::{DownlinkMacCommand, UplinkMacCommand};
+use lorawan_encoding::maccommands::{parse_mac_commands, Frequency, SerializableMacCommand};
+use lorawan_encoding::parser::FCtrl;
+use lorawan_tools::{decode_hex, encode_base64, encode_hex, parse_key, parse_msb_hex};
+use serde::Deserialize;
+use std::convert::TryFrom;
+use std::fs;
+use std::io::{self, Read};
+use std::process;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum FrameType {
+    JoinRequest,
+    JoinAccept,
+    Data,
+}
+
+/// The description of the frame to build. Every field can also be given as a flag, in which
+/// case the flag takes precedence.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Description {
+    frame: Option<FrameType>,
+    app_key: Option<String>,
+    app_eui: Option<String>,
+    dev_eui: Option<String>,
+    dev_nonce: Option<String>,
+    app_nonce: Option<String>,
+    net_id: Option<String>,
+    dev_addr: Option<String>,
+    dl_settings: u8,
+    rx_delay: u8,
+    cf_list: Vec<u32>,
+    nwk_skey: Option<String>,
+    app_skey: Option<String>,
+    downlink: bool,
+    confirmed: bool,
+    adr: bool,
+    adr_ack_req: bool,
+    ack: bool,
+    f_pending: bool,
+    fcnt: u32,
+    f_port: Option<u8>,
+    payload: Option<String>,
+    mac_commands: Vec<MacCommandDescription>,
+}
+
+/// The description of a single mac command, tagged with the name of the command.
+///
+/// Commands that are not listed can be given as their raw bytes, starting with the CID.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "name", deny_unknown_fields)]
+enum MacCommandDescription {
+    LinkCheckReq,
+    LinkCheckAns {
+        margin: u8,
+        gateway_count: u8,
+    },
+    LinkADRReq {
+        data_rate: u8,
+        tx_power: u8,
+        channel_mask: u16,
+        redundancy: u8,
+    },
+    LinkADRAns {
+        #[serde(default)]
+        channel_mask_ack: bool,
+        #[serde(default)]
+        data_rate_ack: bool,
+        #[serde(default)]
+        tx_power_ack: bool,
+    },
+    DutyCycleReq {
+        max_duty_cycle: u8,
+    },
+    DutyCycleAns,
+    RXParamSetupReq {
+        dl_settings: u8,
+        frequency: u32,
+    },
+    RXParamSetupAns {
+        #[serde(default)]
+        channel_ack: bool,
+        #[serde(default)]
+        rx2_data_rate_ack: bool,
+        #[serde(default)]
+        rx1_data_rate_offset_ack: bool,
+    },
+    DevStatusReq,
+    DevStatusAns {
+        battery: u8,
+        margin: i8,
+    },
+    NewChannelReq {
+        channel_index: u8,
+        frequency: u32,
+        data_rate_range: u8,
+    },
+    NewChannelAns {
+        #[serde(default)]
+        channel_frequency_ack: bool,
+        #[serde(default)]
+        data_rate_range_ack: bool,
+    },
+    RXTimingSetupReq {
+        delay: u8,
+    },
+    RXTimingSetupAns,
+    TxParamSetupReq {
+        #[serde(default)]
+        downlink_dwell_time: bool,
+        #[serde(default)]
+        uplink_dwell_time: bool,
+        max_eirp: u8,
+    },
+    TxParamSetupAns,
+    DlChannelReq {
+        channel_index: u8,
+        frequency: u32,
+    },
+    DlChannelAns {
+        #[serde(default)]
+        channel_frequency_ack: bool,
+        #[serde(default)]
+        uplink_frequency_exists_ack: bool,
+    },
+    Raw {
+        bytes: String,
+    },
+}
+
+/// An owned mac command for either direction.
+enum OwnedMacCommand {
+    Uplink(UplinkMacCommand),
+    Downlink(DownlinkMacCommand),
+}
+
+impl OwnedMacCommand {
+    fn as_serializable(&self) -> &dyn SerializableMacCommand {
+        match self {
+            OwnedMacCommand::Uplink(c) => c,
+            OwnedMacCommand::Downlink(c) => c,
+        }
+    }
+
+    fn uplink(&self) -> bool {
+        match self {
+            OwnedMacCommand::Uplink(_) => true,
+            OwnedMacCommand::Downlink(_) => false,
+        }
+    }
+}
+
+fn up<C: Into<UplinkMacCommand>>(creator: C) -> OwnedMacCommand {
+    OwnedMacCommand::Uplink(creator.into())
+}
+
+fn down<C: Into<DownlinkMacCommand>>(creator: C) -> OwnedMacCommand {
+    OwnedMacCommand::Downlink(creator.into())
+}
+
+/// Converts a frequency in Hz to its three bytes representation in units of 100 Hz.
+fn frequency_bytes(hz: u32) -> Result<[u8; 3], String> {
+    let value = hz / 100;
+    if value * 100 != hz || value >= 1 << 24 {
+        return Err(format!("invalid frequency {}", hz));
+    }
+    let bytes = value.to_le_bytes();
+    Ok([bytes[0], bytes[1], bytes[2]])
+}
+
+fn err_str<E: std::fmt::Debug>(e: E) -> String {
+    format!("{:?}", e)
+}
+
+impl MacCommandDescription {
+    fn build(&self, uplink: bool) -> Result<Vec<OwnedMacCommand>, String> {
+        use MacCommandDescription::*;
+
+        let cmd = match self {
+            LinkCheckReq => up(LinkCheckReqCreator::new()),
+            LinkCheckAns {
+                margin,
+                gateway_count,
+            } => {
+                let mut c = LinkCheckAnsCreator::new();
+                c.set_margin(*margin).set_gateway_count(*gateway_count);
+                down(c)
+            }
+            LinkADRReq {
+                data_rate,
+                tx_power,
+                channel_mask,
+                redundancy,
+            } => {
+                let mut c = LinkADRReqCreator::new();
+                c.set_data_rate(*data_rate)
+                    .map_err(err_str)?
+                    .set_tx_power(*tx_power)
+                    .map_err(err_str)?
+                    .set_channel_mask(channel_mask.to_le_bytes())
+                    .set_redundancy(*redundancy);
+                down(c)
+            }
+            LinkADRAns {
+                channel_mask_ack,
+                data_rate_ack,
+                tx_power_ack,
+            } => {
+                let mut c = LinkADRAnsCreator::new();
+                c.set_channel_mask_ack(*channel_mask_ack)
+                    .set_data_rate_ack(*data_rate_ack)
+                    .set_tx_power_ack(*tx_power_ack);
+                up(c)
+            }
+            DutyCycleReq { max_duty_cycle } => {
+                let mut c = DutyCycleReqCreator::new();
+                c.set_max_duty_cycle(*max_duty_cycle).map_err(err_str)?;
+                down(c)
+            }
+            DutyCycleAns => up(DutyCycleAnsCreator::new()),
+            RXParamSetupReq {
+                dl_settings,
+                frequency,
+            } => {
+                let freq = frequency_bytes(*frequency)?;
+                let mut c = RXParamSetupReqCreator::new();
+                c.set_dl_settings(*dl_settings).set_frequency(&freq);
+                down(c)
+            }
+            RXParamSetupAns {
+                channel_ack,
+                rx2_data_rate_ack,
+                rx1_data_rate_offset_ack,
+            } => {
+                let mut c = RXParamSetupAnsCreator::new();
+                c.set_channel_ack(*channel_ack)
+                    .set_rx2_data_rate_ack(*rx2_data_rate_ack)
+                    .set_rx1_data_rate_offset_ack(*rx1_data_rate_offset_ack);
+                up(c)
+            }
+            DevStatusReq => down(DevStatusReqCreator::new()),
+            DevStatusAns { battery, margin } => {
+                let mut c = DevStatusAnsCreator::new();
+                c.set_battery(*battery)
+                    .set_margin(*margin)
+                    .map_err(err_str)?;
+                up(c)
+            }
+            NewChannelReq {
+                channel_index,
+                frequency,
+                data_rate_range,
+            } => {
+                let freq = frequency_bytes(*frequency)?;
+                let mut c = NewChannelReqCreator::new();
+                c.set_channel_index(*channel_index)
+                    .set_frequency(&freq)
+                    .set_data_rate_range(*data_rate_range);
+                down(c)
+            }
+            NewChannelAns {
+                channel_frequency_ack,
+                data_rate_range_ack,
+            } => {
+                let mut c = NewChannelAnsCreator::new();
+                c.set_channel_frequency_ack(*channel_frequency_ack)
+                    .set_data_rate_range_ack(*data_rate_range_ack);
+                up(c)
+            }
+            RXTimingSetupReq { delay } => {
+                let mut c = RXTimingSetupReqCreator::new();
+                c.set_delay(*delay).map_err(err_str)?;
+                down(c)
+            }
+            RXTimingSetupAns => up(RXTimingSetupAnsCreator::new()),
+            TxParamSetupReq {
+                downlink_dwell_time,
+                uplink_dwell_time,
+                max_eirp,
+            } => {
+                let mut c = TxParamSetupReqCreator::new();
+                c.set_downlink_dwell_time(*downlink_dwell_time)
+                    .set_uplink_dwell_time(*uplink_dwell_time)
+                    .set_max_eirp(*max_eirp)
+                    .map_err(err_str)?;
+                down(c)
+            }
+            TxParamSetupAns => up(TxParamSetupAnsCreator::new()),
+            DlChannelReq {
+                channel_index,
+                frequency,
+            } => {
+                let freq = frequency_bytes(*frequency)?;
+                let mut c = DlChannelReqCreator::new();
+                c.set_channel_index(*channel_index).set_frequency(&freq);
+                down(c)
+            }
+            DlChannelAns {
+                channel_frequency_ack,
+                uplink_frequency_exists_ack,
+            } => {
+                let mut c = DlChannelAnsCreator::new();
+                c.set_channel_frequency_ack(*channel_frequency_ack)
+                    .set_uplink_frequency_exists_ack(*uplink_frequency_exists_ack);
+                up(c)
+            }
+            Raw { bytes } => {
+                let bytes = decode_hex(bytes)?;
+                let mut res = Vec::new();
+                let mut parsed_len = 0;
+                for cmd in parse_mac_commands(&bytes, uplink) {
+                    parsed_len += cmd.len() + 1;
+                    res.push(if uplink {
+                        up(UplinkMacCommand::try_from(cmd).map_err(err_str)?)
+                    } else {
+                        down(DownlinkMacCommand::try_from(cmd).map_err(err_str)?)
+                    });
+                }
+                if parsed_len != bytes.len() {
+                    return Err(format!(
+                        "could not parse mac commands {}",
+                        encode_hex(&bytes)
+                    ));
+                }
+                return Ok(res);
+            }
+        };
+        if cmd.uplink() != uplink {
+            let direction = if uplink { "an uplink" } else { "a downlink" };
+            return Err(format!("{:?} can not be sent in {}", self, direction));
+        }
+        Ok(vec![cmd])
+    }
+}
+
+fn required<'a>(value: &'a Option<String>, name: &str) -> Result<&'a str, String> {
+    value
+        .as_deref()
+        .ok_or_else(|| format!("missing required value {}", name))
+}
+
+fn key(value: &Option<String>, name: &str) -> Result<AES128, String> {
+    parse_key(required(value, name)?).map_err(|e| format!("{}: {}", name, e))
+}
+
+fn msb_hex<const N: usize>(value: &Option<String>, name: &str) -> Result<[u8; N], String> {
+    parse_msb_hex(required(value, name)?).map_err(|e| format!("{}: {}", name, e))
+}
+
+fn build_join_request(desc: &Description) -> Result<Vec<u8>, String> {
+    let app_eui: [u8; 8] = msb_hex(&desc.app_eui, "app-eui")?;
+    let dev_eui: [u8; 8] = msb_hex(&desc.dev_eui, "dev-eui")?;
+    let dev_nonce: [u8; 2] = msb_hex(&desc.dev_nonce, "dev-nonce")?;
+    let mut phy = JoinRequestCreator::new();
+    phy.set_app_eui(&app_eui)
+        .set_dev_eui(&dev_eui)
+        .set_dev_nonce(&dev_nonce);
+    let key = key(&desc.app_key, "app-key")?;
+    Ok(phy.build(&key).map_err(err_str)?.to_vec())
+}
+
+fn build_join_accept(desc: &Description) -> Result<Vec<u8>, String> {
+    let app_nonce: [u8; 3] = msb_hex(&desc.app_nonce, "app-nonce")?;
+    let net_id: [u8; 3] = msb_hex(&desc.net_id, "net-id")?;
+    let dev_addr: [u8; 4] = msb_hex(&desc.dev_addr, "dev-addr")?;
+    let cf_list = desc
+        .cf_list
+        .iter()
+        .map(|f| frequency_bytes(*f))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut phy = JoinAcceptCreator::new();
+    phy.set_app_nonce(&app_nonce)
+        .set_net_id(&net_id)
+        .set_dev_addr(&dev_addr)
+        .set_dl_settings(desc.dl_settings)
+        .set_rx_delay(desc.rx_delay);
+    if !cf_list.is_empty() {
+        let freqs: Vec<Frequency> = cf_list.iter().map(Frequency::from).collect();
+        phy.set_c_f_list(freqs).map_err(err_str)?;
+    }
+    let key = key(&desc.app_key, "app-key")?;
+    Ok(phy.build(&key).map_err(err_str)?.to_vec())
+}
+
+fn build_data(desc: &Description) -> Result<Vec<u8>, String> {
+    let uplink = !desc.downlink;
+    let dev_addr: [u8; 4] = msb_hex(&desc.dev_addr, "dev-addr")?;
+    let payload = match &desc.payload {
+        Some(p) => decode_hex(p).map_err(|e| format!("payload: {}", e))?,
+        None => Vec::new(),
+    };
+    let mut cmds = Vec::new();
+    for cmd in &desc.mac_commands {
+        cmds.extend(cmd.build(uplink)?);
+    }
+    let cmds: Vec<&dyn SerializableMacCommand> = cmds.iter().map(|c| c.as_serializable()).collect();
+
+    let mut fctrl = 0;
+    for (set, bit) in [
+        (desc.adr, 0x80),
+        (desc.adr_ack_req && uplink, 0x40),
+        (desc.ack, 0x20),
+        (desc.f_pending && !uplink, 0x10),
+    ]
+    .iter()
+    {
+        if *set {
+            fctrl |= bit;
+        }
+    }
+
+    let mut phy = DataPayloadCreator::new();
+    phy.set_uplink(uplink)
+        .set_confirmed(desc.confirmed)
+        .set_dev_addr(&dev_addr)
+        .set_fctrl(&FCtrl::new(fctrl, uplink))
+        .set_fcnt(desc.fcnt);
+    if let Some(f_port) = desc.f_port {
+        phy.set_f_port(f_port);
+    }
+    let nwk_skey = key(&desc.nwk_skey, "nwk-skey")?;
+    // the AppSKey is only needed when there is application payload to encrypt
+    let app_skey = match desc.f_port {
+        Some(port) if port != 0 => key(&desc.app_skey, "app-skey")?,
+        _ => AES128([0; 16]),
+    };
+    Ok(phy
+        .build(&payload, &cmds, &nwk_skey, &app_skey)
+        .map_err(err_str)?
+        .to_vec())
+}
+
+fn read_description(matches: &ArgMatches) -> Result<Description, String> {
+    let mut desc = match matches.value_of("description") {
+        Some(path) => {
+            let text = if path == "-" {
+                let mut text = String::new();
+                io::stdin()
+                    .read_to_string(&mut text)
+                    .map_err(|e| e.to_string())?;
+                text
+            } else {
+                fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?
+            };
+            serde_json::from_str(&text).map_err(|e| format!("invalid description: {}", e))?
+        }
+        None => Description::default(),
+    };
+
+    if let Some(frame) = matches.value_of("frame") {
+        desc.frame = Some(match frame {
+            "join-request" => FrameType::JoinRequest,
+            "join-accept" => FrameType::JoinAccept,
+            _ => FrameType::Data,
+        });
+    }
+    for (name, field) in [
+        ("app-key", &mut desc.app_key),
+        ("app-eui", &mut desc.app_eui),
+        ("dev-eui", &mut desc.dev_eui),
+        ("dev-nonce", &mut desc.dev_nonce),
+        ("app-nonce", &mut desc.app_nonce),
+        ("net-id", &mut desc.net_id),
+        ("dev-addr", &mut desc.dev_addr),
+        ("nwk-skey", &mut desc.nwk_skey),
+        ("app-skey", &mut desc.app_skey),
+        ("payload", &mut desc.payload),
+    ]
+    .iter_mut()
+    {
+        if let Some(v) = matches.value_of(*name) {
+            **field = Some(v.to_string());
+        }
+    }
+    for (name, field) in [
+        ("downlink", &mut desc.downlink),
+        ("confirmed", &mut desc.confirmed),
+        ("adr", &mut desc.adr),
+        ("adr-ack-req", &mut desc.adr_ack_req),
+        ("ack", &mut desc.ack),
+        ("f-pending", &mut desc.f_pending),
+    ]
+    .iter_mut()
+    {
+        if matches.is_present(*name) {
+            **field = true;
+        }
+    }
+    let number = |name: &str| -> Result<Option<u32>, String> {
+        matches
+            .value_of(name)
+            .map(|v| v.parse::<u32>().map_err(|e| format!("{}: {}", name, e)))
+            .transpose()
+    };
+    if let Some(v) = number("fcnt")? {
+        desc.fcnt = v;
+    }
+    if let Some(v) = number("fport")? {
+        desc.f_port = Some(u8::try_from(v).map_err(|e| format!("fport: {}", e))?);
+    }
+    if let Some(v) = number("dl-settings")? {
+        desc.dl_settings = u8::try_from(v).map_err(|e| format!("dl-settings: {}", e))?;
+    }
+    if let Some(v) = number("rx-delay")? {
+        desc.rx_delay = u8::try_from(v).map_err(|e| format!("rx-delay: {}", e))?;
+    }
+    if let Some(values) = matches.values_of("cf-list") {
+        desc.cf_list = values
+            .map(|v| v.parse::<u32>().map_err(|e| format!("cf-list: {}", e)))
+            .collect::<Result<_, _>>()?;
+    }
+    if let Some(values) = matches.values_of("mac-command") {
+        for v in values {
+            desc.mac_commands
+                .push(serde_json::from_str(v).map_err(|e| format!("invalid mac command: {}", e))?);
+        }
+    }
+    Ok(desc)
+}
+
+fn run(matches: &ArgMatches) -> Result<String, String> {
+    let desc = read_description(matches)?;
+    let frame = match desc.frame {
+        Some(FrameType::JoinRequest) => build_join_request(&desc)?,
+        Some(FrameType::JoinAccept) => build_join_accept(&desc)?,
+        Some(FrameType::Data) => build_data(&desc)?,
+        None => return Err("the frame type must be given".into()),
+    };
+    if matches.is_present("base64") {
+        Ok(encode_base64(&frame))
+    } else {
+        Ok(encode_hex(&frame))
+    }
+}
+
+fn main() {
+    let value_arg = |name, value_name, help| {
+        Arg::with_name(name)
+            .long(name)
+            .takes_value(true)
+            .value_name(value_name)
+            .help(help)
+    };
+    let flag_arg = |name, help| Arg::with_name(name).long(name).help(help);
+    let matches = App::new("lorawan-encode")
+        .version(crate_version!())
+        .about("Builds a LoRaWAN PHYPayload from flags or a JSON description")
+        .arg(
+            Arg::with_name("frame")
+                .value_name("FRAME")
+                .possible_values(&["join-request", "join-accept", "data"])
+                .help("The type of frame to build, optional if given in the description"),
+        )
+        .arg(
+            value_arg(
+                "description",
+                "FILE",
+                "Read the frame description from a JSON file",
+            )
+            .short("d"),
+        )
+        .arg(flag_arg("base64", "Print the frame as base64 instead of hex").short("b"))
+        .arg(value_arg("app-key", "HEX", "AppKey for join frames"))
+        .arg(value_arg("app-eui", "HEX", "AppEUI of a join request"))
+        .arg(value_arg("dev-eui", "HEX", "DevEUI of a join request"))
+        .arg(value_arg("dev-nonce", "HEX", "DevNonce of a join request"))
+        .arg(value_arg("app-nonce", "HEX", "AppNonce of a join accept"))
+        .arg(value_arg("net-id", "HEX", "NetID of a join accept"))
+        .arg(value_arg(
+            "dev-addr",
+            "HEX",
+            "DevAddr of a join accept or data frame",
+        ))
+        .arg(value_arg(
+            "dl-settings",
+            "BYTE",
+            "DLSettings of a join accept",
+        ))
+        .arg(value_arg("rx-delay", "DELAY", "RxDelay of a join accept"))
+        .arg(
+            value_arg(
+                "cf-list",
+                "HZ",
+                "Frequencies of the CFList of a join accept",
+            )
+            .use_delimiter(true),
+        )
+        .arg(value_arg("nwk-skey", "HEX", "NwkSKey for data frames"))
+        .arg(value_arg(
+            "app-skey",
+            "HEX",
+            "AppSKey for data frames with FPort > 0",
+        ))
+        .arg(flag_arg(
+            "downlink",
+            "Build a downlink instead of an uplink",
+        ))
+        .arg(flag_arg("confirmed", "Build a confirmed data frame"))
+        .arg(flag_arg("adr", "Set the ADR bit"))
+        .arg(flag_arg(
+            "adr-ack-req",
+            "Set the ADRACKReq bit of an uplink",
+        ))
+        .arg(flag_arg("ack", "Set the ACK bit"))
+        .arg(flag_arg("f-pending", "Set the FPending bit of a downlink"))
+        .arg(value_arg("fcnt", "FCNT", "The 32-bit frame counter"))
+        .arg(value_arg("fport", "PORT", "The FPort of a data frame"))
+        .arg(value_arg("payload", "HEX", "The unencrypted FRMPayload"))
+        .arg(
+            value_arg(
+                "mac-command",
+                "JSON",
+                "A mac command, e.g. '{\"name\":\"LinkCheckReq\"}'",
+            )
+            .multiple(true)
+            .number_of_values(1),
+        )
+        .get_matches();
+
+    match run(&matches) {
+        Ok(frame) => println!("{}", frame),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+
+//! Helpers shared by the command-line tools for reading their arguments and writing their
+//! output.
+
+use lorawan_encoding::extra::{base64, hex};
+use lorawan_encoding::keys::AES128;
+
+/// Gives the hex representation of the provided bytes.
+pub fn encode_hex(data: &[u8]) -> String {
+    hex::encode(data)
+}
+
+/// Parses a hex string, ignoring whitespace and an optional "0x" prefix.
+pub fn decode_hex(input: &str) -> Result<Vec<u8>, String> {
+    let input = input.trim();
+    let input = input.strip_prefix("0x").unwrap_or(input);
+    let digits: String = input.chars().filter(|c| !c.is_whitespace()).collect();
+    hex::decode(&digits).map_err(|e| format!("{}: {}", e, input))
+}
+
+/// Gives the standard, padded base64 representation of the provided bytes.
+pub fn encode_base64(data: &[u8]) -> String {
+    base64::encode(data)
+}
+
+/// Parses a standard base64 string, with or without padding.
+pub fn decode_base64(input: &str) -> Result<Vec<u8>, String> {
+    let input = input.trim();
+    base64::decode(input).map_err(|e| format!("{}: {}", e, input))
+}
+
+/// Parses a hex string as an AES128 key.
+pub fn parse_key(input: &str) -> Result<AES128, String> {
+    let bytes = decode_hex(input)?;
+    if bytes.len() != 16 {
+        return Err(format!("key must be 16 bytes long, got {}", bytes.len()));
+    }
+    let mut key = [0; 16];
+    key.copy_from_slice(&bytes);
+    Ok(AES128(key))
+}
+
+/// Parses a hex string of exactly N bytes given most significant byte first, as EUIs and
+/// addresses are usually written, and returns the bytes in transmission order.
+pub fn parse_msb_hex<const N: usize>(input: &str) -> Result<[u8; N], String> {
+    let bytes = decode_hex(input)?;
+    if bytes.len() != N {
+        return Err(format!("expected {} bytes, got {}", N, bytes.len()));
+    }
+    let mut res = [0; N];
+    for (r, b) in res.iter_mut().zip(bytes.iter().rev()) {
+        *r = *b;
+    }
+    Ok(res)
+}
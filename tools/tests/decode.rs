@@ -0,0 +1,88 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+use std::process::{Command, Output};
+
+const DATA_UP: &str = "400403020180010001a694642615d6c3b582";
+const NWK_SKEY: &str = "02020202020202020202020202020202";
+const APP_SKEY: &str = "01010101010101010101010101010101";
+
+fn decode(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_lorawan-decode"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn test_decode_data_up_without_keys() {
+    let output = decode(&[DATA_UP]);
+    assert!(output.status.success());
+    let text = stdout(&output);
+    assert!(text.starts_with("UnconfirmedDataUp DevAddr=01020304 FCnt=1 ADR FPort=1 len=5"));
+    assert!(text.contains("MIC: d6c3b582 (not checked)"));
+    assert!(text.contains("FRMPayload (encrypted): a694642615"));
+}
+
+#[test]
+fn test_decode_data_up_with_keys() {
+    let output = decode(&[DATA_UP, "--nwk-skey", NWK_SKEY, "--app-skey", APP_SKEY]);
+    assert!(output.status.success());
+    let text = stdout(&output);
+    assert!(text.contains("MIC: d6c3b582 (valid)"));
+    assert!(text.contains("FRMPayload: 68656c6c6f"));
+}
+
+#[test]
+fn test_decode_invalid_mic_exits_with_2() {
+    let output = decode(&[DATA_UP, "--nwk-skey", APP_SKEY]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stdout(&output).contains("MIC: d6c3b582 (invalid)"));
+}
+
+#[test]
+fn test_decode_base64_join_request_as_json() {
+    let output = decode(&[
+        "-b",
+        "AAQDAgEEAwIBBQQDAgUEAwItEGqZDhI=",
+        "--app-key",
+        APP_SKEY,
+        "--json",
+    ]);
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(json["mic_valid"], serde_json::Value::Bool(true));
+    assert!(json["frame"]["join_request"].is_object());
+}
+
+#[test]
+fn test_decode_invalid_input() {
+    let output = decode(&["40zz"]);
+    assert_eq!(output.status.code(), Some(1));
+    let output = decode(&["00"]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_decode_fcnt_overflow() {
+    let output = decode(&[
+        DATA_UP,
+        "--fcnt",
+        "4294967295",
+        "--nwk-skey",
+        NWK_SKEY,
+        "--app-skey",
+        APP_SKEY,
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("FCntGapTooLarge"));
+}
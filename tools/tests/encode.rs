@@ -0,0 +1,121 @@
+// Copyright (c) 2020 Ivaylo Petrov
+//
+// Licensed under the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+// author: Ivaylo Petrov <ivajloip@gmail.com>
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+const NWK_SKEY: &str = "02020202020202020202020202020202";
+const APP_SKEY: &str = "01010101010101010101010101010101";
+
+fn encode(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_lorawan-encode"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn encode_stdin(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lorawan-encode"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn test_encode_data_up() {
+    let output = encode(&[
+        "data",
+        "--dev-addr",
+        "01020304",
+        "--fcnt",
+        "1",
+        "--fport",
+        "1",
+        "--adr",
+        "--payload",
+        "68656c6c6f",
+        "--nwk-skey",
+        NWK_SKEY,
+        "--app-skey",
+        APP_SKEY,
+    ]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "400403020180010001a694642615d6c3b582\n");
+}
+
+#[test]
+fn test_encode_join_request_base64() {
+    let output = encode(&[
+        "join-request",
+        "-b",
+        "--app-eui",
+        "0102030401020304",
+        "--dev-eui",
+        "0203040502030405",
+        "--dev-nonce",
+        "102d",
+        "--app-key",
+        APP_SKEY,
+    ]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "AAQDAgEEAwIBBQQDAgUEAwItEGqZDhI=\n");
+}
+
+#[test]
+fn test_encode_downlink_from_description() {
+    let description = r#"{
+        "frame": "data",
+        "downlink": true,
+        "dev_addr": "01020304",
+        "fcnt": 5,
+        "nwk_skey": "02020202020202020202020202020202",
+        "mac_commands": [
+            {"name": "LinkADRReq", "data_rate": 3, "tx_power": 2, "channel_mask": 255,
+             "redundancy": 1},
+            {"name": "Raw", "bytes": "06"}
+        ]
+    }"#;
+    let output = encode_stdin(&["-d", "-"], description);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "60040302010605000332ff0001065548911a\n");
+}
+
+#[test]
+fn test_encode_mac_command_in_wrong_direction() {
+    let output = encode(&[
+        "data",
+        "--dev-addr",
+        "01020304",
+        "--nwk-skey",
+        NWK_SKEY,
+        "--mac-command",
+        r#"{"name": "DevStatusReq"}"#,
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_encode_missing_values() {
+    let output = encode(&["data", "--dev-addr", "01020304"]);
+    assert_eq!(output.status.code(), Some(1));
+    let output = encode(&["--dev-addr", "01020304", "--nwk-skey", NWK_SKEY]);
+    assert_eq!(output.status.code(), Some(1));
+}